@@ -2,82 +2,261 @@
 #include<stdio.h>
 #include<string.h>
 #include<getopt.h>
+#include<errno.h>
+#include<limits.h>
+#include<stdbool.h>
 
 
-static void usage(const char *progname) {
-	printf("usage: %s [options] IN_FILE [OUT_FILE [WORD...]]\n%s", progname,
-	       "  IN_FILE\n"
-	       "        an input file for this example program\n"
-	       "  OUT_FILE\n"
-	       "        where we'll put some output\n"
-	       "  WORD\n"
-	       "        word(s) of interest\n"
-	       "  -h  --help\n"
-	       "        print this usage and exit\n"
-	       "  -b  --block-size <num>  (aliased: --blocksize --bs)\n"
-	       "        set the block size, defaults to 12.\n"
-	       "      --fav-number <num>\n"
-	       "        your favorite number\n"
-	       "  -q  --quiet\n"
-	       "        disable output\n"
-	       "      --name <arg>\n"
-	       "        your name\n"
-	       );
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options] IN_FILE [OUT_FILE [WORD...]]\n%s", progname,
+	             "  IN_FILE                 an input file for this example program\n"
+	             "  OUT_FILE                where we\'ll put some output\n"
+	             "  WORD                    word(s) of interest\n"
+	             "  -h, --help              print this usage and exit\n"
+	             "  -b, --block-size <num>  set the block size, defaults to 12. (aliased:\n"
+	             "                          --blocksize --bs)\n"
+	             "      --fav-number <num>  your favorite number\n"
+	             "  -q, --quiet             disable output\n"
+	             "      --name <STR>        your name\n"
+	             );
 }
 
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[11];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"block-size",
+		"blocksize",
+		"bs",
+		"fav-number",
+		"quiet",
+		"name",
+		"help",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static int is_digit(char c) {
+	return c >= '0' && c <= '9';
+}
+
+static int is_negative_number(const char *arg) {
+	if (arg[0] != '-' || !is_digit(arg[1])) {
+		return 0;
+	}
+	arg++;
+	while (is_digit(*arg)) {
+		arg++;
+	}
+	if (arg[0] == '.' && is_digit(arg[1])) {
+		arg++;
+		while (is_digit(*arg)) {
+			arg++;
+		}
+	}
+	return *arg == '\0';
+}
+
+static int parse_int(const char *progname, const char *name, const char *arg) {
+	char *end;
+	long value;
+	errno = 0;
+	value = strtol(arg, &end, 0);
+	if (end == arg || *end != '\0') {
+		fprintf(stderr, "%s: invalid value for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+		fprintf(stderr, "%s: value out of range for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	return (int)value;
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] block_size -b, --block-size: set the block size, defaults to
+ *   12. Defaults to 12.
+ * @param[out] fave_number --fav-number: your favorite number. Defaults to
+ *   0xBEEF.
+ * @param[out] quiet -q, --quiet: disable output. Set when given.
+ * @param[out] username --name: your name. Defaults to "John Smith".
+ * @param[out] out_file IN_FILE: an input file for this example program.
+ *   Required.
+ * @param[out] in_file OUT_FILE: where we'll put some output.
+ * @param[out] words WORD...: word(s) of interest.
+ * @param[out] words__size Number of values in words.
+ */
 void parse_args(int argc, char **argv, int *block_size, int *fave_number, int *quiet, char* *username, char* *out_file, char* *in_file, char* **words, size_t *words__size) {
-	int block_size__isset = 0;
-	int fave_number__isset = 0;
-	int username__isset = 0;
+	const char *progname = argv[0];
+	bool block_size__isset = false;
+	bool fave_number__isset = false;
+	bool quiet__isset = false;
+	bool username__isset = false;
 	static int block_size__default = 12;
-	static int fave_number__default = 0xDEADBEEF;
+	static int fave_number__default = 0xBEEF;
 	static char* username__default = "John Smith";
 	static struct option longopts[] = {
 		{"block-size", required_argument, 0, 98},
-		{"fav-number", required_argument, 0, 254},
+		{"blocksize", required_argument, 0, 98},
+		{"bs", required_argument, 0, 98},
+		{"fav-number", required_argument, 0, 256},
 		{"quiet", no_argument, 0, 113},
-		{"name", required_argument, 0, 253},
+		{"name", required_argument, 0, 257},
 		{"help", 0, 0, 'h'},
 		{0, 0, 0, 0}
 	};
+	int npos = 0;
 	int ch;
-	while ((ch = getopt_long(argc, argv, "b:qh", longopts, NULL)) != -1) {
+	int at;
+	int longindex;
+	*in_file = NULL;
+	*words = NULL;
+	*words__size = 0;
+	opterr = 0;
+	for (;;) {
+		if (optind < argc && is_negative_number(argv[optind])) {
+			argv[++npos] = argv[optind++];
+			continue;
+		}
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:b:qh", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(1);
+		}
 		switch (ch) {
 		case 98:
-			*block_size = atoi(optarg);
-			block_size__isset = 1;
+			*block_size = parse_int(progname, "--block-size", optarg);
+			block_size__isset = true;
 			break;
-		case 254:
-			*fave_number = atoi(optarg);
-			fave_number__isset = 1;
+		case 256:
+			*fave_number = parse_int(progname, "--fav-number", optarg);
+			fave_number__isset = true;
 			break;
 		case 113:
 			*quiet = 1;
+			quiet__isset = true;
 			break;
-		case 253:
+		case 257:
 			*username = optarg;
-			username__isset = 1;
+			username__isset = true;
+			break;
+		case 1:
+			argv[++npos] = optarg;
 			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(1);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(1);
 		case 0:
 			break;
 		case 'h':
+			usage(stdout, progname);
+			exit(0);
 		default:
-			usage(argv[0]);
+			usage(stderr, progname);
 			exit(1);
 		}
 	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
 	if (!block_size__isset) {
 		*block_size = block_size__default;
 	}
 	if (!fave_number__isset) {
 		*fave_number = fave_number__default;
 	}
+	if (!quiet__isset) {
+		*quiet = 0;
+	}
 	if (!username__isset) {
 		*username = username__default;
 	}
 
 	if (argc-optind < 1) {
-		usage(argv[0]);
+		usage(stderr, progname);
 		exit(1);
 	}
 	argv += optind;
@@ -97,6 +276,9 @@ void parse_args(int argc, char **argv, int *block_size, int *fave_number, int *q
 	}
 }
 
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
 int main(int argc, char **argv) {
 	int block_size;
 	int fave_number;
@@ -109,6 +291,8 @@ int main(int argc, char **argv) {
 
 	parse_args(argc, argv, &block_size, &fave_number, &quiet, &username, &out_file, &in_file, &words, &words__size);
 
+	/* ARGEN:BEGIN user-code */
 	/* call your code here */
 	return 0;
+	/* ARGEN:END */
 }
@@ -14,29 +14,345 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+mod builder;
+mod c;
+mod completion;
+mod cpp;
+mod import;
+mod init;
+mod manpage;
+mod python;
+mod regions;
+mod rust;
+mod schema;
+mod selftest;
+mod style;
+mod template;
+
+pub use builder::{Opt, Positional, SpecBuilder};
+pub use completion::Shell;
+pub use regions::{preserve_regions, RegionError};
+pub use style::{Braces, CStd, CTarget, IndentKind, Style};
+pub use template::{Template, TemplateError};
+
 use regex::Regex;
-use serde::Deserialize;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
 use std::error::Error;
 use std::fmt;
-use std::io::Write;
+use std::fs;
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Names the generated code uses itself, which c_vars would shadow.
+const RESERVED_C_VARS: [&str; 39] = [
+    "argc",
+    "argen_selftest",
+    "argv",
+    "ascii_tolower",
+    "at",
+    "bool",
+    "ch",
+    "config_add",
+    "config_check_used",
+    "config_flag",
+    "config_get",
+    "copy_string",
+    "copy_strings",
+    "errbuf",
+    "expand_response_files",
+    "false",
+    "file_config",
+    "fold_option_case",
+    "free_args",
+    "is_digit",
+    "is_negative_number",
+    "longindex",
+    "longopts",
+    "map_push",
+    "npos",
+    "optarg",
+    "opterr",
+    "optind",
+    "optopt",
+    "parse_args",
+    "parse_int",
+    "progname",
+    "read_config",
+    "reject_value",
+    "run_validator",
+    "selftest_argv",
+    "true",
+    "unknown_option",
+    "usage",
+];
+
+/// How deeply @file arguments may refer to other response files.
+const RESPONSE_FILE_DEPTH: usize = 16;
 
-const INCLUDES: [&str; 4] = ["stdlib", "stdio", "string", "getopt"];
+const HELP_PREFIX: &str = "\t       \"";
 
-const HELP_PREFIX: &str = "\t       \"  ";
+/// Width usage() output is wrapped to unless the spec sets help_width.
+const DEFAULT_HELP_WIDTH: usize = 80;
 
-/// c_quote takes a string and quotes it suitably for use in a char* literal in C.
+/// Labels longer than this get their description on the following line.
+const HELP_LABEL_MAX: usize = 24;
+
+/// Descriptions are never wrapped narrower than this.
+const HELP_DESCR_MIN: usize = 20;
+
+/// c_quote takes a string and escapes it suitably for use inside a char* or char literal in C.
+///
+/// Bytes outside of printable ASCII are written as three-digit octal escapes, which (unlike hex
+/// escapes) cannot swallow a following character. A `?` after another `?` is escaped so the
+/// output never forms a trigraph.
 fn c_quote(i: &str) -> String {
-    i.replace("\"", "\\\"").replace("\n", "\\n")
+    let mut quoted = String::with_capacity(i.len());
+    let mut prev = 0;
+    for &b in i.as_bytes() {
+        match b {
+            b'"' => quoted.push_str("\\\""),
+            b'\'' => quoted.push_str("\\'"),
+            b'\\' => quoted.push_str("\\\\"),
+            b'\n' => quoted.push_str("\\n"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\t' => quoted.push_str("\\t"),
+            b'?' if prev == b'?' => quoted.push_str("\\?"),
+            0x20..=0x7e => quoted.push(b as char),
+            _ => quoted.push_str(&format!("\\{:03o}", b)),
+        }
+        prev = b;
+    }
+    quoted
+}
+
+/// c_format_quote is c_quote for text that ends up in a printf format string.
+fn c_format_quote(i: &str) -> String {
+    c_quote(i).replace('%', "%%")
+}
+
+/// The text of a generated doc comment, made of sentences that each end in a full stop. A */ in
+/// the text would end a C comment early.
+fn doc_text(sentences: &[String]) -> String {
+    sentences
+        .iter()
+        .map(|sentence| match sentence.chars().last() {
+            Some('.') | Some('!') | Some('?') => sentence.to_owned(),
+            _ => format!("{}.", sentence),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("*/", "* /")
+}
+
+/// The call checking count values at values with a user-supplied validator, if there is one.
+/// name is how messages refer to the argument.
+fn cgen_run_validator(
+    validator: &Option<String>,
+    indent: &str,
+    name: &str,
+    count: &str,
+    values: &str,
+) -> String {
+    match validator {
+        Some(validator) => format!(
+            "{}run_validator(progname, \"{}\", {}, {}, {});\n",
+            indent,
+            c_quote(name),
+            count,
+            values,
+            validator
+        ),
+        None => String::new(),
+    }
+}
+
+/// Statements reading arg into *target with a user-supplied converter, exiting through
+/// reject_value if it fails. name is how messages refer to the argument.
+fn cgen_convert(
+    converter: &Option<String>,
+    indent: &str,
+    name: &str,
+    arg: &str,
+    target: &str,
+) -> String {
+    format!(
+        "{indent}errbuf[0] = '\\0';\n\
+         {indent}if ({}({}, {}, errbuf, sizeof(errbuf)) != 0) {{\n\
+         {indent}\treject_value(progname, \"{}\", {1}, errbuf);\n\
+         {indent}}}\n",
+        converter.as_deref().unwrap_or_default(),
+        arg,
+        target,
+        c_quote(name),
+        indent = indent
+    )
+}
+
+/// Whether name can be a C identifier. The regex is compiled once however many names there are
+/// to check.
+fn is_c_identifier(name: &str) -> bool {
+    static IDENTIFIER_RE: OnceLock<Regex> = OnceLock::new();
+    IDENTIFIER_RE
+        .get_or_init(|| Regex::new(r"^[_a-zA-Z][_a-zA-Z0-9]*$").unwrap())
+        .is_match(name)
+}
+
+/// Checks the fields that only go with custom c_types, naming the field at fault.
+fn validate_custom(
+    param: &str,
+    c_type: CType,
+    converter: &Option<String>,
+    c_decl_type: &Option<String>,
+) -> Result<(), (&'static str, ValidationError)> {
+    match (c_type, converter, c_decl_type) {
+        (CType::Custom, Some(converter), Some(c_decl_type)) => {
+            if !is_c_identifier(converter) {
+                return Err((
+                    "converter",
+                    ValidationError::InvalidConverter(param.to_owned(), converter.to_owned()),
+                ));
+            }
+            if c_decl_type.trim().is_empty() {
+                return Err((
+                    "c_decl_type",
+                    ValidationError::CustomWithoutConverter(param.to_owned()),
+                ));
+            }
+            Ok(())
+        }
+        (CType::Custom, None, _) => Err((
+            "converter",
+            ValidationError::CustomWithoutConverter(param.to_owned()),
+        )),
+        (CType::Custom, _, None) => Err((
+            "c_decl_type",
+            ValidationError::CustomWithoutConverter(param.to_owned()),
+        )),
+        (_, None, None) => Ok(()),
+        (_, None, Some(_)) => Err((
+            "c_decl_type",
+            ValidationError::ConverterNotCustom(param.to_owned()),
+        )),
+        _ => Err((
+            "converter",
+            ValidationError::ConverterNotCustom(param.to_owned()),
+        )),
+    }
+}
+
+/// Problems found checking a spec, each with the JSON pointer of the field at fault.
+type Problems = Vec<(String, ValidationError)>;
+
+/// wrap breaks text into lines of at most width characters where possible, keeping explicit
+/// newlines. Words longer than width get a line to themselves.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// One row of the help listing: the argument as typed and what it does.
+struct HelpEntry {
+    label: String,
+    descr: Option<String>,
+}
+
+/// Lays out sections of help entries in two columns lined up across all of them, wrapping
+/// descriptions to fit in width. Sections with a title start with a blank line and the title.
+fn help_sections(sections: &[(Option<&str>, &[HelpEntry])], width: usize) -> Vec<String> {
+    let label_width = sections
+        .iter()
+        .flat_map(|(_, entries)| entries.iter())
+        .map(|entry| entry.label.chars().count())
+        .filter(|&len| len <= HELP_LABEL_MAX)
+        .max()
+        .unwrap_or(0);
+    let column = 2 + label_width + 2;
+    let descr_width = width.saturating_sub(column).max(HELP_DESCR_MIN);
+    let mut lines = Vec::new();
+    for (title, entries) in sections {
+        if let Some(title) = title {
+            lines.push(String::new());
+            lines.push(format!("{}:", title));
+        }
+        for entry in entries.iter() {
+            let mut line = format!("  {}", entry.label);
+            let descr = match &entry.descr {
+                Some(descr) => wrap(descr, descr_width),
+                None => Vec::new(),
+            };
+            let mut descr = descr.into_iter();
+            if entry.label.chars().count() <= label_width {
+                if let Some(first) = descr.next() {
+                    let pad = column - line.chars().count();
+                    line.push_str(&" ".repeat(pad));
+                    line.push_str(&first);
+                }
+            }
+            lines.push(line);
+            lines.extend(descr.map(|rest| format!("{}{}", " ".repeat(column), rest)));
+        }
+    }
+    lines
+        .iter()
+        .map(|line| line.trim_end().to_owned())
+        .collect()
+}
+
+/// c_int_literal checks that a string is a C integer literal (decimal, hex, or octal, optionally
+/// signed) whose value fits in a 32-bit int, whatever its radix.
+fn c_int_literal(lit: &str) -> bool {
+    let (negative, digits) = match lit.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, lit.strip_prefix('+').unwrap_or(lit)),
+    };
+    let (radix, digits) = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        (16, hex)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (8, &digits[1..])
+    } else {
+        (10, digits)
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return false;
+    }
+    match u64::from_str_radix(digits, radix) {
+        Ok(v) if negative => v <= 1 << 31,
+        Ok(v) => v <= i32::MAX as u64,
+        Err(_) => false,
+    }
 }
 
 /// Error type for sanity checks
 #[derive(Debug)]
 pub enum ValidationError {
+    IoError(io::Error),
     TomlError(toml::de::Error),
+    JsonError(serde_json::Error),
+    YamlError(serde_yaml::Error),
     BadIdent(String, String),
     RequiredHasDefault(String),
+    InvalidDefault(String, String, CType),
     MultiNotChars(String),
     InvalidLong(String),
     InvalidShort(String, String),
@@ -44,17 +360,53 @@ pub enum ValidationError {
     FlagMustBeInt(String),
     FlagHasDefault(String),
     FlagCannotBeRequired(String),
+    RequiredCannotBeHidden(String),
+    RequiredCannotBeDeprecated(String),
+    MapNotChars(String),
+    MapCannotBeFlag(String),
+    MapHasDefault(String),
     RequiredPositionalGoesBeforeOptionPositional(String),
     MultiMustBeLast(String),
+    ArityNotMulti(String),
+    InvalidArity(String),
+    DuplicateLong(String, String),
+    DuplicateShort(String, String),
+    DuplicateCVar(String, String),
+    GroupTooSmall(String),
+    GroupWithoutConstraint(String),
+    GroupUnknownOption(String, String),
+    UnknownDependency(String, String),
+    SelfDependency(String),
+    InvalidValidator(String, String),
+    ValidatorOnFlag(String),
+    CustomWithoutConverter(String),
+    ConverterNotCustom(String),
+    InvalidConverter(String, String),
+    CustomHasDefault(String),
+    IncludeWithoutPath,
+    LocaleDirWithoutTextDomain,
+    InvalidExitCode(String, i32),
+    ConfigFileWithoutPath,
+    ConfigUnknownOption(String),
+    ConfigOptionNotPath(String),
+    SelftestNeedsValue(String),
+    SelftestNeedsPosix,
+    IncludeCycle(String),
+    InIncluded(String, Box<ValidationError>),
 }
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ValidationError::TomlError(e) => e.fmt(f),
+            ValidationError::IoError(e) => e.fmt(f),
+            ValidationError::TomlError(e) => write!(f, "toml: {}", e),
+            ValidationError::JsonError(e) => write!(f, "json: {}", e),
+            ValidationError::YamlError(e) => write!(f, "yaml: {}", e),
             ValidationError::BadIdent(param, ident) =>
                 write!(f, "in param {}: invalid c variable \"{}\"", param, ident),
             ValidationError::RequiredHasDefault(param) =>
                 write!(f, "in param {}: cannot set default value for required argument", param),
+            ValidationError::InvalidDefault(param, default, c_type) =>
+                write!(f, "in param {}: default value \"{}\" is not a valid {}", param, default, c_type),
             ValidationError::MultiNotChars(param) =>
                 write!(f, "in param {}: multi-valued argument must be of type char* (though they will be stored in char**)", param),
             ValidationError::InvalidLong(long) =>
@@ -69,64 +421,298 @@ impl fmt::Display for ValidationError {
                 write!(f, "in param {}: options that are flags cannot have default", param),
             ValidationError::FlagCannotBeRequired(param) =>
                 write!(f, "in param {}: options that are flags cannot also be required", param),
+            ValidationError::RequiredCannotBeHidden(param) =>
+                write!(f, "in param {}: required options cannot be hidden from usage", param),
+            ValidationError::RequiredCannotBeDeprecated(param) =>
+                write!(f, "in param {}: required options cannot be deprecated", param),
+            ValidationError::MapNotChars(param) =>
+                write!(f, "in param {}: map options must be of type char* (though keys and values will be stored in char**)", param),
+            ValidationError::MapCannotBeFlag(param) =>
+                write!(f, "in param {}: map options take a value, so cannot also be flags", param),
+            ValidationError::MapHasDefault(param) =>
+                write!(f, "in param {}: map options cannot have default", param),
             ValidationError::RequiredPositionalGoesBeforeOptionPositional(param) =>
                 write!(f, "in param {}: required positional argument cannot come after a non-required one", param),
             ValidationError::MultiMustBeLast(param) =>
                 write!(f, "in param {}: only the last positional argument can take multiple values", param),
+            ValidationError::ArityNotMulti(param) =>
+                write!(f, "in param {}: min_values and max_values only go with multi", param),
+            ValidationError::InvalidArity(param) =>
+                write!(f, "in param {}: max_values must be at least 1 and at least min_values", param),
+            ValidationError::DuplicateLong(param, long) =>
+                write!(f, "in param {}: option name --{} is already in use", param, long),
+            ValidationError::DuplicateShort(param, short) =>
+                write!(f, "in param {}: short name -{} is already in use", param, short),
+            ValidationError::DuplicateCVar(param, c_var) =>
+                write!(f, "in param {}: c variable \"{}\" is already in use", param, c_var),
+            ValidationError::GroupTooSmall(group) =>
+                write!(f, "in group {}: groups must contain at least two options", group),
+            ValidationError::GroupWithoutConstraint(group) =>
+                write!(f, "in group {}: groups must be exclusive, required, or both", group),
+            ValidationError::GroupUnknownOption(group, long) =>
+                write!(f, "in group {}: no option named --{}", group, long),
+            ValidationError::UnknownDependency(param, long) =>
+                write!(f, "in param {}: no option named --{}", param, long),
+            ValidationError::SelfDependency(param) =>
+                write!(f, "in param {}: option cannot require or conflict with itself", param),
+            ValidationError::InvalidValidator(param, validator) =>
+                write!(f, "in param {}: invalid validator function name \"{}\" (not an identifier, or already in use)", param, validator),
+            ValidationError::ValidatorOnFlag(param) =>
+                write!(f, "in param {}: options that are flags take no value to validate", param),
+            ValidationError::CustomWithoutConverter(param) =>
+                write!(f, "in param {}: custom c_type needs both a converter and a c_decl_type", param),
+            ValidationError::ConverterNotCustom(param) =>
+                write!(f, "in param {}: converter and c_decl_type only go with c_type custom", param),
+            ValidationError::InvalidConverter(param, converter) =>
+                write!(f, "in param {}: invalid converter function name \"{}\" (not an identifier, or already in use)", param, converter),
+            ValidationError::CustomHasDefault(param) =>
+                write!(f, "in param {}: custom c_type cannot have default, as it is only read by the converter", param),
+            ValidationError::IncludeWithoutPath =>
+                write!(f, "include can only be resolved for a spec read from a file"),
+            ValidationError::LocaleDirWithoutTextDomain =>
+                write!(f, "locale_dir needs a text_domain to bind"),
+            ValidationError::InvalidExitCode(field, code) =>
+                write!(f, "{} must be from 1 to 255, not {}", field, code),
+            ValidationError::ConfigFileWithoutPath =>
+                write!(f, "config_file needs a path, an option, or both"),
+            ValidationError::ConfigUnknownOption(long) =>
+                write!(f, "in config_file: no option named --{}", long),
+            ValidationError::ConfigOptionNotPath(long) =>
+                write!(f, "in config_file: --{} must be a char* option without a default to give the path", long),
+            ValidationError::SelftestNeedsValue(param) =>
+                write!(f, "in param {}: the self-test cannot make up a value for a required argument with a validator or custom c_type", param),
+            ValidationError::SelftestNeedsPosix =>
+                write!(f, "the self-test runs its checks with fork, which the windows target does not have"),
+            ValidationError::IncludeCycle(path) =>
+                write!(f, "{} includes itself", path),
+            ValidationError::InIncluded(path, e) =>
+                write!(f, "in included spec {}: {}", path, e),
         }
     }
 }
 impl Error for ValidationError {}
+impl From<io::Error> for ValidationError {
+    fn from(err: io::Error) -> ValidationError {
+        ValidationError::IoError(err)
+    }
+}
 impl From<toml::de::Error> for ValidationError {
     fn from(err: toml::de::Error) -> ValidationError {
         ValidationError::TomlError(err)
     }
 }
+impl From<serde_json::Error> for ValidationError {
+    fn from(err: serde_json::Error) -> ValidationError {
+        ValidationError::JsonError(err)
+    }
+}
+impl From<serde_yaml::Error> for ValidationError {
+    fn from(err: serde_yaml::Error) -> ValidationError {
+        ValidationError::YamlError(err)
+    }
+}
 
-#[derive(Clone, Copy, Deserialize)]
-enum CType {
+/// The serialization format a spec is written in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpecFormat {
+    Toml,
+    Json,
+    Yaml,
+}
+impl SpecFormat {
+    /// Guesses the format from a file extension, defaulting to toml.
+    pub fn from_path(path: &Path) -> SpecFormat {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => SpecFormat::Json,
+            Some("yaml") | Some("yml") => SpecFormat::Yaml,
+            _ => SpecFormat::Toml,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub enum CType {
     #[serde(rename = "char*")]
     Chars,
     #[serde(rename = "int")]
     Int,
+    /// Declared as the item's c_decl_type and read by its converter function.
+    #[serde(rename = "custom")]
+    Custom,
+}
+impl CType {
+    /// Whether lit can be assigned as a default value of this type.
+    fn accepts_default(self, lit: &str) -> bool {
+        match self {
+            CType::Chars => true,
+            CType::Int => c_int_literal(lit),
+            CType::Custom => false,
+        }
+    }
+    /// Statement giving c_var, a pointer to this type, the value that stands for nothing given.
+    fn cgen_zero(self, c_var: &str) -> String {
+        match self {
+            CType::Chars => format!("\t*{} = NULL;\n", c_var),
+            CType::Int => format!("\t*{} = 0;\n", c_var),
+            CType::Custom => format!("\tmemset({}, 0, sizeof(*{0}));\n", c_var),
+        }
+    }
+    /// Placeholder for a value of this type in help output.
+    fn default_metavar(self) -> &'static str {
+        match self {
+            CType::Chars => "STR",
+            CType::Int => "NUM",
+            CType::Custom => "VALUE",
+        }
+    }
+    /// A default of this type as doc comments show it, with strings in quotes.
+    fn doc_value(self, lit: &str) -> String {
+        match self {
+            CType::Chars => format!("\"{}\"", lit),
+            CType::Int | CType::Custom => lit.to_owned(),
+        }
+    }
 }
 impl fmt::Display for CType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             CType::Chars => write!(f, "char*"),
             CType::Int => write!(f, "int"),
+            CType::Custom => write!(f, "custom"),
         }
     }
 }
 
-#[derive(Deserialize)]
-struct PositionalItem {
+#[derive(Deserialize, Serialize)]
+pub struct PositionalItem {
     c_var: String,
     c_type: CType,
+    #[serde(alias = "metavar")]
     help_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     help_descr: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     required: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     //multi: c_var will be c_type*, and c_var__size will be size_t. default occupies first entry.
     multi: Option<bool>,
+    /// With multi, the fewest values it takes, 1 if required and 0 otherwise if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_values: Option<usize>,
+    /// With multi, the most values it takes, any number if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_values: Option<usize>,
+    /// Name of a user-supplied C function that checks each value given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validator: Option<String>,
+    /// Name of the user-supplied C function that reads values of a custom c_type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converter: Option<String>,
+    /// The C type that variables of a custom c_type are declared with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    c_decl_type: Option<String>,
 }
 
 impl PositionalItem {
     fn is_required(&self) -> bool {
-        self.required.unwrap_or(false)
+        self.required.unwrap_or(false) || self.min_values.unwrap_or(0) > 0
     }
     fn is_multi(&self) -> bool {
         self.multi.unwrap_or(false)
     }
+    /// How many values the item takes at least.
+    fn min_values(&self) -> usize {
+        let required = usize::from(self.required.unwrap_or(false));
+        if self.is_multi() {
+            self.min_values.unwrap_or(0).max(required)
+        } else {
+            required
+        }
+    }
+    /// How many values the item takes at most, if there is a limit.
+    fn max_values(&self) -> Option<usize> {
+        if self.is_multi() {
+            self.max_values
+        } else {
+            Some(1)
+        }
+    }
+    /// Whether the item takes a number of values besides the usual none or one, one or more.
+    fn has_arity(&self) -> bool {
+        self.min_values() > 1 || self.max_values.is_some()
+    }
+    /// The item on a usage line, with name standing for each value: a space and the name for
+    /// every value it takes, and how many brackets that leaves for the end of the line to close.
+    fn synopsis(&self, name: &str) -> (String, usize) {
+        let min = self.min_values();
+        let mut words = format!(" {}", name).repeat(min);
+        match self.max_values() {
+            Some(max) if max == min => (words, 0),
+            Some(max) if max == min + 1 => (words + &format!(" [{}", name), 1),
+            None if min > 0 => (words + "...", 0),
+            _ => {
+                words.push_str(&format!(" [{}...", name));
+                (words, 1)
+            }
+        }
+    }
     fn has_default(&self) -> bool {
         self.default.is_some()
     }
+    /// The C type c_var is declared with.
+    fn c_decl_type(&self) -> String {
+        match (&self.c_type, &self.c_decl_type) {
+            (CType::Custom, Some(c_decl_type)) => c_decl_type.to_owned(),
+            _ => self.c_type.to_string(),
+        }
+    }
+    /// What generated doc comments say about the item, from its help and metadata.
+    fn doc(&self) -> String {
+        let name = if self.is_multi() {
+            format!("{}...", self.help_name)
+        } else {
+            self.help_name.to_owned()
+        };
+        let mut sentences = vec![match &self.help_descr {
+            Some(descr) => format!("{}: {}", name, descr),
+            None => name,
+        }];
+        if self.has_arity() {
+            sentences.push(match self.max_values() {
+                Some(max) => format!("Takes {} to {} values", self.min_values(), max),
+                None => format!("Takes at least {} values", self.min_values()),
+            });
+        } else if self.is_required() {
+            sentences.push(String::from("Required"));
+        }
+        if let Some(default) = &self.default {
+            sentences.push(format!("Defaults to {}", self.c_type.doc_value(default)));
+        }
+        doc_text(&sentences)
+    }
+    /// Doxygen @param lines documenting the item's parse_args parameters.
+    fn cgen_doc_params(&self) -> Vec<String> {
+        let mut params = vec![format!("@param[out] {} {}", self.c_var, self.doc())];
+        if self.is_multi() {
+            params.push(format!(
+                "@param[out] {0}__size Number of values in {0}.",
+                self.c_var
+            ));
+        }
+        params
+    }
     /// A suitable string to go into the parse_args declaration. Starts with ',' if anything.
     fn cgen_decl_arg(&self) -> String {
         if self.is_multi() {
-            format!(", {} **{}, size_t *{1}__size", self.c_type, self.c_var)
+            format!(
+                ", {} **{}, size_t *{1}__size",
+                self.c_decl_type(),
+                self.c_var
+            )
         } else {
-            format!(", {} *{}", self.c_type, self.c_var)
+            format!(", {} *{}", self.c_decl_type(), self.c_var)
         }
     }
     /// A suitable string to go into the parse_args function call. Starts with ',' if anything.
@@ -140,15 +726,24 @@ impl PositionalItem {
     /// Declarations for the main function.
     fn cgen_main_decls(&self) -> String {
         if self.is_multi() {
-            format!("\t{} *{};\n\tsize_t {1}__size;\n", self.c_type, self.c_var)
+            format!(
+                "\t{} *{};\n\tsize_t {1}__size;\n",
+                self.c_decl_type(),
+                self.c_var
+            )
         } else {
-            format!("\t{} {};\n", self.c_type, self.c_var)
+            format!("\t{} {};\n", self.c_decl_type(), self.c_var)
         }
     }
     /// Declaration of __isset variables for the parse_args (not main) function.
-    fn cgen_isset_decl(&self) -> String {
+    fn cgen_isset_decl(&self, std: CStd) -> String {
         if self.has_default() {
-            format!("\tint {}__isset = 0;\n", self.c_var)
+            format!(
+                "\t{} {}__isset = {};\n",
+                std.bool_type(),
+                self.c_var,
+                std.false_lit()
+            )
         } else {
             String::new()
         }
@@ -160,7 +755,7 @@ impl PositionalItem {
                 let quoted = format!("\"{}\"", c_quote(default));
                 let default = match self.c_type {
                     CType::Chars => &quoted,
-                    CType::Int => default,
+                    CType::Int | CType::Custom => default,
                 };
                 format!(
                     "\tstatic {} {}__default = {};\n",
@@ -171,25 +766,91 @@ impl PositionalItem {
         }
     }
     /// Assigns value to c_var using argv[0].
-    fn cgen_assign_argv0(&self) -> String {
+    fn cgen_assign_argv0(&self, std: CStd) -> String {
         let indent = if self.is_required() { "\t" } else { "\t\t" };
         let set_isset = if self.has_default() {
-            format!("{}{}__isset = 1;\n", indent, self.c_var)
+            format!("{}{}__isset = {};\n", indent, self.c_var, std.true_lit())
         } else {
             String::new()
         };
+        let count = if self.is_multi() { "argc" } else { "1" };
+        let validate = cgen_run_validator(&self.validator, indent, &self.help_name, count, "argv");
         if self.is_multi() {
             format!(
-                "{}*{} = argv;\n{0}*{1}__size = argc;\n{}",
-                indent, self.c_var, set_isset
+                "{}{}*{} = argv;\n{1}*{2}__size = argc;\n{}",
+                validate, indent, self.c_var, set_isset
             )
         } else {
             match self.c_type {
-                CType::Chars => format!("{}*{} = argv[0];\n{}", indent, self.c_var, set_isset),
-                CType::Int => format!("{}*{} = atoi(argv[0]);\n{}", indent, self.c_var, set_isset),
+                CType::Chars => format!(
+                    "{}{}*{} = argv[0];\n{}",
+                    validate, indent, self.c_var, set_isset
+                ),
+                CType::Int => format!(
+                    "{}{}*{} = parse_int(progname, \"{}\", argv[0]);\n{}",
+                    validate,
+                    indent,
+                    self.c_var,
+                    c_quote(&self.help_name),
+                    set_isset
+                ),
+                CType::Custom => format!(
+                    "{}{}{}",
+                    validate,
+                    cgen_convert(
+                        &self.converter,
+                        indent,
+                        &self.help_name,
+                        "argv[0]",
+                        &self.c_var
+                    ),
+                    set_isset
+                ),
             }
         }
     }
+    /// Replaces the values parse_args gave c_var with copies, if they are strings.
+    fn cgen_copy(&self) -> String {
+        match self.c_type {
+            CType::Chars if self.is_multi() => {
+                format!("\t*{} = copy_strings(*{0}, *{0}__size);\n", self.c_var)
+            }
+            CType::Chars => format!("\t*{} = copy_string(*{0});\n", self.c_var),
+            CType::Int | CType::Custom => String::new(),
+        }
+    }
+    /// The free_args parameters for the item's copies, starting with ", ", and the statements
+    /// freeing them.
+    fn cgen_free(&self, std: CStd) -> (String, String) {
+        match self.c_type {
+            CType::Chars if self.is_multi() => (
+                format!(", char **{}, size_t {0}__size", self.c_var),
+                format!(
+                    "\tfor ({}; i < {}__size; i++) {{\n\
+                     \t\tfree({1}[i]);\n\
+                     \t}}\n\
+                     \tfree({1});\n",
+                    std.for_init("size_t", "i", "0"),
+                    self.c_var
+                ),
+            ),
+            CType::Chars => (
+                format!(", char *{}", self.c_var),
+                format!("\tfree({});\n", self.c_var),
+            ),
+            CType::Int | CType::Custom => (String::new(), String::new()),
+        }
+    }
+    /// Zeroes c_var before parsing if it could otherwise be left unassigned.
+    fn cgen_zero(&self) -> String {
+        if self.is_required() || self.has_default() {
+            String::new()
+        } else if self.is_multi() {
+            format!("\t*{} = NULL;\n\t*{0}__size = 0;\n", self.c_var)
+        } else {
+            self.c_type.cgen_zero(&self.c_var)
+        }
+    }
     /// Performs checks and conditional assignments after the parse loop.
     fn cgen_post_loop(&self) -> String {
         if self.has_default() {
@@ -206,58 +867,197 @@ impl PositionalItem {
             String::new()
         }
     }
-    /// Error if self is invalid.
-    fn validate(&self) -> Result<(), ValidationError> {
-        let identifier_re = Regex::new(r"^[_a-zA-Z][_a-zA-Z0-9]*$").unwrap();
-        if !identifier_re.is_match(&self.c_var) {
-            return Err(ValidationError::BadIdent(
-                self.help_name.to_owned(),
-                self.c_var.to_owned(),
-            ));
+    /// Adds what is wrong with self to problems, at the JSON pointer at.
+    fn check(&self, at: &str, problems: &mut Problems) {
+        let mut report = |field: &str, e| problems.push((format!("{}/{}", at, field), e));
+        if !is_c_identifier(&self.c_var) {
+            report(
+                "c_var",
+                ValidationError::BadIdent(self.help_name.to_owned(), self.c_var.to_owned()),
+            );
         }
         if self.is_required() && self.has_default() {
-            return Err(ValidationError::RequiredHasDefault(
-                self.help_name.to_owned(),
-            ));
+            report(
+                "default",
+                ValidationError::RequiredHasDefault(self.help_name.to_owned()),
+            );
         }
-        if self.is_multi() {
-            if let CType::Int = self.c_type {
-                return Err(ValidationError::MultiNotChars(self.help_name.to_owned()));
+        if let Err((field, e)) = validate_custom(
+            &self.help_name,
+            self.c_type,
+            &self.converter,
+            &self.c_decl_type,
+        ) {
+            report(field, e);
+        }
+        if self.is_multi() && !matches!(self.c_type, CType::Chars) {
+            report(
+                "c_type",
+                ValidationError::MultiNotChars(self.help_name.to_owned()),
+            );
+        }
+        if !self.is_multi() && (self.min_values.is_some() || self.max_values.is_some()) {
+            report(
+                "multi",
+                ValidationError::ArityNotMulti(self.help_name.to_owned()),
+            );
+        } else if let Some(max) = self.max_values {
+            if max == 0 || max < self.min_values() {
+                report(
+                    "max_values",
+                    ValidationError::InvalidArity(self.help_name.to_owned()),
+                );
+            }
+        }
+        if self.has_default() && matches!(self.c_type, CType::Custom) {
+            report(
+                "default",
+                ValidationError::CustomHasDefault(self.help_name.to_owned()),
+            );
+        } else if let Some(default) = &self.default {
+            if !self.c_type.accepts_default(default) {
+                report(
+                    "default",
+                    ValidationError::InvalidDefault(
+                        self.help_name.to_owned(),
+                        default.to_owned(),
+                        self.c_type,
+                    ),
+                );
             }
         }
-        Ok(())
     }
-    fn help(&self) -> String {
-        if let Some(d) = &self.help_descr {
-            format!(
-                "{}{}\\n\"\n{0}      {}\\n\"\n",
-                HELP_PREFIX,
-                self.help_name,
-                &c_quote(&d)
-            )
-        } else {
-            format!("{}{}\\n\"\n", HELP_PREFIX, self.help_name)
+    /// Entry for the usage function's help listing.
+    fn help(&self) -> HelpEntry {
+        HelpEntry {
+            label: self.help_name.to_owned(),
+            descr: self.help_descr.to_owned(),
+        }
+    }
+}
+
+/// Another long name for an option: just the name, or a table with the name that can mark it
+/// deprecated, as for a spelling kept so old command lines still work.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum Alias {
+    Name(String),
+    Table {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        deprecated: Option<bool>,
+    },
+}
+
+impl Alias {
+    fn name(&self) -> &String {
+        match self {
+            Alias::Name(name) | Alias::Table { name, .. } => name,
+        }
+    }
+    fn is_deprecated(&self) -> bool {
+        match self {
+            Alias::Name(_) => false,
+            Alias::Table { deprecated, .. } => deprecated.unwrap_or(false),
         }
     }
 }
 
-#[derive(Deserialize)]
-struct NonPositionalItem {
+#[derive(Deserialize, Serialize)]
+pub struct NonPositionalItem {
     c_var: String,
     c_type: CType,
     long: String,
+    #[serde(alias = "metavar")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     help_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     help_descr: Option<String>,
-    aliases: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aliases: Option<Vec<Alias>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     short: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     required: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     default: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     flag: Option<bool>,
+    /// Flag whose c_var counts how many times it was given, as in -vvv.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<bool>,
+    /// Repeatable NAME=VALUE option, split into c_var__keys and c_var__values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    map: Option<bool>,
+    /// Long names of options that must also be given when this one is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    requires: Option<Vec<String>>,
+    /// Long names of options that cannot be given along with this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    conflicts_with: Option<Vec<String>>,
+    /// Name of a user-supplied C function that checks each value given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validator: Option<String>,
+    /// Name of the user-supplied C function that reads values of a custom c_type.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converter: Option<String>,
+    /// The C type that variables of a custom c_type are declared with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    c_decl_type: Option<String>,
+    /// Left out of usage, completions and the man page, but still parsed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden: Option<bool>,
+    /// Still parsed, but giving it prints this message to stderr as a warning.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecated: Option<String>,
+    /// Long names match whatever their case, as in --Verbose for --verbose. Defaults to the
+    /// spec's case_insensitive.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    case_insensitive: Option<bool>,
+    /// Title of the help section the option is listed in, apart from the ungrouped options.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
 }
 
 impl NonPositionalItem {
+    /// Whether the option takes no argument. Counted options are flags too.
     fn is_flag(&self) -> bool {
-        self.flag.unwrap_or(false)
+        self.flag.unwrap_or(false) || self.is_count()
+    }
+    fn is_count(&self) -> bool {
+        self.count.unwrap_or(false)
+    }
+    fn is_map(&self) -> bool {
+        self.map.unwrap_or(false)
+    }
+    /// Whether the option can give the path of a config file.
+    fn takes_path(&self) -> bool {
+        matches!(self.c_type, CType::Chars) && !self.is_map() && !self.has_default()
+    }
+    /// Whether a config file can give the option a value. Maps and counts only come from the
+    /// command line.
+    fn is_configurable(&self) -> bool {
+        !self.is_map() && !self.is_count()
+    }
+    /// Variables the option is stored in, as declared in main.
+    fn c_vars(&self) -> Vec<String> {
+        if self.is_map() {
+            vec![
+                format!("{}__keys", self.c_var),
+                format!("{}__values", self.c_var),
+                format!("{}__size", self.c_var),
+            ]
+        } else {
+            vec![self.c_var.to_owned()]
+        }
+    }
+    /// Placeholder for the option's value in help output.
+    fn metavar(&self) -> &str {
+        match &self.help_name {
+            Some(help_name) => help_name,
+            None if self.is_map() => "NAME=VALUE",
+            None => self.c_type.default_metavar(),
+        }
     }
     fn is_required(&self) -> bool {
         self.required.unwrap_or(false)
@@ -265,24 +1065,116 @@ impl NonPositionalItem {
     fn has_default(&self) -> bool {
         self.default.is_some()
     }
+    fn is_hidden(&self) -> bool {
+        self.hidden.unwrap_or(false)
+    }
+    /// The help description, marked if the option is deprecated.
+    fn descr(&self) -> Option<String> {
+        match (&self.help_descr, &self.deprecated) {
+            (Some(descr), Some(_)) => Some(format!("{} (deprecated)", descr)),
+            (None, Some(_)) => Some(String::from("(deprecated)")),
+            (descr, None) => descr.to_owned(),
+        }
+    }
+    /// What the generated parsers print to stderr when the option is given, if deprecated.
+    fn deprecation_warning(&self) -> Option<String> {
+        self.deprecated
+            .as_ref()
+            .map(|msg| format!("warning: --{} is deprecated: {}", self.long, msg))
+    }
+    /// What the generated parsers print to stderr when a deprecated alias is given.
+    fn alias_warning(&self, alias: &str) -> String {
+        format!("warning: --{} is deprecated, use --{}", alias, self.long)
+    }
+    /// The C type c_var is declared with.
+    fn c_decl_type(&self) -> String {
+        match (&self.c_type, &self.c_decl_type) {
+            (CType::Custom, Some(c_decl_type)) => c_decl_type.to_owned(),
+            _ => self.c_type.to_string(),
+        }
+    }
+    /// What generated doc comments say about the item, from its help and metadata.
+    fn doc(&self) -> String {
+        // hidden options keep their names out of the generated code
+        let name = match &self.short {
+            _ if self.is_hidden() => String::from("Hidden option"),
+            Some(short) => format!("-{}, --{}", short, self.long),
+            None => format!("--{}", self.long),
+        };
+        let mut sentences = vec![match &self.help_descr {
+            Some(descr) => format!("{}: {}", name, descr),
+            None => name,
+        }];
+        if self.is_count() {
+            sentences.push(String::from("Counts the times it is given"));
+        } else if self.is_flag() {
+            sentences.push(String::from("Set when given"));
+        }
+        if self.is_required() {
+            sentences.push(String::from("Required"));
+        }
+        if let Some(default) = &self.default {
+            sentences.push(format!("Defaults to {}", self.c_type.doc_value(default)));
+        }
+        if self.deprecated.is_some() {
+            sentences.push(String::from("Deprecated"));
+        }
+        doc_text(&sentences)
+    }
+    /// Doxygen @param lines documenting the item's parse_args parameters.
+    fn cgen_doc_params(&self) -> Vec<String> {
+        let mut params = vec![format!("@param[out] {} {}", self.c_vars()[0], self.doc())];
+        if self.is_map() {
+            params.push(format!(
+                "@param[out] {0}__values Values paired with {0}__keys.",
+                self.c_var
+            ));
+            params.push(format!("@param[out] {}__size Number of pairs.", self.c_var));
+        }
+        params
+    }
     /// A suitable string to go into the parse_args declaration. Starts with ',' if anything.
     fn cgen_decl_arg(&self) -> String {
-        format!(", {} *{}", self.c_type, self.c_var)
+        if self.is_map() {
+            format!(
+                ", {} **{}__keys, {0} **{1}__values, size_t *{1}__size",
+                self.c_type, self.c_var
+            )
+        } else {
+            format!(", {} *{}", self.c_decl_type(), self.c_var)
+        }
     }
     /// A suitable string to go into the parse_args function call. Starts with ',' if anything.
     fn cgen_call_arg(&self) -> String {
-        format!(", &{}", self.c_var)
+        self.c_vars().iter().map(|v| format!(", &{}", v)).collect()
     }
     /// Declarations for the main function.
     fn cgen_main_decl(&self) -> String {
-        format!("\t{} {};\n", self.c_type, self.c_var)
+        if self.is_map() {
+            format!(
+                "\t{} *{}__keys;\n\t{0} *{1}__values;\n\tsize_t {1}__size;\n",
+                self.c_type, self.c_var
+            )
+        } else {
+            format!("\t{} {};\n", self.c_decl_type(), self.c_var)
+        }
     }
-    /// Declaration of __isset variables for the parse_args (not main) function.
-    fn cgen_isset_decl(&self) -> String {
-        if !self.is_flag() {
-            format!("\tint {}__isset = 0;\n", self.c_var)
+    /// Declaration of __isset variables for the parse_args (not main) function, if tracked.
+    fn cgen_isset_decl(&self, std: CStd, tracked: bool) -> String {
+        let isset = if tracked {
+            format!(
+                "\t{} {}__isset = {};\n",
+                std.bool_type(),
+                self.c_var,
+                std.false_lit()
+            )
         } else {
             String::new()
+        };
+        if self.is_map() {
+            format!("{}\tsize_t {}__cap = 0;\n", isset, self.c_var)
+        } else {
+            isset
         }
     }
     /// Definition of __default variables for the parse_args (not main) function.
@@ -292,7 +1184,7 @@ impl NonPositionalItem {
                 let quoted = format!("\"{}\"", c_quote(default));
                 let default = match self.c_type {
                     CType::Chars => &quoted,
-                    CType::Int => default,
+                    CType::Int | CType::Custom => default,
                 };
                 format!(
                     "\tstatic {} {}__default = {};\n",
@@ -302,38 +1194,144 @@ impl NonPositionalItem {
             _ => String::new(),
         }
     }
-    /// Assigns value to the c_var in parse loop.
-    fn cgen_assign_optarg(&self) -> String {
-        if self.is_flag() {
-            format!("\t\t\t*{} = 1;\n", self.c_var)
+    /// Assigns value to the c_var in parse loop, noting that it was given if tracked.
+    fn cgen_assign_optarg(&self, std: CStd, tracked: bool) -> String {
+        let set_isset = if tracked {
+            format!("\t\t\t{}__isset = {};\n", self.c_var, std.true_lit())
+        } else {
+            String::new()
+        };
+        let name = format!("--{}", self.long);
+        let validate = cgen_run_validator(&self.validator, "\t\t\t", &name, "1", "&optarg");
+        let assign = if self.is_map() {
+            format!(
+                "\t\t\tmap_push(progname, \"{}\", optarg, {}__keys, {1}__values, {1}__size, &{1}__cap);\n{}",
+                c_quote(&self.long),
+                self.c_var,
+                set_isset
+            )
+        } else if self.is_count() {
+            format!(
+                "\t\t\t*{} = {}__isset ? *{0} + 1 : 1;\n{}",
+                self.c_var, self.c_var, set_isset
+            )
+        } else if self.is_flag() {
+            format!("\t\t\t*{} = 1;\n{}", self.c_var, set_isset)
         } else {
-            let set_isset = format!("\t\t\t{}__isset = 1;\n", self.c_var);
             match self.c_type {
                 CType::Chars => format!("\t\t\t*{} = optarg;\n{}", self.c_var, set_isset),
-                CType::Int => format!("\t\t\t*{} = atoi(optarg);\n{}", self.c_var, set_isset),
+                CType::Int => format!(
+                    "\t\t\t*{} = parse_int(progname, \"--{}\", optarg);\n{}",
+                    self.c_var,
+                    c_quote(&self.long),
+                    set_isset
+                ),
+                CType::Custom => format!(
+                    "{}{}",
+                    cgen_convert(&self.converter, "\t\t\t", &name, "optarg", &self.c_var),
+                    set_isset
+                ),
             }
-        }
+        };
+        validate + &assign
     }
-    /// Long option as per getopt_long(3).
-    fn cgen_getopt(&self, uniq: u8) -> String {
-        format!(
-            "\t\t{{\"{}\", {}, 0, {}}},\n",
-            self.long,
-            if self.is_flag() {
-                "no_argument"
-            } else {
-                "required_argument"
-            },
-            uniq
+    /// The long name followed by any aliases.
+    fn long_names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.long).chain(self.aliases.iter().flatten().map(Alias::name))
+    }
+    /// The long name followed by the aliases that are not deprecated.
+    fn current_long_names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.long).chain(
+            self.aliases
+                .iter()
+                .flatten()
+                .filter(|alias| !alias.is_deprecated())
+                .map(Alias::name),
         )
     }
-    /// Performs checks and conditional assignments after the parse loop.
-    fn cgen_post_loop(&self) -> String {
+    fn deprecated_aliases(&self) -> impl Iterator<Item = &String> {
+        self.aliases
+            .iter()
+            .flatten()
+            .filter(|alias| alias.is_deprecated())
+            .map(Alias::name)
+    }
+    /// Long options as per getopt_long(3), one for the name and each alias. The name and current
+    /// aliases have the first of uniqs, and deprecated aliases each have one of the rest.
+    fn cgen_getopt(&self, uniqs: &[i32]) -> String {
+        let has_arg = if self.is_flag() {
+            "no_argument"
+        } else {
+            "required_argument"
+        };
+        self.current_long_names()
+            .map(|long| (long, uniqs[0]))
+            .chain(self.deprecated_aliases().zip(uniqs[1..].iter().copied()))
+            .map(|(long, uniq)| {
+                format!("\t\t{{\"{}\", {}, 0, {}}},\n", c_quote(long), has_arg, uniq)
+            })
+            .collect()
+    }
+    /// Replaces the value parse_args gave c_var with a copy, if it is a string. Maps copy their
+    /// pairs as they are given.
+    fn cgen_copy(&self) -> String {
+        match self.c_type {
+            CType::Chars if !self.is_map() => {
+                format!("\t*{} = copy_string(*{0});\n", self.c_var)
+            }
+            _ => String::new(),
+        }
+    }
+    /// The free_args parameters for the option's copies, starting with ", ", and the
+    /// statements freeing them. A map's keys and values share one copy of each pair.
+    fn cgen_free(&self, std: CStd) -> (String, String) {
+        match self.c_type {
+            CType::Chars if self.is_map() => (
+                format!(
+                    ", char **{}__keys, char **{0}__values, size_t {0}__size",
+                    self.c_var
+                ),
+                format!(
+                    "\tfor ({}; i < {}__size; i++) {{\n\
+                     \t\tfree({1}__keys[i]);\n\
+                     \t}}\n\
+                     \tfree({1}__keys);\n\
+                     \tfree({1}__values);\n",
+                    std.for_init("size_t", "i", "0"),
+                    self.c_var
+                ),
+            ),
+            CType::Chars => (
+                format!(", char *{}", self.c_var),
+                format!("\tfree({});\n", self.c_var),
+            ),
+            _ => (String::new(), String::new()),
+        }
+    }
+    /// Zeroes c_var before parsing if it could otherwise be left unassigned.
+    fn cgen_zero(&self) -> String {
+        if self.is_required() || self.has_default() || self.is_flag() || self.is_map() {
+            String::new()
+        } else {
+            self.c_type.cgen_zero(&self.c_var)
+        }
+    }
+    /// Performs checks and conditional assignments after the parse loop, running usage_exit if
+    /// a required item was not given.
+    fn cgen_post_loop(&self, usage_exit: &str) -> String {
         if self.is_required() {
+            format!("\tif (!{}__isset) {{\n{}\t}}\n", self.c_var, usage_exit)
+        } else if self.is_map() {
             format!(
-                "\tif (!{}__isset) {{\n\t\tusage(argv[0]);\n\t\texit(1);\n\t}}\n",
+                "\tif (!{}__isset) {{\n\
+                 \t\t*{0}__keys = NULL;\n\
+                 \t\t*{0}__values = NULL;\n\
+                 \t\t*{0}__size = 0;\n\
+                 \t}}\n",
                 self.c_var
             )
+        } else if self.is_flag() {
+            format!("\tif (!{}__isset) {{\n\t\t*{0} = 0;\n\t}}\n", self.c_var)
         } else if self.default.is_none() {
             String::new()
         } else {
@@ -343,390 +1341,2348 @@ impl NonPositionalItem {
             )
         }
     }
-    /// Error if self is invalid.
-    fn validate(&self) -> Result<(), ValidationError> {
-        let identifier_re = Regex::new(r"^[_a-zA-Z][_a-zA-Z0-9]*$").unwrap();
-        if !identifier_re.is_match(&self.c_var) {
-            return Err(ValidationError::BadIdent(
-                self.long.to_owned(),
-                self.c_var.to_owned(),
-            ));
+    /// Errors after the parse loop through usage_exit if other is not also given.
+    fn cgen_requires(&self, other: &NonPositionalItem, usage_exit: &str) -> String {
+        format!(
+            "\tif ({}__isset && !{}__isset) {{\n\
+             \t\tfprintf(stderr, \"%s: option --{} requires --{}\\n\", progname);\n\
+             {}\t}}\n",
+            self.c_var,
+            other.c_var,
+            c_format_quote(&self.long),
+            c_format_quote(&other.long),
+            usage_exit
+        )
+    }
+    /// Errors after the parse loop through usage_exit if other is also given.
+    fn cgen_conflicts(&self, other: &NonPositionalItem, usage_exit: &str) -> String {
+        format!(
+            "\tif ({}__isset && {}__isset) {{\n\
+             \t\tfprintf(stderr, \"%s: option --{} conflicts with --{}\\n\", progname);\n\
+             {}\t}}\n",
+            self.c_var,
+            other.c_var,
+            c_format_quote(&self.long),
+            c_format_quote(&other.long),
+            usage_exit
+        )
+    }
+    /// Adds what is wrong with self to problems, at the JSON pointer at.
+    fn check(&self, at: &str, problems: &mut Problems) {
+        let mut report = |field: &str, e| problems.push((format!("{}/{}", at, field), e));
+        let long = || self.long.to_owned();
+        if !is_c_identifier(&self.c_var) {
+            report(
+                "c_var",
+                ValidationError::BadIdent(long(), self.c_var.to_owned()),
+            );
         }
         if self.long.find(' ').is_some() {
-            return Err(ValidationError::InvalidLong(self.long.to_owned()));
+            report("long", ValidationError::InvalidLong(long()));
+        }
+        if let Err((field, e)) =
+            validate_custom(&self.long, self.c_type, &self.converter, &self.c_decl_type)
+        {
+            report(field, e);
         }
         if self.is_flag() {
-            if let CType::Chars = self.c_type {
-                return Err(ValidationError::FlagMustBeInt(self.long.to_owned()));
+            if !matches!(self.c_type, CType::Int) {
+                report("c_type", ValidationError::FlagMustBeInt(long()));
             }
             if self.has_default() {
-                return Err(ValidationError::FlagHasDefault(self.long.to_owned()));
+                report("default", ValidationError::FlagHasDefault(long()));
             }
             if self.is_required() {
-                return Err(ValidationError::FlagCannotBeRequired(self.long.to_owned()));
+                report("required", ValidationError::FlagCannotBeRequired(long()));
+            }
+            if self.validator.is_some() {
+                report("validator", ValidationError::ValidatorOnFlag(long()));
+            }
+        }
+        if self.is_map() {
+            if !matches!(self.c_type, CType::Chars) {
+                report("c_type", ValidationError::MapNotChars(long()));
+            }
+            if self.is_flag() {
+                report("map", ValidationError::MapCannotBeFlag(long()));
+            }
+            if self.has_default() {
+                report("default", ValidationError::MapHasDefault(long()));
             }
         }
         if self.has_default() && self.is_required() {
-            return Err(ValidationError::RequiredHasDefault(self.long.to_owned()));
+            report("default", ValidationError::RequiredHasDefault(long()));
         }
-        if let Some(short_name) = &self.short {
-            if short_name.len() != 1 {
-                return Err(ValidationError::InvalidShort(
-                    self.long.to_owned(),
-                    short_name.to_owned(),
-                ));
-            }
+        if self.is_required() && self.is_hidden() {
+            report("hidden", ValidationError::RequiredCannotBeHidden(long()));
         }
-        if let Some(aliases) = &self.aliases {
-            for alias in aliases {
-                if alias.find(' ').is_some() {
-                    return Err(ValidationError::InvalidAlias(
-                        self.long.to_owned(),
-                        alias.to_owned(),
-                    ));
-                }
-            }
+        if self.is_required() && self.deprecated.is_some() {
+            report(
+                "deprecated",
+                ValidationError::RequiredCannotBeDeprecated(long()),
+            );
         }
-        Ok(())
-    }
-    fn help(&self) -> String {
-        let mut long = String::from("  --");
-        long.push_str(&self.long);
-        if !self.is_flag() {
-            if let Some(help_name) = &self.help_name {
-                long.push_str(&format!(" <{}>", help_name));
-            } else {
-                long.push_str(" <arg>")
+        if self.has_default() && matches!(self.c_type, CType::Custom) {
+            report("default", ValidationError::CustomHasDefault(long()));
+        } else if let Some(default) = &self.default {
+            if !self.c_type.accepts_default(default) {
+                report(
+                    "default",
+                    ValidationError::InvalidDefault(long(), default.to_owned(), self.c_type),
+                );
             }
         }
-        if let Some(aliases) = &self.aliases {
-            long.push_str("  (aliased:");
-            for alias in aliases {
-                long.push_str(" --");
-                long.push_str(alias);
+        if let Some(short_name) = &self.short {
+            if short_name.len() != 1 {
+                report(
+                    "short",
+                    ValidationError::InvalidShort(long(), short_name.to_owned()),
+                );
             }
-            long.push_str(")");
         }
-        let descr = match &self.help_descr {
-            Some(h) => {
-                let mut hm = String::from("\\n\"\n\t       \"        ");
-                hm.push_str(&c_quote(&h));
-                hm
+        for (i, alias) in self.aliases.iter().flatten().map(Alias::name).enumerate() {
+            if alias.find(' ').is_some() {
+                report(
+                    &format!("aliases/{}", i),
+                    ValidationError::InvalidAlias(long(), alias.to_owned()),
+                );
             }
-            _ => String::new(),
-        };
-        if let Some(short) = &self.short {
-            format!("{}-{}{}{}\\n\"\n", HELP_PREFIX, short, long, descr)
-        } else {
-            format!("{}  {}{}\\n\"\n", HELP_PREFIX, long, descr)
         }
     }
-}
-
-#[derive(Deserialize)]
-pub struct Spec {
-    /// Positional must be ordered: required, then optional.
+    /// Entries for the usage function's help listing: the option's, then one for each
+    /// deprecated alias saying which option it spells.
+    fn help(&self) -> Vec<HelpEntry> {
+        let value = if self.is_flag() {
+            String::new()
+        } else {
+            format!(" <{}>", self.metavar())
+        };
+        let label = match &self.short {
+            Some(short) => format!("-{}, --{}{}", short, self.long, value),
+            None => format!("    --{}{}", self.long, value),
+        };
+        let mut descr = self.descr();
+        let aliases: Vec<String> = self
+            .current_long_names()
+            .skip(1)
+            .map(|alias| format!(" --{}", alias))
+            .collect();
+        if !aliases.is_empty() {
+            let aliased = format!("(aliased:{})", aliases.concat());
+            descr = Some(match descr {
+                Some(d) => format!("{} {}", d, aliased),
+                None => aliased,
+            });
+        }
+        let mut entries = vec![HelpEntry { label, descr }];
+        entries.extend(self.deprecated_aliases().map(|alias| HelpEntry {
+            label: format!("    --{}{}", alias, value),
+            descr: Some(format!("(alias of --{}, deprecated)", self.long)),
+        }));
+        entries
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Group {
+    /// Long names of the non-positional options in the group.
+    options: Vec<String>,
+    /// At most one of the options may be given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exclusive: Option<bool>,
+    /// At least one of the options must be given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    required: Option<bool>,
+}
+
+/// Where generated C reads values for options not given on the command line.
+#[derive(Deserialize, Serialize)]
+pub struct ConfigFile {
+    /// File read if it exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    /// Long name of a char* option naming a file to read instead, which must exist.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    option: Option<String>,
+}
+
+impl Group {
+    fn is_exclusive(&self) -> bool {
+        self.exclusive.unwrap_or(false)
+    }
+    fn is_required(&self) -> bool {
+        self.required.unwrap_or(false)
+    }
+    /// Name for the group in error messages.
+    fn name(&self) -> String {
+        self.options.join(", ")
+    }
+    /// Enforces the group after the parse loop, given the c_var of each option, running
+    /// usage_exit if it is broken.
+    fn cgen_post_loop(&self, c_vars: &[&str], usage_exit: &str) -> String {
+        let count = c_vars
+            .iter()
+            .map(|c_var| format!("{}__isset", c_var))
+            .collect::<Vec<_>>()
+            .join(" + ");
+        let mut post = String::new();
+        if self.is_exclusive() {
+            post.push_str(&format!(
+                "\tif ({} > 1) {{\n\
+                 \t\tfprintf(stderr, \"%s: mutually exclusive options given:\", progname);\n",
+                count
+            ));
+            for (long, c_var) in self.options.iter().zip(c_vars) {
+                post.push_str(&format!(
+                    "\t\tif ({}__isset) {{\n\
+                     \t\t\tfprintf(stderr, \" --{}\");\n\
+                     \t\t}}\n",
+                    c_var,
+                    c_format_quote(long)
+                ));
+            }
+            post.push_str("\t\tfprintf(stderr, \"\\n\");\n");
+            post.push_str(usage_exit);
+            post.push_str("\t}\n");
+        }
+        if self.is_required() {
+            let names = self
+                .options
+                .iter()
+                .map(|long| format!("--{}", long))
+                .collect::<Vec<_>>()
+                .join(", ");
+            post.push_str(&format!(
+                "\tif ({} < 1) {{\n\
+                 \t\tfprintf(stderr, \"%s: one of these options is required: {}\\n\", progname);\n\
+                 {}\
+                 \t}}\n",
+                count,
+                c_format_quote(&names),
+                usage_exit
+            ));
+        }
+        post
+    }
+}
+
+/// A language parsers can be generated in. Each piece is generated separately and gen puts them
+/// together in order, so a new target only says how its pieces look.
+pub trait CodeGenerator {
+    /// Includes or imports at the top of the file.
+    fn prelude(&self, spec: &Spec) -> String;
+    /// The code that prints help, for targets that do not get it from a library.
+    fn usage(&self, spec: &Spec) -> String;
+    /// Functions the parser relies on.
+    fn helpers(&self, spec: &Spec) -> String;
+    /// Start of the parser, up to where arguments are read.
+    fn decls(&self, spec: &Spec) -> String;
+    /// Reading of the non-positional option at index i.
+    fn parse_item(&self, spec: &Spec, i: usize) -> String;
+    /// Reading of every non-positional, in order. Targets that work out something about all
+    /// options to read each one do it once here.
+    fn parse_items(&self, spec: &Spec) -> String {
+        (0..spec.non_positional.len())
+            .map(|i| self.parse_item(spec, i))
+            .collect()
+    }
+    /// Reading of all arguments, given what parse_item made of each non-positional.
+    fn parse_loop(&self, spec: &Spec, items: &str) -> String;
+    /// Checks and defaults once arguments are read, to the end of the parser.
+    fn post_loop(&self, spec: &Spec) -> String;
+    /// Entry point that calls the parser.
+    fn main(&self, spec: &Spec) -> String;
+    /// Turns whole lines of generated code into what is written out, as C restyles them. It is
+    /// given the lines in order, a few at a time.
+    fn finish_lines(&self, _spec: &Spec) -> Box<dyn FnMut(&str) -> String> {
+        Box::new(str::to_owned)
+    }
+    /// Generates everything, in the default layout.
+    fn gen(&self, spec: &Spec) -> String {
+        self.gen_with(spec, &Template::default())
+    }
+    /// Generates everything, laid out by template.
+    fn gen_with(&self, spec: &Spec, template: &Template) -> String {
+        let mut code = Vec::new();
+        self.write_with(spec, template, &mut code).unwrap();
+        String::from_utf8(code).unwrap()
+    }
+    /// Writes everything out as it is generated, laid out by template.
+    fn write_with(&self, spec: &Spec, template: &Template, out: &mut dyn Write) -> io::Result<()> {
+        assemble(self, spec, template, out)
+    }
+}
+
+/// The piece of generated code or spec field a template names.
+fn piece<G: CodeGenerator + ?Sized>(gen: &G, spec: &Spec, field: &str) -> String {
+    match field {
+        "prelude" => gen.prelude(spec),
+        "usage" => gen.usage(spec),
+        "helpers" => gen.helpers(spec),
+        "decls" => gen.decls(spec),
+        "parse_loop" => gen.parse_loop(spec, &gen.parse_items(spec)),
+        "post_loop" => gen.post_loop(spec),
+        "main" => gen.main(spec),
+        "program_name" => spec.program_name.clone().unwrap_or_default(),
+        "version" => spec.version.clone().unwrap_or_default(),
+        "about" => spec.about.clone().unwrap_or_default(),
+        _ => unreachable!("templates only name known fields"),
+    }
+}
+
+/// Writes the pieces from a CodeGenerator to out as template lays them out, each generated once
+/// the template reaches it, so the whole program is never held at once. Lines go through the
+/// generator's finish_lines as soon as they are whole.
+fn assemble<G: CodeGenerator + ?Sized>(
+    gen: &G,
+    spec: &Spec,
+    template: &Template,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let mut finish = gen.finish_lines(spec);
+    let mut partial = String::new();
+    template.render(
+        |field| piece(gen, spec, field),
+        |text| {
+            partial.push_str(text);
+            if let Some(end) = partial.rfind('\n') {
+                let lines: String = partial.drain(..=end).collect();
+                out.write_all(finish(&lines).as_bytes())?;
+            }
+            Ok(())
+        },
+    )?;
+    out.write_all(finish(&partial).as_bytes())
+}
+
+/// Languages that parsers can be generated in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    C,
+    Cpp,
+    Python,
+    Rust,
+}
+impl Language {
+    /// Name of the file in a template directory that lays out code in this language.
+    pub fn template_file(self) -> &'static str {
+        match self {
+            Language::C => "c.tmpl",
+            Language::Cpp => "cpp.tmpl",
+            Language::Python => "python.tmpl",
+            Language::Rust => "rust.tmpl",
+        }
+    }
+    fn generator(self) -> &'static dyn CodeGenerator {
+        match self {
+            Language::C => &c::C,
+            Language::Cpp => &cpp::Cpp,
+            Language::Python => &python::Python,
+            Language::Rust => &rust::Rust,
+        }
+    }
+}
+impl FromStr for Language {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Language, String> {
+        match s {
+            "c" => Ok(Language::C),
+            "c++" | "cpp" => Ok(Language::Cpp),
+            "python" => Ok(Language::Python),
+            "rust" => Ok(Language::Rust),
+            _ => Err(format!(
+                "unsupported language \"{}\" (try c, c++, python, or rust)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct Spec {
+    /// Spec files merged into this one, relative to it. Only read by Spec::from_path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    include: Option<Vec<String>>,
+    /// Name shown in usage and --version output, argv[0] if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    program_name: Option<String>,
+    /// Enables -V/--version when set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    /// Short description shown under the usage line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    about: Option<String>,
+    /// Stop parsing options at the first positional argument, as POSIX requires.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    strict_order: Option<bool>,
+    /// Column usage() output is wrapped at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help_width: Option<usize>,
+    /// Replace @file arguments with the whitespace-separated words in file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_files: Option<bool>,
+    /// Accept unambiguous prefixes of long option names, as in --verb for --verbose.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    allow_abbrev: Option<bool>,
+    /// Match long option names whatever their case, unless an option says otherwise.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    case_insensitive: Option<bool>,
+    /// Take arguments that look like negative numbers, as -5 or -1.5, as positional arguments
+    /// rather than options, as is the default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    negative_numbers: Option<bool>,
+    /// gettext domain help text is translated in. Help is left untranslated if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text_domain: Option<String>,
+    /// Directory the text domain's message catalogs are installed in, the system's if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    locale_dir: Option<String>,
+    /// Write Doxygen comments on the generated functions and variables, as is the default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    docs: Option<bool>,
+    /// Exit status when the command line is wrong, after printing the usage. 1 if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage_exit_code: Option<i32>,
+    /// Exit status for other errors, such as a response file that cannot be read. 1 if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_exit_code: Option<i32>,
+    /// Print the usage shown after a command-line error to stderr, as is the default, rather
+    /// than stdout. --help always prints to stdout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage_to_stderr: Option<bool>,
+    /// Copy string values out of argv, so they outlive it, and write free_args to free them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    copy_strings: Option<bool>,
+    /// Have parse_args take --argen-selftest, alone, to run checks of itself and exit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    selftest: Option<bool>,
+    /// File of long option names and values that stand in for options not given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_file: Option<ConfigFile>,
+    /// Positional must be ordered: required, then optional.
     /// Only the last PositionalItem can be multi.
+    #[serde(default)]
     positional: Vec<PositionalItem>,
     /// Non-positional is unordered.
+    #[serde(default)]
     non_positional: Vec<NonPositionalItem>,
+    /// Constraints across non-positional items.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    groups: Option<Vec<Group>>,
+    /// Layout and C standard of generated C.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    style: Option<Style>,
+    /// Extra headers for generated C, such as those defining custom types.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    includes: Option<Vec<String>>,
 }
 
-impl Spec {
+impl FromStr for Spec {
+    type Err = ValidationError;
     /// Deserializes toml from a string into a Spec.
-    pub fn from_str(toml: &str) -> Result<Spec, ValidationError> {
+    fn from_str(toml: &str) -> Result<Spec, ValidationError> {
         let s: Spec = toml::from_str(toml)?;
         s.validate()?;
         Ok(s)
     }
+}
+
+impl Spec {
+    fn is_strict_order(&self) -> bool {
+        self.strict_order.unwrap_or(false)
+    }
+    fn has_response_files(&self) -> bool {
+        self.response_files.unwrap_or(false)
+    }
+    fn allows_abbrev(&self) -> bool {
+        self.allow_abbrev.unwrap_or(false)
+    }
+    /// Whether npi's long names match whatever their case.
+    fn is_case_insensitive(&self, npi: &NonPositionalItem) -> bool {
+        npi.case_insensitive
+            .or(self.case_insensitive)
+            .unwrap_or(false)
+    }
+    /// Long names of the options that match whatever their case, aliases included.
+    fn case_insensitive_names(&self) -> Vec<&str> {
+        self.non_positional
+            .iter()
+            .filter(|npi| self.is_case_insensitive(npi))
+            .flat_map(|npi| npi.long_names())
+            .map(String::as_str)
+            .collect()
+    }
+    /// Whether arguments that look like negative numbers are values rather than options. Never
+    /// when a short option is a digit, since -5 could then be that option.
+    fn takes_negative_numbers(&self) -> bool {
+        self.negative_numbers.unwrap_or(true)
+            && !self.non_positional.iter().any(|npi| {
+                npi.short
+                    .as_ref()
+                    .is_some_and(|s| s.starts_with(|c: char| c.is_ascii_digit()))
+            })
+    }
+    /// Whether arguments that look like negative numbers are taken as positionals, which the C
+    /// and C++ parsers need only check for if there are any.
+    fn takes_negative_positionals(&self) -> bool {
+        self.takes_negative_numbers() && !self.positional.is_empty()
+    }
+    fn copies_strings(&self) -> bool {
+        self.copy_strings.unwrap_or(false)
+    }
+    fn usage_exit_code(&self) -> i32 {
+        self.usage_exit_code.unwrap_or(1)
+    }
+    fn error_exit_code(&self) -> i32 {
+        self.error_exit_code.unwrap_or(1)
+    }
+    fn has_usage_to_stderr(&self) -> bool {
+        self.usage_to_stderr.unwrap_or(true)
+    }
+    /// C that prints the usage for a command-line error and exits, indented by depth tabs.
+    fn cgen_usage_exit(&self, depth: usize) -> String {
+        let stream = if self.has_usage_to_stderr() {
+            "stderr"
+        } else {
+            "stdout"
+        };
+        format!(
+            "{0}usage({1}, progname);\n{0}exit({2});\n",
+            "\t".repeat(depth),
+            stream,
+            self.usage_exit_code()
+        )
+    }
+    fn has_docs(&self) -> bool {
+        self.docs.unwrap_or(true)
+    }
+    /// Long names of the options whose being given matters to a group, requires or
+    /// conflicts_with, found in one pass so each option can be looked up in them.
+    fn constrained_options(&self) -> HashSet<&str> {
+        let mut constrained = HashSet::new();
+        for group in self.groups.iter().flatten() {
+            constrained.extend(group.options.iter().map(String::as_str));
+        }
+        for npi in &self.non_positional {
+            let requires = npi.requires.iter().flatten();
+            let others: Vec<&str> = requires
+                .chain(npi.conflicts_with.iter().flatten())
+                .map(String::as_str)
+                .collect();
+            if !others.is_empty() {
+                constrained.insert(npi.long.as_str());
+                constrained.extend(others);
+            }
+        }
+        constrained
+    }
+    fn has_map(&self) -> bool {
+        self.non_positional.iter().any(NonPositionalItem::is_map)
+    }
+    /// Whether any argument is an int read from the command line, as flags are not.
+    fn has_int_arg(&self) -> bool {
+        self.positional
+            .iter()
+            .any(|pi| matches!(pi.c_type, CType::Int))
+            || self
+                .non_positional
+                .iter()
+                .any(|npi| matches!(npi.c_type, CType::Int) && !npi.is_flag())
+    }
+    fn has_custom(&self) -> bool {
+        self.positional
+            .iter()
+            .any(|pi| matches!(pi.c_type, CType::Custom))
+            || self
+                .non_positional
+                .iter()
+                .any(|npi| matches!(npi.c_type, CType::Custom))
+    }
+    fn style(&self) -> Style {
+        self.style.clone().unwrap_or_default()
+    }
+    fn c_std(&self) -> CStd {
+        self.style().std()
+    }
+    /// Writes generated C for std, whatever the spec's style says.
+    pub fn set_c_std(&mut self, std: CStd) {
+        self.style.get_or_insert_with(Style::default).std = Some(std);
+    }
+    fn c_target(&self) -> CTarget {
+        self.style().target()
+    }
+    /// Writes generated C for target, whatever the spec's style says.
+    pub fn set_c_target(&mut self, target: CTarget) {
+        self.style.get_or_insert_with(Style::default).target = Some(target);
+    }
+    /// Deserializes a Spec written in the given format from a reader.
+    pub fn from_reader<R>(rdr: R, format: SpecFormat) -> Result<Spec, ValidationError>
+    where
+        R: Read,
+    {
+        match format {
+            SpecFormat::Toml => Spec::from_toml_reader(rdr),
+            SpecFormat::Json => Spec::from_json_reader(rdr),
+            SpecFormat::Yaml => Spec::from_yaml_reader(rdr),
+        }
+    }
+    /// Deserializes toml from a reader into a Spec.
+    pub fn from_toml_reader<R>(mut rdr: R) -> Result<Spec, ValidationError>
+    where
+        R: Read,
+    {
+        let mut contents = String::new();
+        rdr.read_to_string(&mut contents)?;
+        Spec::from_str(&contents)
+    }
+    /// Deserializes json from a reader into a Spec.
+    pub fn from_json_reader<R>(rdr: R) -> Result<Spec, ValidationError>
+    where
+        R: Read,
+    {
+        let s: Spec = serde_json::from_reader(rdr)?;
+        s.validate()?;
+        Ok(s)
+    }
+    /// Deserializes yaml from a reader into a Spec.
+    pub fn from_yaml_reader<R>(rdr: R) -> Result<Spec, ValidationError>
+    where
+        R: Read,
+    {
+        let s: Spec = serde_yaml::from_reader(rdr)?;
+        s.validate()?;
+        Ok(s)
+    }
+    /// Reads the spec file at path, in the format its extension says, with the specs it
+    /// includes merged in.
+    ///
+    /// Included specs are merged in the order listed, each over the ones before it, and the
+    /// including spec over all of them. Settings come from the last of these to set them.
+    /// Arguments and groups are kept in that same order, except that an argument with the
+    /// c_var (positional) or long name (non-positional) of an earlier one replaces it where it
+    /// stood, as does a group of the same options.
+    pub fn from_path(path: &Path) -> Result<Spec, ValidationError> {
+        let s = Spec::read_including(path, &mut Vec::new())?;
+        s.validate()?;
+        Ok(s)
+    }
+    /// Reads the spec file at path as from_path does, but returns everything wrong with it
+    /// rather than the spec, as check does. Problems reading the file have an empty pointer.
+    pub fn check_path(path: &Path) -> Vec<(String, ValidationError)> {
+        match Spec::read_including(path, &mut Vec::new()) {
+            Ok(s) => s.check(),
+            Err(e) => vec![(String::new(), e)],
+        }
+    }
+    /// Reads the spec file at path and its includes without validating them. reading holds the
+    /// files being read further up, to catch specs that end up including themselves.
+    fn read_including(path: &Path, reading: &mut Vec<PathBuf>) -> Result<Spec, ValidationError> {
+        let canonical = fs::canonicalize(path)?;
+        if reading.contains(&canonical) {
+            return Err(ValidationError::IncludeCycle(path.display().to_string()));
+        }
+        let contents = fs::read_to_string(path)?;
+        let mut spec: Spec = match SpecFormat::from_path(path) {
+            SpecFormat::Toml => toml::from_str(&contents)?,
+            SpecFormat::Json => serde_json::from_str(&contents)?,
+            SpecFormat::Yaml => serde_yaml::from_str(&contents)?,
+        };
+        reading.push(canonical);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut base = Spec::default();
+        for include in spec.include.take().into_iter().flatten() {
+            let mut included = Spec::read_including(&dir.join(&include), reading)
+                .map_err(|e| ValidationError::InIncluded(include, Box::new(e)))?;
+            included.inherit(base);
+            base = included;
+        }
+        reading.pop();
+        spec.inherit(base);
+        Ok(spec)
+    }
+    /// Merges in a spec that self overrides, as from_path describes.
+    fn inherit(&mut self, base: Spec) {
+        /// items, then each of overrides in place of the item with its key or after them.
+        fn merge<T, K: PartialEq>(
+            mut items: Vec<T>,
+            overrides: Vec<T>,
+            key: impl Fn(&T) -> K,
+        ) -> Vec<T> {
+            for item in overrides {
+                match items.iter().position(|old| key(old) == key(&item)) {
+                    Some(i) => items[i] = item,
+                    None => items.push(item),
+                }
+            }
+            items
+        }
+        self.program_name = self.program_name.take().or(base.program_name);
+        self.version = self.version.take().or(base.version);
+        self.about = self.about.take().or(base.about);
+        self.strict_order = self.strict_order.or(base.strict_order);
+        self.help_width = self.help_width.or(base.help_width);
+        self.response_files = self.response_files.or(base.response_files);
+        self.allow_abbrev = self.allow_abbrev.or(base.allow_abbrev);
+        self.case_insensitive = self.case_insensitive.or(base.case_insensitive);
+        self.negative_numbers = self.negative_numbers.or(base.negative_numbers);
+        self.text_domain = self.text_domain.take().or(base.text_domain);
+        self.locale_dir = self.locale_dir.take().or(base.locale_dir);
+        self.docs = self.docs.or(base.docs);
+        self.usage_exit_code = self.usage_exit_code.or(base.usage_exit_code);
+        self.error_exit_code = self.error_exit_code.or(base.error_exit_code);
+        self.usage_to_stderr = self.usage_to_stderr.or(base.usage_to_stderr);
+        self.copy_strings = self.copy_strings.or(base.copy_strings);
+        self.selftest = self.selftest.or(base.selftest);
+        self.config_file = self.config_file.take().or(base.config_file);
+        self.style = self.style.take().or(base.style);
+        let positional = std::mem::take(&mut self.positional);
+        self.positional = merge(base.positional, positional, |pi| pi.c_var.to_owned());
+        let non_positional = std::mem::take(&mut self.non_positional);
+        self.non_positional = merge(base.non_positional, non_positional, |npi| {
+            npi.long.to_owned()
+        });
+        self.groups = match (base.groups, self.groups.take()) {
+            (Some(groups), Some(overrides)) => Some(merge(groups, overrides, Group::name)),
+            (groups, overrides) => overrides.or(groups),
+        };
+        self.includes = match (base.includes, self.includes.take()) {
+            (Some(headers), Some(more)) => Some(merge(headers, more, String::to_owned)),
+            (headers, more) => more.or(headers),
+        };
+    }
     /// Check all items in the spec to make sure they are valid.
     fn validate(&self) -> Result<(), ValidationError> {
+        match self.check().into_iter().next() {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
+        }
+    }
+    /// Everything wrong with the spec, each paired with the JSON pointer of the field at
+    /// fault. Pointers are into the spec as its includes were merged.
+    pub fn check(&self) -> Vec<(String, ValidationError)> {
+        let mut problems = Problems::new();
+        if self.include.is_some() {
+            problems.push((
+                String::from("/include"),
+                ValidationError::IncludeWithoutPath,
+            ));
+        }
+        if self.locale_dir.is_some() && self.text_domain.is_none() {
+            problems.push((
+                String::from("/locale_dir"),
+                ValidationError::LocaleDirWithoutTextDomain,
+            ));
+        }
+        let exit_codes = [
+            ("usage_exit_code", self.usage_exit_code),
+            ("error_exit_code", self.error_exit_code),
+        ];
+        for (field, code) in exit_codes.iter() {
+            if let Some(code) = *code {
+                if !(1..=255).contains(&code) {
+                    problems.push((
+                        format!("/{}", field),
+                        ValidationError::InvalidExitCode(field.to_string(), code),
+                    ));
+                }
+            }
+        }
         let mut saw_optional = false;
         for (i, pi) in self.positional.iter().enumerate() {
-            pi.validate()?;
+            let at = format!("/positional/{}", i);
+            pi.check(&at, &mut problems);
             if pi.is_required() && saw_optional {
-                return Err(
+                problems.push((
+                    format!("{}/required", at),
                     ValidationError::RequiredPositionalGoesBeforeOptionPositional(
                         pi.help_name.to_owned(),
                     ),
-                );
+                ));
             }
             if pi.is_multi() && i != self.positional.len() - 1 {
-                return Err(ValidationError::MultiMustBeLast(pi.help_name.to_owned()));
+                problems.push((
+                    format!("{}/multi", at),
+                    ValidationError::MultiMustBeLast(pi.help_name.to_owned()),
+                ));
             }
             if !pi.is_required() {
                 saw_optional = true
             }
         }
-        for npi in &self.non_positional {
-            npi.validate()?
+        // looked up by name below, once for each dependency and group member
+        let longs: HashSet<&str> = self
+            .non_positional
+            .iter()
+            .map(|npi| &npi.long[..])
+            .collect();
+        for (i, npi) in self.non_positional.iter().enumerate() {
+            let at = format!("/non_positional/{}", i);
+            npi.check(&at, &mut problems);
+            let requires = npi.requires.iter().flatten().enumerate();
+            let requires = requires.map(|(k, long)| (format!("{}/requires/{}", at, k), long));
+            let conflicts = npi.conflicts_with.iter().flatten().enumerate();
+            let conflicts =
+                conflicts.map(|(k, long)| (format!("{}/conflicts_with/{}", at, k), long));
+            for (pointer, long) in requires.chain(conflicts) {
+                if *long == npi.long {
+                    problems.push((
+                        pointer,
+                        ValidationError::SelfDependency(npi.long.to_owned()),
+                    ));
+                } else if !longs.contains(&long[..]) {
+                    problems.push((
+                        pointer,
+                        ValidationError::UnknownDependency(npi.long.to_owned(), long.to_owned()),
+                    ));
+                }
+            }
         }
-        Ok(())
+        for (i, group) in self.groups.iter().flatten().enumerate() {
+            let at = format!("/groups/{}", i);
+            if group.options.len() < 2 {
+                problems.push((
+                    format!("{}/options", at),
+                    ValidationError::GroupTooSmall(group.name()),
+                ));
+            }
+            if !group.is_exclusive() && !group.is_required() {
+                problems.push((
+                    at.clone(),
+                    ValidationError::GroupWithoutConstraint(group.name()),
+                ));
+            }
+            for (k, long) in group.options.iter().enumerate() {
+                if !longs.contains(&long[..]) {
+                    problems.push((
+                        format!("{}/options/{}", at, k),
+                        ValidationError::GroupUnknownOption(group.name(), long.to_owned()),
+                    ));
+                }
+            }
+        }
+        if let Some(config) = &self.config_file {
+            match &config.option {
+                None if config.path.is_none() => problems.push((
+                    String::from("/config_file"),
+                    ValidationError::ConfigFileWithoutPath,
+                )),
+                None => {}
+                Some(long) => match self.find_non_positional(long) {
+                    None => problems.push((
+                        String::from("/config_file/option"),
+                        ValidationError::ConfigUnknownOption(long.to_owned()),
+                    )),
+                    Some(npi) if !npi.takes_path() => problems.push((
+                        String::from("/config_file/option"),
+                        ValidationError::ConfigOptionNotPath(long.to_owned()),
+                    )),
+                    Some(_) => {}
+                },
+            }
+        }
+        if self.selftest.unwrap_or(false) {
+            problems.extend(self.selftest_problems());
+        }
+        self.check_conflicts(&mut problems);
+        problems
     }
-    /// Creates the necessary headers in C.
-    fn cgen_headers(&self) -> String {
-        INCLUDES
-            .iter()
-            .map(|s| format!("#include<{}.h>\n", s))
-            .collect()
+    /// Looks up a non-positional item by its long name.
+    fn find_non_positional(&self, long: &str) -> Option<&NonPositionalItem> {
+        self.non_positional.iter().find(|npi| npi.long == long)
     }
-    /// Creates the usage function in C.
-    fn cgen_usage(&self) -> String {
-        let positional_usage = {
-            let mut pos = String::new();
-            let mut noptional = 0;
-            for pi in &self.positional {
-                pos.push(' ');
-                if !pi.is_required() {
-                    pos.push('[');
-                    noptional += 1;
+    /// Check that no two items share a name, short name, or c variable, adding any that do to
+    /// problems. The -h/--help option (and -V/--version if enabled) is generated, so it counts
+    /// as taken.
+    fn check_conflicts(&self, problems: &mut Problems) {
+        let mut c_vars: HashSet<String> = RESERVED_C_VARS.iter().map(|s| s.to_string()).collect();
+        let mut longs: HashSet<&str> = ["help"].iter().cloned().collect();
+        let mut shorts: HashSet<&str> = ["h"].iter().cloned().collect();
+        if self.version.is_some() {
+            longs.insert("version");
+            shorts.insert("V");
+        }
+        if self.selftest.unwrap_or(false) {
+            longs.insert("argen-selftest");
+        }
+        for (i, pi) in self.positional.iter().enumerate() {
+            let at = format!("/positional/{}/c_var", i);
+            if !c_vars.insert(pi.c_var.to_owned()) {
+                problems.push((
+                    at.clone(),
+                    ValidationError::DuplicateCVar(pi.help_name.to_owned(), pi.c_var.to_owned()),
+                ));
+            }
+            let size_var = format!("{}__size", pi.c_var);
+            if pi.is_multi() && !c_vars.insert(size_var.clone()) {
+                problems.push((
+                    at,
+                    ValidationError::DuplicateCVar(pi.help_name.to_owned(), size_var),
+                ));
+            }
+        }
+        for (i, npi) in self.non_positional.iter().enumerate() {
+            let at = format!("/non_positional/{}", i);
+            if !c_vars.insert(npi.c_var.to_owned()) {
+                problems.push((
+                    format!("{}/c_var", at),
+                    ValidationError::DuplicateCVar(npi.long.to_owned(), npi.c_var.to_owned()),
+                ));
+            }
+            if npi.is_map() {
+                for c_var in npi.c_vars() {
+                    if !c_vars.insert(c_var.clone()) {
+                        problems.push((
+                            format!("{}/c_var", at),
+                            ValidationError::DuplicateCVar(npi.long.to_owned(), c_var),
+                        ));
+                    }
                 }
-                pos.push_str(&pi.help_name);
-                if pi.is_multi() {
-                    pos.push_str("...");
+            }
+            for (k, long) in npi.long_names().enumerate() {
+                if !longs.insert(long) {
+                    let field = match k {
+                        0 => String::from("long"),
+                        _ => format!("aliases/{}", k - 1),
+                    };
+                    problems.push((
+                        format!("{}/{}", at, field),
+                        ValidationError::DuplicateLong(npi.long.to_owned(), long.to_owned()),
+                    ));
+                }
+            }
+            if let Some(short) = &npi.short {
+                if !shorts.insert(short) {
+                    problems.push((
+                        format!("{}/short", at),
+                        ValidationError::DuplicateShort(npi.long.to_owned(), short.to_owned()),
+                    ));
                 }
             }
-            pos.push_str(&(0..noptional).map(|_| ']').collect::<String>());
-            pos
-        };
-        let mut help = String::new();
-        for pi_usage in self.positional.iter().map(PositionalItem::help) {
-            help.push_str(&pi_usage)
-        }
-        help.push_str(&format!(
-            "{0}-h  --help\\n\"\n\
-             {0}      print this usage and exit\\n\"\n",
-            HELP_PREFIX
-        ));
-        for npi_usage in self.non_positional.iter().map(NonPositionalItem::help) {
-            help.push_str(&npi_usage)
         }
-        format!(
-            "static void usage(const char *progname) {{\n\
-             \tprintf(\"usage: %s [options]{}\\n%s\", progname,\n\
-             {}\t       );\n\
-             }}\n",
-            positional_usage, help
-        )
-    }
-    /// Creates the parse_args function in C.
-    fn cgen_decl(&self) -> String {
-        let mut body = String::new();
-        body.push_str("void parse_args(int argc, char **argv");
-        for npi in &self.non_positional {
-            body.push_str(&npi.cgen_decl_arg())
+        // a name that matches whatever its case must not match another option's, which exact
+        // duplicates were already reported for
+        let mut folded: HashMap<String, Vec<&str>> = HashMap::new();
+        for long in &longs {
+            folded
+                .entry(long.to_ascii_lowercase())
+                .or_default()
+                .push(long);
         }
-        for pi in &self.positional {
-            body.push_str(&pi.cgen_decl_arg())
+        for (i, npi) in self.non_positional.iter().enumerate() {
+            if !self.is_case_insensitive(npi) {
+                continue;
+            }
+            for (k, long) in npi.long_names().enumerate() {
+                let clashes = folded[&long.to_ascii_lowercase()]
+                    .iter()
+                    .any(|other| !npi.long_names().any(|own| own == other));
+                if clashes {
+                    let field = match k {
+                        0 => String::from("long"),
+                        _ => format!("aliases/{}", k - 1),
+                    };
+                    problems.push((
+                        format!("/non_positional/{}/{}", i, field),
+                        ValidationError::DuplicateLong(npi.long.to_owned(), long.to_owned()),
+                    ));
+                }
+            }
         }
-        body.push_str(") {\n");
-
-        // decls for __isset
-        for npi in &self.non_positional {
-            body.push_str(&npi.cgen_isset_decl());
+        // validators and converters are called from parse_args, where c_vars would shadow them
+        let positional = self.positional.iter().enumerate().map(|(i, pi)| {
+            (
+                format!("/positional/{}", i),
+                &pi.help_name,
+                &pi.validator,
+                &pi.converter,
+                pi.c_decl_type(),
+            )
+        });
+        let non_positional = self.non_positional.iter().enumerate().map(|(i, npi)| {
+            (
+                format!("/non_positional/{}", i),
+                &npi.long,
+                &npi.validator,
+                &npi.converter,
+                npi.c_decl_type(),
+            )
+        });
+        // a converter reads one type, since it gets one prototype
+        let mut converted: HashMap<&str, String> = HashMap::new();
+        for (at, param, validator, converter, c_decl_type) in positional.chain(non_positional) {
+            if let Some(validator) = validator {
+                if !is_c_identifier(validator) || c_vars.contains(validator) {
+                    problems.push((
+                        format!("{}/validator", at),
+                        ValidationError::InvalidValidator(param.to_owned(), validator.to_owned()),
+                    ));
+                }
+            }
+            if let Some(converter) = converter {
+                let first_type = converted
+                    .entry(converter)
+                    .or_insert_with(|| c_decl_type.clone());
+                if c_vars.contains(converter) || *first_type != c_decl_type {
+                    problems.push((
+                        format!("{}/converter", at),
+                        ValidationError::InvalidConverter(param.to_owned(), converter.to_owned()),
+                    ));
+                }
+            }
         }
+    }
+    /// The positional arguments as shown after "[options]" on the usage line.
+    fn usage_synopsis(&self) -> String {
+        let mut pos = String::new();
+        let mut noptional = 0;
         for pi in &self.positional {
-            body.push_str(&pi.cgen_isset_decl());
+            let (words, opened) = pi.synopsis(&pi.help_name);
+            pos.push_str(&words);
+            noptional += opened;
         }
-        // defs for __default
-        for npi in &self.non_positional {
-            body.push_str(&npi.cgen_default_decl());
+        pos.push_str(&"]".repeat(noptional));
+        pos
+    }
+    /// How many positional arguments the spec takes at least, and at most if there is a limit.
+    fn positional_arity(&self) -> (usize, Option<usize>) {
+        let min = self.positional.iter().map(PositionalItem::min_values).sum();
+        let max = self.positional.iter().map(PositionalItem::max_values).sum();
+        (min, max)
+    }
+    /// The help shown below the usage line: the about text, then every argument and option.
+    fn help_text(&self) -> Vec<String> {
+        let width = self.help_width.unwrap_or(DEFAULT_HELP_WIDTH);
+        let mut lines = Vec::new();
+        if let Some(about) = &self.about {
+            lines.extend(wrap(about, width));
+            lines.push(String::new());
         }
-        for pi in &self.positional {
-            body.push_str(&pi.cgen_default_decl());
+        let mut entries: Vec<HelpEntry> =
+            self.positional.iter().map(PositionalItem::help).collect();
+        entries.push(HelpEntry {
+            label: String::from("-h, --help"),
+            descr: Some(String::from("print this usage and exit")),
+        });
+        if self.version.is_some() {
+            entries.push(HelpEntry {
+                label: String::from("-V, --version"),
+                descr: Some(String::from("print version and exit")),
+            });
         }
+        let in_group = |group: Option<&str>| -> Vec<HelpEntry> {
+            self.non_positional
+                .iter()
+                .filter(|npi| !npi.is_hidden() && npi.group.as_deref() == group)
+                .flat_map(NonPositionalItem::help)
+                .collect()
+        };
+        entries.extend(in_group(None));
+        let groups: Vec<(&str, Vec<HelpEntry>)> = self
+            .help_groups()
+            .into_iter()
+            .map(|title| (title, in_group(Some(title))))
+            .collect();
+        let mut sections = vec![(None, entries.as_slice())];
+        sections.extend(
+            groups
+                .iter()
+                .map(|(title, entries)| (Some(*title), entries.as_slice())),
+        );
+        lines.extend(help_sections(&sections, width));
+        lines
+    }
+    /// Titles of the help sections visible options are listed in, in the order they first
+    /// appear.
+    fn help_groups(&self) -> Vec<&str> {
+        let mut titles: Vec<&str> = Vec::new();
+        for npi in self.non_positional.iter().filter(|npi| !npi.is_hidden()) {
+            if let Some(title) = npi.group.as_deref() {
+                if !titles.contains(&title) {
+                    titles.push(title);
+                }
+            }
+        }
+        titles
+    }
+    /// Generates everything in the given language.
+    pub fn gen(&self, lang: Language) -> String {
+        lang.generator().gen(self)
+    }
+    /// Generates code in the given language, laid out by template rather than the default.
+    pub fn gen_with(&self, lang: Language, template: &Template) -> String {
+        lang.generator().gen_with(self, template)
+    }
+    /// Writes generated code in the given language to a writer, a piece at a time as it is
+    /// generated.
+    pub fn writeout<W>(&self, wrt: &mut W, lang: Language)
+    where
+        W: Write,
+    {
+        self.writeout_with(wrt, lang, &Template::default())
+            .expect("write generated code to file")
+    }
+    /// Writes generated code in the given language to a writer as writeout does, laid out by
+    /// template rather than the default.
+    pub fn writeout_with<W>(
+        &self,
+        wrt: &mut W,
+        lang: Language,
+        template: &Template,
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        lang.generator().write_with(self, template, wrt)
+    }
+}
 
-        // longopts
-        // unique chars for each longopt
-        let mut all_bytes: HashSet<u8> = (2..255).collect();
-        // remove chars that are used for small opts
-        for npi in &self.non_positional {
-            if let Some(s) = &npi.short {
-                all_bytes.remove(&s.as_bytes()[0]);
+#[cfg(test)]
+mod tests {
+    use super::{
+        c_int_literal, c_quote, help_sections, wrap, CTarget, HelpEntry, Language, Spec,
+        ValidationError,
+    };
+    use std::path::Path;
+    use std::str::FromStr;
+
+    #[test]
+    fn quote_escapes_quotes() {
+        assert_eq!(c_quote(r#"say "hi""#), r#"say \"hi\""#);
+        assert_eq!(c_quote("it's"), r"it\'s");
+    }
+
+    #[test]
+    fn quote_escapes_backslashes() {
+        assert_eq!(c_quote(r"C:\dir\"), r"C:\\dir\\");
+    }
+
+    #[test]
+    fn quote_escapes_newlines() {
+        assert_eq!(c_quote("a\nb\r\tc"), r"a\nb\r\tc");
+    }
+
+    #[test]
+    fn quote_escapes_non_ascii() {
+        assert_eq!(c_quote("caf\u{e9}1"), r"caf\303\2511");
+        assert_eq!(c_quote("\u{7f}\u{1}"), r"\177\001");
+    }
+
+    #[test]
+    fn quote_breaks_trigraphs() {
+        assert_eq!(c_quote("what??!"), r"what?\?!");
+    }
+
+    #[test]
+    fn int_literals() {
+        for lit in &[
+            "0",
+            "12",
+            "-12",
+            "+7",
+            "2147483647",
+            "-2147483648",
+            "0x1f",
+            "0X7FFFFFFF",
+            "-0x80000000",
+            "017",
+        ] {
+            assert!(c_int_literal(lit), "{}", lit);
+        }
+        for lit in &[
+            "",
+            "-",
+            "banana",
+            "12abc",
+            "1.5",
+            "2147483648",
+            "0x",
+            "0x100000000",
+            "0XDEADBEEF",
+            "0x80000000",
+            "-0x80000001",
+            "037777777777",
+            "08",
+            " 1",
+        ] {
+            assert!(!c_int_literal(lit), "{}", lit);
+        }
+    }
+
+    #[test]
+    fn bad_int_default() {
+        let e = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "x"
+            c_type = "int"
+            long = "x"
+            default = "banana"
+            "#,
+        );
+        match e {
+            Err(ValidationError::InvalidDefault(param, default, _)) => {
+                assert_eq!(param, "x");
+                assert_eq!(default, "banana");
             }
+            _ => panic!("expected InvalidDefault"),
         }
-        let mut unused_bytes = all_bytes.drain().collect::<Vec<_>>();
-        unused_bytes.sort();
-        unused_bytes.reverse();
-        let mut next_free_shortname = unused_bytes.into_iter();
-        let uniqs: Vec<u8> = self
-            .non_positional
-            .iter()
-            .map(|npi| {
-                if let Some(s) = &npi.short {
-                    s.as_bytes()[0]
-                } else {
-                    next_free_shortname
-                        .next()
-                        .expect("too many non-positional arguments")
-                }
-            })
-            .collect();
-        body.push_str("\tstatic struct option longopts[] = {\n");
-        for (i, npi) in self.non_positional.iter().enumerate() {
-            body.push_str(&npi.cgen_getopt(uniqs[i]));
+    }
+
+    fn spec_err(toml: &str) -> ValidationError {
+        match Spec::from_str(toml) {
+            Err(e) => e,
+            Ok(_) => panic!("expected spec to be rejected"),
         }
-        body.push_str(
-            "\t\t{\"help\", 0, 0, 'h'},\n\
-             \t\t{0, 0, 0, 0}\n\t};\n",
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let dup_long = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "a"
+            c_type = "int"
+            long = "size"
+            [[non_positional]]
+            c_var = "b"
+            c_type = "int"
+            long = "block-size"
+            aliases = ["size"]
+            "#,
+        );
+        assert!(
+            matches!(dup_long, ValidationError::DuplicateLong(p, l) if p == "block-size" && l == "size")
         );
+        let help = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "a"
+            c_type = "int"
+            long = "halp"
+            short = "h"
+            "#,
+        );
+        assert!(matches!(help, ValidationError::DuplicateShort(_, s) if s == "h"));
+        let c_var = spec_err(
+            r#"
+            [[positional]]
+            c_var = "a"
+            c_type = "char*"
+            help_name = "A"
+            [[non_positional]]
+            c_var = "a"
+            c_type = "int"
+            long = "a"
+            "#,
+        );
+        assert!(matches!(c_var, ValidationError::DuplicateCVar(p, v) if p == "a" && v == "a"));
+    }
 
-        // shortopts
-        let mut optstring = String::from_utf8(
-            self.non_positional
-                .iter()
-                .filter(|npi| npi.short.is_some())
-                .flat_map(|npi| {
-                    let s = npi.short.clone();
-                    let mut v = Vec::new();
-                    v.push(s.unwrap().as_bytes()[0]);
-                    if !npi.is_flag() {
-                        v.push(b':');
-                    }
-                    v.into_iter().collect::<Vec<u8>>()
-                })
-                .collect(),
+    #[test]
+    fn groups() {
+        let spec = r#"
+            positional = []
+            [[non_positional]]
+            c_var = "json"
+            c_type = "int"
+            long = "json"
+            flag = true
+            [[non_positional]]
+            c_var = "yaml"
+            c_type = "int"
+            long = "yaml"
+            flag = true
+            [[groups]]
+            options = ["json", "yaml"]
+            exclusive = true
+            required = true
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains("if (json__isset + yaml__isset > 1) {"));
+        assert!(c.contains("if (json__isset + yaml__isset < 1) {"));
+        // names are part of the format strings they are printed with
+        let c = Spec::from_str(
+            &spec
+                .replace("long = \"yaml\"", "long = \"ya%ml\"")
+                .replace(r#"["json", "yaml"]"#, r#"["json", "ya%ml"]"#),
         )
-        .unwrap();
-        optstring.push('h');
-
-        // parse loop, optional
-        body.push_str(&format!(
-            "\tint ch;\n\
-             \twhile ((ch = getopt_long(argc, argv, \"{}\", longopts, NULL)) != -1) {{\n\
-             \t\tswitch (ch) {{\n",
-            optstring
-        ));
-        for (i, uniq) in uniqs.iter().enumerate() {
-            body.push_str(&format!(
-                "\t\tcase {}:\n{}\t\t\tbreak;\n",
-                uniq,
-                self.non_positional[i].cgen_assign_optarg()
-            ));
-        }
-        body.push_str(
-            "\t\tcase 0:\n\t\t\tbreak;\n\
-             \t\tcase 'h':\n\
-             \t\tdefault:\n\t\t\tusage(argv[0]);\n\t\t\texit(1);\n\
-             \t\t}\n\t}\n",
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains("fprintf(stderr, \" --ya%%ml\");"));
+        assert!(c.contains("one of these options is required: --json, --ya%%ml\\n\""));
+        let unknown = spec_err(&spec.replace(r#"["json", "yaml"]"#, r#"["json", "toml"]"#));
+        assert!(matches!(unknown, ValidationError::GroupUnknownOption(_, l) if l == "toml"));
+    }
+
+    #[test]
+    fn dependencies() {
+        let spec = r#"
+            positional = []
+            [[non_positional]]
+            c_var = "user"
+            c_type = "char*"
+            long = "user"
+            requires = ["password"]
+            conflicts_with = ["anonymous"]
+            [[non_positional]]
+            c_var = "password"
+            c_type = "char*"
+            long = "password"
+            [[non_positional]]
+            c_var = "anonymous"
+            c_type = "int"
+            long = "anonymous"
+            flag = true
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains("if (user__isset && !password__isset) {"));
+        assert!(c.contains("if (user__isset && anonymous__isset) {"));
+        let unknown = spec_err(&spec.replace(r#"["password"]"#, r#"["pass"]"#));
+        assert!(
+            matches!(unknown, ValidationError::UnknownDependency(p, l) if p == "user" && l == "pass")
         );
+    }
 
-        // post loop, optional
-        for npi in &self.non_positional {
-            body.push_str(&npi.cgen_post_loop());
-        }
+    #[test]
+    fn argument_order() {
+        let spec = r#"
+            positional = []
+            non_positional = []
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains(r#"getopt_long(argc, argv, "-:h", longopts, &longindex)"#));
+        assert!(c.contains("argv[++npos] = optarg;"));
+        let c = Spec::from_str(&format!("strict_order = true\n{}", spec))
+            .unwrap()
+            .gen(Language::C);
+        assert!(c.contains(r#"getopt_long(argc, argv, "+:h", longopts, &longindex)"#));
+        assert!(!c.contains("npos"));
+    }
 
-        // parse+post loop, positional
-        let required: Vec<&PositionalItem> = self
-            .positional
-            .iter()
-            .filter(|p| p.is_required() && !p.is_multi())
-            .collect();
-        let nrequired = required.len()
-            + if self
-                .positional
-                .iter()
-                .any(|p| p.is_required() && p.is_multi())
-            {
-                1
-            } else {
-                0
-            };
-        if nrequired > 0 {
-            body.push_str(&format!(
-                "\n\tif (argc-optind < {}) {{\n\
-                   \t\tusage(argv[0]);\n\
-                   \t\texit(1);\n\
-                   \t}}\n\
-                   \targv += optind;\n\targc -= optind;\n\n",
-                nrequired
+    #[test]
+    fn many_options() {
+        let mut toml = String::from("positional = []\n");
+        for i in 0..3000 {
+            toml.push_str(&format!(
+                "[[non_positional]]\nc_var = \"o{0}\"\nc_type = \"int\"\nlong = \"option-{0}\"\n",
+                i
             ));
-            if !required.is_empty() {
-                for pi in &required {
-                    body.push_str(&format!("{}\targv++;\n", pi.cgen_assign_argv0()));
-                }
-                if required.len() == 1 {
-                    body.push_str("\targc--;\n\n");
-                } else {
-                    body.push_str(&format!("\targc -= {};\n\n", required.len()));
-                }
-                for pi in &required {
-                    body.push_str(&pi.cgen_post_loop());
-                }
+            if i % 2 == 1 {
+                toml.push_str(&format!("conflicts_with = [\"option-{}\"]\n", i - 1));
             }
         }
+        let spec = Spec::from_str(&toml).unwrap();
+        assert!(spec.check().is_empty());
+        let c = spec.gen(Language::C);
+        assert!(c.contains("\t\t{\"option-2999\", required_argument, 0, 3255},\n"));
+        assert!(c.contains("\t\tcase 3255:\n"));
+        assert!(c.contains("\tbool o2999__isset = false;\n"));
+    }
 
-        // parse+post loop, positional optional
-        let optional: Vec<&PositionalItem> = self
-            .positional
+    #[test]
+    fn unknown_options() {
+        let c = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "block_size"
+            c_type = "int"
+            long = "block-size"
+            aliases = ["bs"]
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains(r#"{"block-size", required_argument, 0, 256},"#));
+        assert!(c.contains(r#"{"bs", required_argument, 0, 256},"#));
+        assert!(c.contains("size_t row[11];"));
+        assert!(c.contains("\t\t\"block-size\",\n\t\t\"bs\",\n\t\t\"help\",\n"));
+        assert!(c.contains("unknown_option(progname, longopts, optopt, argv[optind-1]);"));
+    }
+
+    #[test]
+    fn wrap_text() {
+        assert_eq!(
+            wrap("one two three four", 9),
+            vec!["one two", "three", "four"]
+        );
+        assert_eq!(wrap("a\nb c", 80), vec!["a", "b c"]);
+        assert_eq!(wrap("tiny enormousword", 5), vec!["tiny", "enormousword"]);
+    }
+
+    #[test]
+    fn help_columns() {
+        let entries = vec![
+            HelpEntry {
+                label: String::from("-h, --help"),
+                descr: Some(String::from("print this usage and exit")),
+            },
+            HelpEntry {
+                label: String::from("    --very-long-option-name <arg>"),
+                descr: Some(String::from("goes below")),
+            },
+            HelpEntry {
+                label: String::from("    --quiet"),
+                descr: None,
+            },
+        ];
+        assert_eq!(
+            help_sections(&[(None, &entries)], 36),
+            vec![
+                "  -h, --help   print this usage and",
+                "               exit",
+                "      --very-long-option-name <arg>",
+                "               goes below",
+                "      --quiet",
+            ]
+        );
+    }
+
+    #[test]
+    fn metavars() {
+        let c = Spec::from_str(
+            r#"
+            [[positional]]
+            c_var = "input"
+            c_type = "char*"
+            metavar = "FILE"
+            [[non_positional]]
+            c_var = "count"
+            c_type = "int"
+            long = "count"
+            [[non_positional]]
+            c_var = "output"
+            c_type = "char*"
+            long = "output"
+            metavar = "FILE"
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains("[options] [FILE]"));
+        assert!(c.contains("--count <NUM>"));
+        assert!(c.contains("--output <FILE>"));
+    }
+
+    #[test]
+    fn response_files() {
+        let spec = r#"
+            positional = []
+            non_positional = []
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(!c.contains("expand_response_files"));
+        assert!(!c.contains("#include<errno.h>"));
+        let c = Spec::from_str(&format!("response_files = true\n{}", spec))
+            .unwrap()
+            .gen(Language::C);
+        assert!(c.contains("#include<ctype.h>\n#include<errno.h>\n"));
+        assert!(c.contains("\tif (depth > 16) {\n"));
+        assert!(c.contains("\texpand_response_files(progname, &argc, &argv);\n"));
+    }
+
+    #[test]
+    fn count_option() {
+        let c = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "verbose"
+            c_type = "int"
+            long = "verbose"
+            short = "v"
+            count = true
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains(r#"{"verbose", no_argument, 0, 118},"#));
+        assert!(c.contains("\"-:vh\""));
+        assert!(c.contains("*verbose = verbose__isset ? *verbose + 1 : 1;"));
+        assert!(c.contains("if (!verbose__isset) {\n\t\t*verbose = 0;\n\t}"));
+    }
+
+    #[test]
+    fn map_option() {
+        let c = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "defs"
+            c_type = "char*"
+            long = "define"
+            short = "D"
+            map = true
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains("char* **defs__keys, char* **defs__values, size_t *defs__size)"));
+        assert!(c.contains(
+            "map_push(progname, \"define\", optarg, defs__keys, defs__values, defs__size, &defs__cap);"
+        ));
+        assert!(c.contains("-D, --define <NAME=VALUE>"));
+        assert!(c.contains("parse_args(argc, argv, &defs__keys, &defs__values, &defs__size);"));
+
+        // map_push prints the name with %s, so it is not a format string
+        let c = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "defs"
+            c_type = "char*"
+            long = "def%s"
+            map = true
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains("map_push(progname, \"def%s\", optarg,"));
+
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "defs"
+            c_type = "int"
+            long = "define"
+            map = true
+            "#,
+        );
+        assert!(matches!(err, ValidationError::MapNotChars(_)));
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "defs"
+            c_type = "char*"
+            long = "define"
+            map = true
+            [[non_positional]]
+            c_var = "defs__size"
+            c_type = "int"
+            long = "size"
+            "#,
+        );
+        assert!(matches!(err, ValidationError::DuplicateCVar(_, _)));
+    }
+
+    #[test]
+    fn hidden_and_deprecated_options() {
+        let c = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "colour"
+            c_type = "int"
+            long = "colour"
+            flag = true
+            hidden = true
+            [[non_positional]]
+            c_var = "jobs"
+            c_type = "int"
+            long = "jobs"
+            deprecated = "use --threads instead"
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains(r#"{"colour", no_argument, 0, "#));
+        assert!(!c.contains("--colour"));
+        assert!(!c.contains("\t\t\"colour\",\n"));
+        assert!(c.contains("      --jobs <NUM>  (deprecated)\\n"));
+        assert!(c.contains(
+            "\t\t\tfprintf(stderr, \"%s: warning: --jobs is deprecated: use --threads instead\\n\", progname);\n"
+        ));
+
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "jobs"
+            c_type = "int"
+            long = "jobs"
+            required = true
+            hidden = true
+            "#,
+        );
+        assert!(matches!(err, ValidationError::RequiredCannotBeHidden(_)));
+    }
+
+    #[test]
+    fn deprecated_aliases() {
+        let spec = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "color"
+            c_type = "int"
+            long = "color"
+            aliases = ["tint", { name = "colour", deprecated = true }]
+            flag = true
+            help_descr = "colorize output"
+            "#,
+        )
+        .unwrap();
+        let warning = "warning: --colour is deprecated, use --color";
+        let c = spec.gen(Language::C);
+        assert!(c.contains("      --color   colorize output (aliased: --tint)\\n"));
+        assert!(c.contains("      --colour  (alias of --color, deprecated)\\n"));
+        assert!(c.contains(&format!(
+            "\t\tcase 257:\n\t\t\tfprintf(stderr, \"%s: {}\\n\", progname);\n\t\t\t/* fall through */\n\t\tcase 256:\n",
+            warning
+        )));
+        assert!(!c.contains("\t\t\"colour\",\n"));
+        let cpp = spec.gen(Language::Cpp);
+        assert!(cpp.contains(&format!(
+            "\t\t\tif (name == \"--colour\") {{\n\t\t\t\tstd::cerr << progname << \": {}\\n\";\n",
+            warning
+        )));
+        let py = spec.gen(Language::Python);
+        assert!(py.contains(&format!(
+            "    warn_when_given(parser.add_argument('--color', '--tint', '--colour', dest='color', \
+             default=argparse.SUPPRESS, action='store_true', help='colorize output'), \
+             {{'--colour': '{}'}})\n",
+            warning
+        )));
+        let rs = spec.gen(Language::Rust);
+        assert!(rs.contains("visible_aliases = [\"tint\"], aliases = [\"colour\"]"));
+        assert!(rs.contains(warning));
+        let json = serde_json::to_value(&spec).unwrap();
+        assert_eq!(json["non_positional"][0]["aliases"][0], "tint");
+        assert_eq!(json["non_positional"][0]["aliases"][1]["deprecated"], true);
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let spec: Spec = toml::from_str(
+            r#"
+            [[positional]]
+            c_var = "count"
+            c_type = "int"
+            help_name = "N"
+            default = "many"
+            [[non_positional]]
+            c_var = "count"
+            c_type = "int"
+            long = "verbose"
+            aliases = ["loud", "bad alias"]
+            flag = true
+            requires = ["quiet"]
+            "#,
+        )
+        .unwrap();
+        let pointers: Vec<String> = spec.check().into_iter().map(|(at, _)| at).collect();
+        assert_eq!(
+            pointers,
+            [
+                "/positional/0/default",
+                "/non_positional/0/aliases/1",
+                "/non_positional/0/requires/0",
+                "/non_positional/0/c_var",
+            ]
+        );
+        assert!(matches!(
+            spec.validate(),
+            Err(ValidationError::InvalidDefault(..))
+        ));
+    }
+
+    #[test]
+    fn included_specs() {
+        let spec = Spec::from_path(Path::new("tests/specs/shared_options.toml")).unwrap();
+        let help = spec.help_text();
+        let options: Vec<&str> = help
             .iter()
-            .filter(|p| !p.is_required() && !p.is_multi())
+            .filter_map(|line| line.split_whitespace().find(|w| w.starts_with("--")))
             .collect();
-        for pi in &optional {
-            body.push_str("\tif (argc > 0) {\n");
-            body.push_str(&pi.cgen_assign_argv0());
-            body.push_str("\t\targv++; argc--;\n\t}\n");
-        }
-        for pi in &optional {
-            body.push_str(&pi.cgen_post_loop());
-        }
+        assert_eq!(
+            options,
+            [
+                "--help",
+                "--version",
+                "--verbose",
+                "--config",
+                "--log-level",
+                "--dry-run"
+            ]
+        );
+        let c = spec.gen(Language::C);
+        assert!(c.contains("static char* log_level__default = \"info\";"));
+        assert!(c.contains("printf(\"%s 2.1\\n\", progname);"));
 
-        // multi item
-        let multi: Option<&PositionalItem> = self.positional.iter().find(|p| p.is_multi());
-        if let Some(pi) = multi {
-            if pi.is_required() {
-                body.push_str(&pi.cgen_assign_argv0());
-            } else {
-                body.push_str("\tif (argc > 0) {\n");
-                body.push_str(&pi.cgen_assign_argv0());
-                body.push_str("\t}\n");
-            }
-            body.push_str(&pi.cgen_post_loop());
-        }
+        let err = Spec::from_path(Path::new("tests/fragments/loops.toml")).err();
+        assert!(matches!(err, Some(ValidationError::InIncluded(_, e))
+            if matches!(*e, ValidationError::IncludeCycle(_))));
+        let err = spec_err("include = [\"common.toml\"]\npositional = []\nnon_positional = []");
+        assert!(matches!(err, ValidationError::IncludeWithoutPath));
+    }
 
-        body.push_str("}\n");
-        body
+    #[test]
+    fn abbreviations() {
+        let spec = r#"
+            positional = []
+            [[non_positional]]
+            c_var = "verbose"
+            c_type = "int"
+            long = "verbose"
+            flag = true
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "\t\tif (longindex >= 0 && strcspn(argv[at] + 2, \"=\") != strlen(longopts[longindex].name)) {\n"
+        ));
+        assert!(!c.contains("ambiguous"));
+        assert!(Spec::from_str(spec)
+            .unwrap()
+            .gen(Language::Python)
+            .contains("allow_abbrev=False"));
+
+        let spec = format!("allow_abbrev = true\n{}", spec);
+        let c = Spec::from_str(&spec).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "\twhile ((ch = getopt_long(argc, argv, \"-:h\", longopts, NULL)) != -1) {\n"
+        ));
+        assert!(!c.contains("longindex"));
+        assert!(c.contains("ambiguous option: --%.*s could be"));
+        let cpp = Spec::from_str(&spec).unwrap().gen(Language::Cpp);
+        assert!(cpp.contains("name = expand_abbrev(progname, name);"));
+        let rs = Spec::from_str(&spec).unwrap().gen(Language::Rust);
+        assert!(rs.contains("infer_long_args = true"));
     }
-    /// Creates the main function in C.
-    fn cgen_main(&self) -> String {
-        let mut main = String::new();
-        main.push_str("int main(int argc, char **argv) {\n");
 
-        for npi in &self.non_positional {
-            main.push_str(&npi.cgen_main_decl())
-        }
-        for pi in &self.positional {
-            main.push_str(&pi.cgen_main_decls())
-        }
+    #[test]
+    fn translated_help() {
+        let spec = r#"
+            text_domain = "demo"
+            locale_dir = "/usr/share/locale"
+            about = "Says hello."
+            positional = []
+            [[non_positional]]
+            c_var = "name"
+            c_type = "char*"
+            long = "name"
+            help_descr = "who to greet"
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains("#include<libintl.h>\n#include<locale.h>\n"));
+        assert!(c.contains("#define _(msgid) gettext(msgid)\n"));
+        assert!(c.contains("\tfprintf(out, _(\"usage: %s [options]\\n%s\"), progname, _(\n"));
+        assert!(c.contains(
+            "\tsetlocale(LC_ALL, \"\");\n\
+             \tbindtextdomain(\"demo\", \"/usr/share/locale\");\n\
+             \ttextdomain(\"demo\");\n"
+        ));
+        let py = Spec::from_str(spec).unwrap().gen(Language::Python);
+        assert!(py.contains("_ = gettext.translation('demo', '/usr/share/locale', fallback=True)"));
+        assert!(py.contains("description=_('Says hello.')"));
+        assert!(py.contains("help=_('who to greet')"));
 
-        main.push_str("\n\tparse_args(argc, argv");
-        for npi in &self.non_positional {
-            main.push_str(&npi.cgen_call_arg())
-        }
-        for pi in &self.positional {
-            main.push_str(&pi.cgen_call_arg())
+        let untranslated = spec.replace("text_domain = \"demo\"", "");
+        let err = spec_err(&untranslated);
+        assert!(matches!(err, ValidationError::LocaleDirWithoutTextDomain));
+        let c = Spec::from_str(&untranslated.replace("locale_dir", "#"))
+            .unwrap()
+            .gen(Language::C);
+        assert!(!c.contains("gettext"));
+        assert!(!c.contains("setlocale"));
+    }
+
+    #[test]
+    fn doc_comments() {
+        let spec = r#"
+            [[positional]]
+            c_var = "files"
+            c_type = "char*"
+            help_name = "FILE"
+            help_descr = "files to read"
+            multi = true
+            [[non_positional]]
+            c_var = "name"
+            c_type = "char*"
+            long = "name"
+            short = "n"
+            help_descr = "who to greet */"
+            default = "world"
+            [[non_positional]]
+            c_var = "debug"
+            c_type = "int"
+            long = "debug"
+            flag = true
+            hidden = true
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "/**\n \
+             * Prints the usage line and help for each argument.\n \
+             *\n \
+             * @param out Stream to print to, stdout for --help.\n \
+             * @param progname Name of the program, shown in the usage line.\n \
+             */\n\
+             static void usage(FILE *out, const char *progname) {\n"
+        ));
+        assert!(c.contains(
+            " * @param[out] name -n, --name: who to greet * /. Defaults to \"world\".\n \
+             * @param[out] debug Hidden option. Set when given.\n \
+             * @param[out] files FILE...: files to read.\n \
+             * @param[out] files__size Number of values in files.\n \
+             */\n\
+             void parse_args("
+        ));
+        let cpp = Spec::from_str(spec).unwrap().gen(Language::Cpp);
+        assert!(cpp.contains("\t/// FILE...: files to read.\n\tstd::vector<std::string> files;\n"));
+
+        let spec = format!("docs = false\n{}", spec);
+        let c = Spec::from_str(&spec).unwrap().gen(Language::C);
+        assert!(!c.contains("/**"));
+        let cpp = Spec::from_str(&spec).unwrap().gen(Language::Cpp);
+        assert!(!cpp.contains("/// FILE"));
+    }
+
+    #[test]
+    fn config_file() {
+        let spec = r#"
+            positional = []
+            [config_file]
+            path = "/etc/demo.conf"
+            option = "config"
+            [[non_positional]]
+            c_var = "config_path"
+            c_type = "char*"
+            long = "config"
+            [[non_positional]]
+            c_var = "level"
+            c_type = "int"
+            long = "level"
+            [[non_positional]]
+            c_var = "verbose"
+            c_type = "int"
+            long = "verbose"
+            count = true
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "\tread_config(progname, config_path__isset ? *config_path : \"/etc/demo.conf\", \
+             config_path__isset, &file_config);\n\
+             \tif ((optarg = config_get(&file_config, \"level\")) != NULL && !level__isset) {\n\
+             \t\t*level = parse_int(progname, \"--level\", optarg);\n\
+             \t\tlevel__isset = true;\n\
+             \t}\n\
+             \tconfig_check_used(progname, &file_config);\n"
+        ));
+        assert!(!c.contains("\"config\")) != NULL"));
+        assert!(!c.contains("\"verbose\")) != NULL"));
+
+        let err = spec_err(&spec.replace("option = \"config\"", "option = \"level\""));
+        assert!(matches!(err, ValidationError::ConfigOptionNotPath(l) if l == "level"));
+        let err = spec_err(&spec.replace("option = \"config\"", "option = \"conf\""));
+        assert!(matches!(err, ValidationError::ConfigUnknownOption(l) if l == "conf"));
+        let err = spec_err(
+            &spec
+                .replace("option = \"config\"", "")
+                .replace("path = ", "#"),
+        );
+        assert!(matches!(err, ValidationError::ConfigFileWithoutPath));
+        let spec = spec.replace("option = \"config\"", "");
+        let c = Spec::from_str(&spec).unwrap().gen(Language::C);
+        assert!(c.contains("\tread_config(progname, \"/etc/demo.conf\", 0, &file_config);\n"));
+        assert!(c.contains("(optarg = config_get(&file_config, \"config\")) != NULL"));
+    }
+
+    #[test]
+    fn windows_target() {
+        let spec = r#"
+            positional = []
+            [style]
+            target = "windows"
+            wmain = true
+            [[non_positional]]
+            c_var = "name"
+            c_type = "char*"
+            long = "name"
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.starts_with("#define _CRT_SECURE_NO_WARNINGS\n#define WIN32_LEAN_AND_MEAN\n"));
+        assert!(c.contains("#include<windows.h>\n"));
+        assert!(!c.contains("getopt.h"));
+        assert!(
+            c.contains("static int getopt_long(int argc, char **argv, const char *optstring,\n")
+        );
+        assert!(c.contains("\nstatic int utf8_main(int argc, char **argv) {\n"));
+        assert!(c.contains("\nint wmain(int argc, wchar_t **wargv) {\n"));
+        assert!(c.contains("\treturn utf8_main(argc, argv);\n"));
+
+        let mut spec = Spec::from_str(spec).unwrap();
+        spec.set_c_target(CTarget::Posix);
+        let c = spec.gen(Language::C);
+        assert!(c.contains("#include<getopt.h>\n"));
+        assert!(!c.contains("_CRT_SECURE_NO_WARNINGS"));
+        assert!(!c.contains("wmain"));
+        assert!(c.contains("\nint main(int argc, char **argv) {\n"));
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let spec = r#"
+            case_insensitive = true
+            positional = []
+            [[non_positional]]
+            c_var = "verbose"
+            c_type = "int"
+            long = "verbose"
+            aliases = ["loud"]
+            flag = true
+            [[non_positional]]
+            c_var = "mode"
+            c_type = "char*"
+            long = "mode"
+            case_insensitive = false
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "\tstatic const char *names[] = {\n\
+             \t\t\"verbose\",\n\
+             \t\t\"loud\",\n\
+             \t};\n"
+        ));
+        assert!(c.contains("\t\tfold_option_case(argv, optind, argc);\n\t\tat = optind;\n"));
+        assert!(!c.contains("strcasecmp"));
+        let cpp = Spec::from_str(spec).unwrap().gen(Language::Cpp);
+        assert!(cpp.contains("\t\t\tname = fold_option_case(name);\n"));
+
+        let c = Spec::from_str(&spec.replace("case_insensitive = true", ""))
+            .unwrap()
+            .gen(Language::C);
+        assert!(!c.contains("fold_option_case"));
+
+        let err = spec_err(&spec.replace("long = \"mode\"", "long = \"Verbose\""));
+        assert!(
+            matches!(err, ValidationError::DuplicateLong(p, l) if p == "verbose" && l == "verbose")
+        );
+    }
+
+    #[test]
+    fn negative_numbers() {
+        let spec = r#"
+            [[positional]]
+            c_var = "n"
+            c_type = "int"
+            help_name = "N"
+            [[non_positional]]
+            c_var = "offset"
+            c_type = "int"
+            long = "offset"
+            short = "o"
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "\t\tif (optind < argc && is_negative_number(argv[optind])) {\n\
+             \t\t\targv[++npos] = argv[optind++];\n\
+             \t\t\tcontinue;\n\
+             \t\t}\n"
+        ));
+        let strict = format!("strict_order = true\n{}", spec);
+        let c = Spec::from_str(&strict).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "\t\tif (optind < argc && is_negative_number(argv[optind])) {\n\t\t\tbreak;\n"
+        ));
+        let cpp = Spec::from_str(spec).unwrap().gen(Language::Cpp);
+        assert!(cpp.contains("if (arg.size() < 2 || arg[0] != '-' || is_negative_number(arg)) {"));
+        let rust = Spec::from_str(spec).unwrap().gen(Language::Rust);
+        assert!(rust.contains("allow_negative_numbers = true"));
+
+        // -5 could be an option 5, or one asked to be taken as such
+        for spec in &[
+            spec.replace("short = \"o\"", "short = \"5\""),
+            format!("negative_numbers = false\n{}", spec),
+        ] {
+            let spec = Spec::from_str(spec).unwrap();
+            assert!(!spec.gen(Language::C).contains("is_negative_number"));
+            assert!(!spec.gen(Language::Cpp).contains("is_negative_number"));
+            assert!(!spec.gen(Language::Rust).contains("allow_negative_numbers"));
         }
-        main.push_str(
-            ");\n\n\
-                      \t/* call your code here */\n\
-                      \treturn 0;\n}\n",
+    }
+
+    #[test]
+    fn copy_strings() {
+        let spec = r#"
+            copy_strings = true
+            [[positional]]
+            c_var = "files"
+            c_type = "char*"
+            help_name = "FILE"
+            multi = true
+            [[non_positional]]
+            c_var = "name"
+            c_type = "char*"
+            long = "name"
+            [[non_positional]]
+            c_var = "jobs"
+            c_type = "int"
+            long = "jobs"
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains(
+            "\t*name = copy_string(*name);\n\
+             \t*files = copy_strings(*files, *files__size);\n\
+             }\n"
+        ));
+        assert!(c.contains("void free_args(char *name, char **files, size_t files__size) {\n"));
+        assert!(c.contains("\tfree_args(name, files, files__size);\n\treturn 0;\n"));
+        assert!(!c.contains("copy_string(*jobs)"));
+
+        let c = Spec::from_str(&spec.replace("copy_strings = true", ""))
+            .unwrap()
+            .gen(Language::C);
+        assert!(!c.contains("copy_string"));
+        assert!(!c.contains("free_args"));
+    }
+
+    #[test]
+    fn selftest() {
+        let spec = r#"
+            selftest = true
+            usage_exit_code = 64
+            [[positional]]
+            c_var = "input"
+            c_type = "char*"
+            help_name = "FILE"
+            required = true
+            [[non_positional]]
+            c_var = "jobs"
+            c_type = "int"
+            long = "jobs"
+            default = "4"
+            [[non_positional]]
+            c_var = "quiet"
+            c_type = "int"
+            long = "quiet"
+            flag = true
+            conflicts_with = ["jobs"]
+            "#;
+        let mut parsed = Spec::from_str(spec).unwrap();
+        let c = parsed.gen(Language::C);
+        assert!(c.contains("#include<sys/wait.h>\n"));
+        assert!(c.contains("\tif (argc == 2 && strcmp(argv[1], \"--argen-selftest\") == 0) {\n"));
+        assert!(c.contains(
+            "static const char *const argen_selftest_0_args[] = {\"selftest\", \"value\"};\n"
+        ));
+        assert!(c.contains(
+            "\treturn jobs != (int)4\n\t\t|| quiet != 0\n\t\t|| input == NULL || strcmp(input, \"value\") != 0;\n"
+        ));
+        assert!(c.contains("\t\t{\"FILE is required\", argen_selftest_1, 64},\n"));
+        // options with conflicts are left to the defaults check
+        assert!(!c.contains("is read"));
+
+        // asked for on the command line, the windows target leaves the self-test out
+        parsed.set_c_target(CTarget::Windows);
+        assert!(!parsed.gen(Language::C).contains("selftest"));
+        let err = spec_err(&format!("{}[style]\ntarget = \"windows\"\n", spec));
+        assert!(matches!(err, ValidationError::SelftestNeedsPosix));
+
+        let err = spec_err(
+            r#"
+            selftest = true
+            [[non_positional]]
+            c_var = "port"
+            c_type = "int"
+            long = "port"
+            required = true
+            validator = "check_port"
+            "#,
         );
-        main
-    }
-    /// Generates everything
-    pub fn gen(&self) -> String {
-        let h = self.cgen_headers();
-        let usage = self.cgen_usage();
-        let body = self.cgen_decl();
-        let main = self.cgen_main();
-        format!("{}\n\n{}\n{}\n{}", h, usage, body, main)
-    }
-    /// Writes generate C code to a writer.
-    pub fn writeout<W>(&self, wrt: &mut W)
-    where
-        W: Write,
-    {
-        wrt.write_all(self.gen().as_bytes())
-            .expect("write generated code to file")
+        assert!(matches!(err, ValidationError::SelftestNeedsValue(_)));
+    }
+
+    #[test]
+    fn positional_arity() {
+        let spec = r#"
+            non_positional = []
+            [[positional]]
+            c_var = "input"
+            c_type = "char*"
+            help_name = "INPUT"
+            required = true
+            [[positional]]
+            c_var = "words"
+            c_type = "char*"
+            help_name = "WORD"
+            multi = true
+            min_values = 2
+            max_values = 4
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains("usage: %s [options] INPUT WORD WORD [WORD...]\\n%s"));
+        assert!(c.contains("\tif (argc-optind < 3) {\n"));
+        assert!(c.contains(
+            "\tif (argc-optind > 5) {\n\
+             \t\tfprintf(stderr, \"%s: unexpected argument: %s\\n\", progname, argv[optind+5]);\n"
+        ));
+        let c = Spec::from_str(&spec.replace("max_values = 4", ""))
+            .unwrap()
+            .gen(Language::C);
+        assert!(c.contains("usage: %s [options] INPUT WORD WORD...\\n%s"));
+        assert!(!c.contains("unexpected argument"));
+        let c = Spec::from_str(&spec.replace("max_values = 4", "max_values = 2"))
+            .unwrap()
+            .gen(Language::C);
+        assert!(c.contains("usage: %s [options] INPUT WORD WORD\\n%s"));
+
+        let err = spec_err(&spec.replace("max_values = 4", "max_values = 1"));
+        assert!(matches!(err, ValidationError::InvalidArity(p) if p == "WORD"));
+        let err = spec_err(&spec.replace("multi = true", ""));
+        assert!(matches!(err, ValidationError::ArityNotMulti(p) if p == "WORD"));
+        let err = spec_err(&spec.replace("max_values = 4", "default = \"x\""));
+        assert!(matches!(err, ValidationError::RequiredHasDefault(p) if p == "WORD"));
+    }
+
+    #[test]
+    fn exit_codes() {
+        let spec = r#"
+            usage_exit_code = 64
+            error_exit_code = 66
+            response_files = true
+            positional = []
+            [[non_positional]]
+            c_var = "name"
+            c_type = "char*"
+            long = "name"
+            required = true
+            "#;
+        let c = Spec::from_str(spec).unwrap().gen(Language::C);
+        assert!(c.contains("\t\tcase 'h':\n\t\t\tusage(stdout, progname);\n\t\t\texit(0);\n"));
+        assert!(c.contains("\t\tdefault:\n\t\t\tusage(stderr, progname);\n\t\t\texit(64);\n"));
+        assert!(c.contains("\tif (!name__isset) {\n\t\tusage(stderr, progname);\n\t\texit(64);\n"));
+        assert!(c.contains(
+            "cannot read @%s: %s\\n\", progname, path, strerror(errno));\n\t\texit(66);\n"
+        ));
+        assert!(!c.contains("exit(1)"));
+        let cpp = Spec::from_str(spec).unwrap().gen(Language::Cpp);
+        assert!(cpp.contains("\t\tusage(std::cerr, e.progname);\n\t\treturn 64;\n"));
+
+        let c = Spec::from_str(&spec.replace("response_files = true", "usage_to_stderr = false"))
+            .unwrap()
+            .gen(Language::C);
+        assert!(c.contains("\t\tdefault:\n\t\t\tusage(stdout, progname);\n\t\t\texit(64);\n"));
+
+        let err = spec_err(&spec.replace("= 66", "= 256"));
+        assert!(matches!(err, ValidationError::InvalidExitCode(f, 256) if f == "error_exit_code"));
+        let err = spec_err(&spec.replace("= 64", "= 0"));
+        assert!(matches!(err, ValidationError::InvalidExitCode(f, 0) if f == "usage_exit_code"));
+    }
+
+    #[test]
+    fn help_groups() {
+        let spec = r#"
+            positional = []
+            [[non_positional]]
+            c_var = "quiet"
+            c_type = "int"
+            long = "quiet"
+            flag = true
+            [[non_positional]]
+            c_var = "output"
+            c_type = "char*"
+            long = "output"
+            group = "Output options"
+            [[non_positional]]
+            c_var = "jobs"
+            c_type = "int"
+            long = "jobs"
+            group = "Performance"
+            [[non_positional]]
+            c_var = "color"
+            c_type = "int"
+            long = "color"
+            flag = true
+            group = "Output options"
+            "#;
+        let spec = Spec::from_str(spec).unwrap();
+        assert_eq!(
+            spec.help_text(),
+            vec![
+                "  -h, --help          print this usage and exit",
+                "      --quiet",
+                "",
+                "Output options:",
+                "      --output <STR>",
+                "      --color",
+                "",
+                "Performance:",
+                "      --jobs <NUM>",
+            ]
+        );
+        let py = spec.gen(Language::Python);
+        assert!(py.contains(
+            "    groups = [parser.add_argument_group('Output options'), \
+             parser.add_argument_group('Performance')]\n"
+        ));
+        assert!(py.contains("    groups[1].add_argument('--jobs', "));
+        assert!(py.contains("    parser.add_argument('--quiet', "));
+        let rs = spec.gen(Language::Rust);
+        assert!(rs.contains("help_heading = \"Performance\""));
+        let man = spec.gen_manpage("demo");
+        assert!(man.contains(".SS Output options\n"));
+    }
+
+    #[test]
+    fn validators() {
+        let c = Spec::from_str(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "port"
+            c_type = "int"
+            long = "port"
+            validator = "validate_port"
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains("int validate_port(const char *value, char *errbuf, size_t errlen);\n"));
+        assert!(c.contains(
+            "\t\t\trun_validator(progname, \"--port\", 1, &optarg, validate_port);\n\
+             \t\t\t*port = parse_int(progname, \"--port\", optarg);\n"
+        ));
+
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "port"
+            c_type = "int"
+            long = "port"
+            validator = "port"
+            "#,
+        );
+        assert!(matches!(err, ValidationError::InvalidValidator(_, _)));
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "quiet"
+            c_type = "int"
+            long = "quiet"
+            flag = true
+            validator = "validate_quiet"
+            "#,
+        );
+        assert!(matches!(err, ValidationError::ValidatorOnFlag(_)));
+    }
+
+    #[test]
+    fn custom_types() {
+        let c = Spec::from_str(
+            r#"
+            includes = ["netinet/in.h", "\"local.h\""]
+            positional = []
+            [[non_positional]]
+            c_var = "addr"
+            c_type = "custom"
+            c_decl_type = "struct in_addr"
+            converter = "parse_ipaddr"
+            long = "addr"
+            "#,
+        )
+        .unwrap()
+        .gen(Language::C);
+        assert!(c.contains("#include<netinet/in.h>\n#include\"local.h\"\n"));
+        assert!(c.contains(
+            "int parse_ipaddr(const char *value, struct in_addr *out, char *errbuf, size_t errlen);\n"
+        ));
+        assert!(c.contains("void parse_args(int argc, char **argv, struct in_addr *addr) {"));
+        assert!(c.contains("\tmemset(addr, 0, sizeof(*addr));\n"));
+        assert!(c.contains(
+            "\t\t\tif (parse_ipaddr(optarg, addr, errbuf, sizeof(errbuf)) != 0) {\n\
+             \t\t\t\treject_value(progname, \"--addr\", optarg, errbuf);\n"
+        ));
+        assert!(c.contains("\tstruct in_addr addr;\n"));
+
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "addr"
+            c_type = "custom"
+            long = "addr"
+            converter = "parse_ipaddr"
+            "#,
+        );
+        assert!(matches!(err, ValidationError::CustomWithoutConverter(_)));
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "addr"
+            c_type = "char*"
+            long = "addr"
+            converter = "parse_ipaddr"
+            "#,
+        );
+        assert!(matches!(err, ValidationError::ConverterNotCustom(_)));
+        let err = spec_err(
+            r#"
+            positional = []
+            [[non_positional]]
+            c_var = "addr"
+            c_type = "custom"
+            c_decl_type = "struct in_addr"
+            converter = "parse_ipaddr"
+            long = "addr"
+            [[non_positional]]
+            c_var = "port"
+            c_type = "custom"
+            c_decl_type = "unsigned short"
+            converter = "parse_ipaddr"
+            long = "port"
+            "#,
+        );
+        assert!(matches!(err, ValidationError::InvalidConverter(_, _)));
+    }
+
+    #[test]
+    fn version_option() {
+        let s = Spec::from_str(
+            r#"
+            program_name = "demo"
+            version = "1.0"
+            positional = []
+            non_positional = []
+            "#,
+        )
+        .unwrap();
+        let c = s.gen(Language::C);
+        assert!(c.contains(r#"const char *progname = "demo";"#));
+        assert!(c.contains(r#"{"version", 0, 0, 'V'},"#));
+        assert!(c.contains(r#"printf("%s 1.0\n", progname);"#));
+    }
+
+    #[test]
+    fn quoted_spec_strings() {
+        let s = Spec::from_str(
+            r#"
+            [[positional]]
+            c_var = "p"
+            c_type = "char*"
+            help_name = "100%"
+            help_descr = "a \"quoted\" path"
+            default = "C:\\tmp"
+
+            [[non_positional]]
+            c_var = "n"
+            c_type = "char*"
+            long = "name"
+            help_descr = "back\\slash"
+            "#,
+        )
+        .unwrap();
+        let c = s.gen(Language::C);
+        assert!(c.contains(r#"[options] [100%%]\n%s""#));
+        assert!(c.contains(r#"a \"quoted\" path"#));
+        assert!(c.contains(r#"static char* p__default = "C:\\tmp";"#));
+        assert!(c.contains(r"back\\slash"));
     }
 }
@@ -0,0 +1,415 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{Alias, CType, Group, NonPositionalItem, PositionalItem, Spec, ValidationError};
+
+/// A non-positional option, as built by SpecBuilder::option.
+pub type Opt = NonPositionalItem;
+/// A positional argument, as built by SpecBuilder::positional.
+pub type Positional = PositionalItem;
+
+/// Builds a Spec in code, for tools that generate specs rather than read them from a file.
+///
+/// ```
+/// use argen::{Opt, Positional, SpecBuilder};
+///
+/// let spec = SpecBuilder::new()
+///     .program_name("serve")
+///     .positional(Positional::new("root").required())
+///     .option(Opt::new("port").int32().default("80"))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Default)]
+pub struct SpecBuilder {
+    spec: Spec,
+}
+
+impl SpecBuilder {
+    pub fn new() -> SpecBuilder {
+        SpecBuilder::default()
+    }
+    pub fn program_name(mut self, name: &str) -> SpecBuilder {
+        self.spec.program_name = Some(name.to_owned());
+        self
+    }
+    pub fn version(mut self, version: &str) -> SpecBuilder {
+        self.spec.version = Some(version.to_owned());
+        self
+    }
+    pub fn about(mut self, about: &str) -> SpecBuilder {
+        self.spec.about = Some(about.to_owned());
+        self
+    }
+    pub fn strict_order(mut self) -> SpecBuilder {
+        self.spec.strict_order = Some(true);
+        self
+    }
+    pub fn help_width(mut self, width: usize) -> SpecBuilder {
+        self.spec.help_width = Some(width);
+        self
+    }
+    pub fn response_files(mut self) -> SpecBuilder {
+        self.spec.response_files = Some(true);
+        self
+    }
+    pub fn allow_abbrev(mut self) -> SpecBuilder {
+        self.spec.allow_abbrev = Some(true);
+        self
+    }
+    /// Matches every long option name whatever its case.
+    pub fn case_insensitive(mut self) -> SpecBuilder {
+        self.spec.case_insensitive = Some(true);
+        self
+    }
+    /// Takes arguments such as -5 as options rather than negative numbers.
+    pub fn no_negative_numbers(mut self) -> SpecBuilder {
+        self.spec.negative_numbers = Some(false);
+        self
+    }
+    /// Copies string values out of argv in generated C, which then has free_args to free them.
+    pub fn copy_strings(mut self) -> SpecBuilder {
+        self.spec.copy_strings = Some(true);
+        self
+    }
+    /// Has parse_args in generated C take --argen-selftest, which runs checks of the parser and
+    /// exits 0 if they pass.
+    pub fn selftest(mut self) -> SpecBuilder {
+        self.spec.selftest = Some(true);
+        self
+    }
+    /// Sets the exit status for a wrong command line and for other errors, both 1 by default.
+    pub fn exit_codes(mut self, usage: i32, error: i32) -> SpecBuilder {
+        self.spec.usage_exit_code = Some(usage);
+        self.spec.error_exit_code = Some(error);
+        self
+    }
+    /// Adds a positional argument after the ones already added.
+    pub fn positional(mut self, pi: PositionalItem) -> SpecBuilder {
+        self.spec.positional.push(pi);
+        self
+    }
+    pub fn option(mut self, npi: NonPositionalItem) -> SpecBuilder {
+        self.spec.non_positional.push(npi);
+        self
+    }
+    pub fn group(mut self, group: Group) -> SpecBuilder {
+        self.spec.groups.get_or_insert_with(Vec::new).push(group);
+        self
+    }
+    /// Adds a header to include in generated C, as "<netinet/in.h>" or "\"local.h\"".
+    pub fn include(mut self, header: &str) -> SpecBuilder {
+        self.spec
+            .includes
+            .get_or_insert_with(Vec::new)
+            .push(header.to_owned());
+        self
+    }
+    /// Checks the spec as reading it from a file would.
+    pub fn build(self) -> Result<Spec, ValidationError> {
+        self.spec.validate()?;
+        Ok(self.spec)
+    }
+}
+
+impl PositionalItem {
+    /// A char* argument stored in c_var, shown in help as c_var in upper case.
+    pub fn new(c_var: &str) -> PositionalItem {
+        PositionalItem {
+            c_var: c_var.to_owned(),
+            c_type: CType::Chars,
+            help_name: c_var.to_uppercase(),
+            help_descr: None,
+            required: None,
+            default: None,
+            multi: None,
+            min_values: None,
+            max_values: None,
+            validator: None,
+            converter: None,
+            c_decl_type: None,
+        }
+    }
+    pub fn c_type(mut self, c_type: CType) -> PositionalItem {
+        self.c_type = c_type;
+        self
+    }
+    pub fn int32(self) -> PositionalItem {
+        self.c_type(CType::Int)
+    }
+    pub fn help_name(mut self, help_name: &str) -> PositionalItem {
+        self.help_name = help_name.to_owned();
+        self
+    }
+    pub fn help_descr(mut self, help_descr: &str) -> PositionalItem {
+        self.help_descr = Some(help_descr.to_owned());
+        self
+    }
+    pub fn required(mut self) -> PositionalItem {
+        self.required = Some(true);
+        self
+    }
+    pub fn default(mut self, default: &str) -> PositionalItem {
+        self.default = Some(default.to_owned());
+        self
+    }
+    pub fn multi(mut self) -> PositionalItem {
+        self.multi = Some(true);
+        self
+    }
+    /// Makes the argument multi, taking from min to max values, or any number above min.
+    pub fn values(mut self, min: usize, max: Option<usize>) -> PositionalItem {
+        self.min_values = Some(min);
+        self.max_values = max;
+        self.multi()
+    }
+    /// Checks each value with the user-supplied C function named validator.
+    pub fn validator(mut self, validator: &str) -> PositionalItem {
+        self.validator = Some(validator.to_owned());
+        self
+    }
+    /// Makes the argument a c_decl_type, as in "struct in_addr", read by the user-supplied C
+    /// function named converter.
+    pub fn custom(mut self, c_decl_type: &str, converter: &str) -> PositionalItem {
+        self.c_decl_type = Some(c_decl_type.to_owned());
+        self.converter = Some(converter.to_owned());
+        self.c_type(CType::Custom)
+    }
+}
+
+impl NonPositionalItem {
+    /// A char* option named --long, stored in c_var long with dashes made underscores.
+    pub fn new(long: &str) -> NonPositionalItem {
+        NonPositionalItem {
+            c_var: long.replace('-', "_"),
+            c_type: CType::Chars,
+            long: long.to_owned(),
+            help_name: None,
+            help_descr: None,
+            aliases: None,
+            short: None,
+            required: None,
+            default: None,
+            flag: None,
+            count: None,
+            map: None,
+            requires: None,
+            conflicts_with: None,
+            hidden: None,
+            deprecated: None,
+            case_insensitive: None,
+            group: None,
+            validator: None,
+            converter: None,
+            c_decl_type: None,
+        }
+    }
+    pub fn c_var(mut self, c_var: &str) -> NonPositionalItem {
+        self.c_var = c_var.to_owned();
+        self
+    }
+    pub fn c_type(mut self, c_type: CType) -> NonPositionalItem {
+        self.c_type = c_type;
+        self
+    }
+    pub fn int32(self) -> NonPositionalItem {
+        self.c_type(CType::Int)
+    }
+    pub fn short(mut self, short: char) -> NonPositionalItem {
+        self.short = Some(short.to_string());
+        self
+    }
+    pub fn alias(mut self, alias: &str) -> NonPositionalItem {
+        self.aliases
+            .get_or_insert_with(Vec::new)
+            .push(Alias::Name(alias.to_owned()));
+        self
+    }
+    /// Adds an alias that still works but warns to use the long name instead.
+    pub fn deprecated_alias(mut self, alias: &str) -> NonPositionalItem {
+        self.aliases
+            .get_or_insert_with(Vec::new)
+            .push(Alias::Table {
+                name: alias.to_owned(),
+                deprecated: Some(true),
+            });
+        self
+    }
+    pub fn help_name(mut self, help_name: &str) -> NonPositionalItem {
+        self.help_name = Some(help_name.to_owned());
+        self
+    }
+    pub fn help_descr(mut self, help_descr: &str) -> NonPositionalItem {
+        self.help_descr = Some(help_descr.to_owned());
+        self
+    }
+    pub fn required(mut self) -> NonPositionalItem {
+        self.required = Some(true);
+        self
+    }
+    pub fn default(mut self, default: &str) -> NonPositionalItem {
+        self.default = Some(default.to_owned());
+        self
+    }
+    /// Makes the option take no argument. Flags are always int.
+    pub fn flag(mut self) -> NonPositionalItem {
+        self.flag = Some(true);
+        self.int32()
+    }
+    /// Makes the option a flag that counts how many times it was given.
+    pub fn count(mut self) -> NonPositionalItem {
+        self.count = Some(true);
+        self.int32()
+    }
+    pub fn map(mut self) -> NonPositionalItem {
+        self.map = Some(true);
+        self
+    }
+    pub fn requires(mut self, long: &str) -> NonPositionalItem {
+        self.requires
+            .get_or_insert_with(Vec::new)
+            .push(long.to_owned());
+        self
+    }
+    pub fn conflicts_with(mut self, long: &str) -> NonPositionalItem {
+        self.conflicts_with
+            .get_or_insert_with(Vec::new)
+            .push(long.to_owned());
+        self
+    }
+    /// Leaves the option out of usage while still parsing it.
+    pub fn hidden(mut self) -> NonPositionalItem {
+        self.hidden = Some(true);
+        self
+    }
+    /// Warns with msg, as in "use --new-name instead", whenever the option is given.
+    pub fn deprecated(mut self, msg: &str) -> NonPositionalItem {
+        self.deprecated = Some(msg.to_owned());
+        self
+    }
+    /// Lists the option in help under the section with the given title.
+    pub fn group(mut self, title: &str) -> NonPositionalItem {
+        self.group = Some(title.to_owned());
+        self
+    }
+    /// Matches the option's long names whatever their case.
+    pub fn case_insensitive(mut self) -> NonPositionalItem {
+        self.case_insensitive = Some(true);
+        self
+    }
+    /// Checks each value with the user-supplied C function named validator.
+    pub fn validator(mut self, validator: &str) -> NonPositionalItem {
+        self.validator = Some(validator.to_owned());
+        self
+    }
+    /// Makes the option take a c_decl_type, as in "struct in_addr", read by the user-supplied
+    /// C function named converter.
+    pub fn custom(mut self, c_decl_type: &str, converter: &str) -> NonPositionalItem {
+        self.c_decl_type = Some(c_decl_type.to_owned());
+        self.converter = Some(converter.to_owned());
+        self.c_type(CType::Custom)
+    }
+}
+
+impl Group {
+    /// A group of the options with the given long names. Make it exclusive, required, or both.
+    pub fn new(options: &[&str]) -> Group {
+        Group {
+            options: options.iter().map(|long| long.to_string()).collect(),
+            exclusive: None,
+            required: None,
+        }
+    }
+    pub fn exclusive(mut self) -> Group {
+        self.exclusive = Some(true);
+        self
+    }
+    pub fn required(mut self) -> Group {
+        self.required = Some(true);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Group, Opt, Positional, SpecBuilder};
+    use crate::codegen::{Language, Spec, SpecFormat, ValidationError};
+    use std::str::FromStr;
+
+    fn example() -> SpecBuilder {
+        SpecBuilder::new()
+            .program_name("serve")
+            .positional(Positional::new("root").help_name("DIR").required())
+            .option(Opt::new("port").short('p').int32().default("80"))
+            .option(Opt::new("verbose").short('v').count())
+            .option(Opt::new("quiet").flag())
+            .group(Group::new(&["verbose", "quiet"]).exclusive())
+    }
+
+    #[test]
+    fn builds_same_as_toml() {
+        let built = example().build().unwrap();
+        let read = Spec::from_str(
+            r#"
+            program_name = "serve"
+            [[positional]]
+            c_var = "root"
+            c_type = "char*"
+            help_name = "DIR"
+            required = true
+            [[non_positional]]
+            c_var = "port"
+            c_type = "int"
+            long = "port"
+            short = "p"
+            default = "80"
+            [[non_positional]]
+            c_var = "verbose"
+            c_type = "int"
+            long = "verbose"
+            short = "v"
+            count = true
+            [[non_positional]]
+            c_var = "quiet"
+            c_type = "int"
+            long = "quiet"
+            flag = true
+            [[groups]]
+            options = ["verbose", "quiet"]
+            exclusive = true
+            "#,
+        )
+        .unwrap();
+        assert_eq!(built.gen(Language::C), read.gen(Language::C));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let built = example().build().unwrap();
+        let json = serde_json::to_string(&built).unwrap();
+        assert!(!json.contains("null"));
+        let read = Spec::from_reader(json.as_bytes(), SpecFormat::Json).unwrap();
+        assert_eq!(built.gen(Language::C), read.gen(Language::C));
+    }
+
+    #[test]
+    fn build_validates() {
+        let err = SpecBuilder::new()
+            .option(Opt::new("name").required().default("x"))
+            .build();
+        assert!(matches!(err, Err(ValidationError::RequiredHasDefault(_))));
+    }
+}
@@ -0,0 +1,1573 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::regions::user_region;
+use super::{
+    c_format_quote, c_quote, wrap, CStd, CTarget, CodeGenerator, NonPositionalItem, PositionalItem,
+    Spec, DEFAULT_HELP_WIDTH, RESPONSE_FILE_DEPTH,
+};
+use std::collections::HashSet;
+
+const INCLUDES: [&str; 4] = ["stdlib", "stdio", "string", "getopt"];
+
+/// Generates C that parses with getopt_long(3).
+pub struct C;
+
+impl Spec {
+    /// Whether parse_args needs to remember if npi was given, to check it or fill in a value
+    /// after the loop. Setting it otherwise would only earn an unused variable warning.
+    fn c_tracks_isset(&self, npi: &NonPositionalItem, constrained: &HashSet<&str>) -> bool {
+        npi.is_required()
+            || npi.has_default()
+            || npi.is_flag()
+            || npi.is_map()
+            || constrained.contains(npi.long.as_str())
+            || self.config_file.is_some()
+    }
+    /// Creates the necessary headers in C.
+    fn cgen_headers(&self) -> String {
+        let windows = self.c_target() == CTarget::Windows;
+        let mut includes = INCLUDES.to_vec();
+        if windows {
+            // the parser brings its own getopt_long
+            includes.retain(|&header| header != "getopt");
+        }
+        let reads_files = self.has_response_files() || self.config_file.is_some();
+        if reads_files {
+            includes.push("ctype");
+        }
+        if reads_files || self.has_int_arg() {
+            includes.push("errno");
+        }
+        if self.has_int_arg() {
+            includes.push("limits");
+        }
+        if self.c_std() != CStd::C89 {
+            includes.push("stdbool");
+        }
+        if self.text_domain.is_some() {
+            includes.push("libintl");
+            includes.push("locale");
+        }
+        if self.style().has_wmain() {
+            includes.push("windows");
+        }
+        if self.has_selftest() {
+            includes.push("unistd");
+            includes.push("sys/wait");
+        }
+        let mut headers = String::new();
+        if windows {
+            // MSVC otherwise warns that strerror and fopen are unsafe
+            headers.push_str("#define _CRT_SECURE_NO_WARNINGS\n");
+        }
+        if self.style().has_wmain() {
+            headers.push_str("#define WIN32_LEAN_AND_MEAN\n");
+        }
+        headers.extend(includes.iter().map(|s| format!("#include<{}.h>\n", s)));
+        // given as <sys/socket.h> or "local.h", with bare names taken as system headers
+        for header in self.includes.iter().flatten() {
+            if header.starts_with('<') || header.starts_with('"') {
+                headers.push_str(&format!("#include{}\n", header));
+            } else {
+                headers.push_str(&format!("#include<{}>\n", header));
+            }
+        }
+        // after the user's headers, in case one of them defines _ already
+        if self.text_domain.is_some() {
+            headers.push_str("#ifndef _\n#define _(msgid) gettext(msgid)\n#endif\n");
+        }
+        headers
+    }
+    /// Creates getopt_long and its globals for the windows target, whose C library has none. It
+    /// follows glibc as far as the parser relies on it: a leading + or - in the optstring, a
+    /// leading : for missing arguments, clustered short options and unambiguous prefixes of
+    /// long ones.
+    fn cgen_getopt(&self) -> String {
+        if self.c_target() != CTarget::Windows {
+            return String::new();
+        }
+        String::from(
+            "struct option {\n\
+             \tconst char *name;\n\
+             \tint has_arg;\n\
+             \tint *flag;\n\
+             \tint val;\n\
+             };\n\
+             \n\
+             #define no_argument 0\n\
+             #define required_argument 1\n\
+             \n\
+             static char *optarg;\n\
+             static int optind = 1, opterr = 1, optopt;\n\
+             \n\
+             static int getopt_long_name(int argc, char **argv, const struct option *longopts, int *longindex) {\n\
+             \tchar *name = argv[optind++] + 2;\n\
+             \tsize_t len = strcspn(name, \"=\");\n\
+             \tint found = -1, ambiguous = 0, i;\n\
+             \tfor (i = 0; longopts[i].name; i++) {\n\
+             \t\tif (strncmp(longopts[i].name, name, len) != 0) {\n\
+             \t\t\tcontinue;\n\
+             \t\t}\n\
+             \t\tif (strlen(longopts[i].name) == len) {\n\
+             \t\t\tfound = i;\n\
+             \t\t\tambiguous = 0;\n\
+             \t\t\tbreak;\n\
+             \t\t}\n\
+             \t\tif (found < 0) {\n\
+             \t\t\tfound = i;\n\
+             \t\t} else if (longopts[i].has_arg != longopts[found].has_arg || longopts[i].val != longopts[found].val) {\n\
+             \t\t\tambiguous = 1;\n\
+             \t\t}\n\
+             \t}\n\
+             \tif (found < 0 || ambiguous) {\n\
+             \t\toptopt = 0;\n\
+             \t\treturn '?';\n\
+             \t}\n\
+             \tif (longindex) {\n\
+             \t\t*longindex = found;\n\
+             \t}\n\
+             \tif (name[len] == '=') {\n\
+             \t\tif (longopts[found].has_arg == no_argument) {\n\
+             \t\t\toptopt = longopts[found].val;\n\
+             \t\t\treturn '?';\n\
+             \t\t}\n\
+             \t\toptarg = name + len + 1;\n\
+             \t} else if (longopts[found].has_arg == required_argument) {\n\
+             \t\tif (optind >= argc) {\n\
+             \t\t\toptopt = longopts[found].val;\n\
+             \t\t\treturn ':';\n\
+             \t\t}\n\
+             \t\toptarg = argv[optind++];\n\
+             \t}\n\
+             \tif (longopts[found].flag) {\n\
+             \t\t*longopts[found].flag = longopts[found].val;\n\
+             \t\treturn 0;\n\
+             \t}\n\
+             \treturn longopts[found].val;\n\
+             }\n\
+             \n\
+             static int getopt_long(int argc, char **argv, const char *optstring,\n\
+             \t                       const struct option *longopts, int *longindex) {\n\
+             \tstatic char *next = NULL;\n\
+             \tconst char *shorts = optstring + strspn(optstring, \"+-:\");\n\
+             \tconst char *spec;\n\
+             \tint c;\n\
+             \toptarg = NULL;\n\
+             \tif (!next || !*next) {\n\
+             \t\tnext = NULL;\n\
+             \t\tif (optind >= argc) {\n\
+             \t\t\treturn -1;\n\
+             \t\t}\n\
+             \t\tif (argv[optind][0] != '-' || argv[optind][1] == '\\0') {\n\
+             \t\t\tif (optstring[0] != '-') {\n\
+             \t\t\t\treturn -1;\n\
+             \t\t\t}\n\
+             \t\t\toptarg = argv[optind++];\n\
+             \t\t\treturn 1;\n\
+             \t\t}\n\
+             \t\tif (strcmp(argv[optind], \"--\") == 0) {\n\
+             \t\t\toptind++;\n\
+             \t\t\treturn -1;\n\
+             \t\t}\n\
+             \t\tif (argv[optind][1] == '-') {\n\
+             \t\t\treturn getopt_long_name(argc, argv, longopts, longindex);\n\
+             \t\t}\n\
+             \t\tnext = argv[optind] + 1;\n\
+             \t}\n\
+             \tc = (unsigned char)*next++;\n\
+             \tspec = c == ':' ? NULL : strchr(shorts, c);\n\
+             \tif (!*next) {\n\
+             \t\toptind++;\n\
+             \t}\n\
+             \tif (!spec) {\n\
+             \t\toptopt = c;\n\
+             \t\treturn '?';\n\
+             \t}\n\
+             \tif (spec[1] == ':') {\n\
+             \t\tif (*next) {\n\
+             \t\t\toptarg = next;\n\
+             \t\t\toptind++;\n\
+             \t\t} else if (optind < argc) {\n\
+             \t\t\toptarg = argv[optind++];\n\
+             \t\t} else {\n\
+             \t\t\toptopt = c;\n\
+             \t\t\treturn ':';\n\
+             \t\t}\n\
+             \t\tnext = NULL;\n\
+             \t}\n\
+             \treturn c;\n\
+             }\n\n",
+        )
+    }
+    /// A Doxygen comment of the given paragraphs, or nothing if the spec turns docs off. Lines
+    /// are wrapped to the default help width, with the rest of a @command indented.
+    fn cgen_doc(&self, paragraphs: &[String]) -> String {
+        if !self.has_docs() {
+            return String::new();
+        }
+        let mut doc = String::from("/**\n");
+        for paragraph in paragraphs {
+            let indent = if paragraph.starts_with('@') { "  " } else { "" };
+            for (i, line) in wrap(paragraph, DEFAULT_HELP_WIDTH - 3 - indent.len())
+                .iter()
+                .enumerate()
+            {
+                match (i, line.is_empty()) {
+                    (_, true) => doc.push_str(" *\n"),
+                    (0, false) => doc.push_str(&format!(" * {}\n", line)),
+                    _ => doc.push_str(&format!(" * {}{}\n", indent, line)),
+                }
+            }
+        }
+        doc.push_str(" */\n");
+        doc
+    }
+    /// Creates the usage function in C. With a text_domain, the usage line and the help below it
+    /// are each looked up in the message catalog as a whole.
+    fn cgen_usage(&self) -> String {
+        let help: String = self
+            .help_text()
+            .iter()
+            .map(|line| format!("\t             \"{}\\n\"\n", c_quote(line)))
+            .collect();
+        let doc = self.cgen_doc(&[
+            String::from("Prints the usage line and help for each argument."),
+            String::new(),
+            String::from("@param out Stream to print to, stdout for --help."),
+            String::from("@param progname Name of the program, shown in the usage line."),
+        ]);
+        let usage = format!(
+            "\"usage: %s [options]{}\\n%s\"",
+            c_format_quote(&self.usage_synopsis())
+        );
+        if self.text_domain.is_some() {
+            format!(
+                "{}static void usage(FILE *out, const char *progname) {{\n\
+                 \tfprintf(out, _({}), progname, _(\n\
+                 {}\t             ));\n\
+                 }}\n",
+                doc, usage, help
+            )
+        } else {
+            format!(
+                "{}static void usage(FILE *out, const char *progname) {{\n\
+                 \tfprintf(out, {}, progname,\n\
+                 {}\t             );\n\
+                 }}\n",
+                doc, usage, help
+            )
+        }
+    }
+    /// Creates the unknown_option function in C, which reports an option getopt did not recognize,
+    /// or a flag given a value, and suggests the closest long name.
+    fn cgen_unknown_option(&self) -> String {
+        let mut names: Vec<&str> = self
+            .non_positional
+            .iter()
+            .filter(|npi| !npi.is_hidden() && npi.deprecated.is_none())
+            .flat_map(|npi| npi.current_long_names())
+            .map(String::as_str)
+            .collect();
+        names.push("help");
+        if self.version.is_some() {
+            names.push("version");
+        }
+        let longest = names.iter().map(|name| name.len()).max().unwrap_or(0);
+        let std = self.c_std();
+        let table: String = names
+            .iter()
+            .map(|name| format!("\t\t\"{}\",\n", c_quote(name)))
+            .collect();
+        format!(
+            "static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {{\n\
+             \tsize_t row[{}];\n\
+             \tsize_t known_len = strlen(known);\n\
+             {}\
+             \tfor ({}; j <= known_len; j++) {{\n\
+             \t\trow[j] = j;\n\
+             \t}}\n\
+             \tfor ({}; i <= typed_len; i++) {{\n\
+             \t\tsize_t diag = row[0];\n\
+             \t\trow[0] = i;\n\
+             \t\tfor ({}; j <= known_len; j++) {{\n\
+             \t\t\tsize_t up = row[j];\n\
+             \t\t\tsize_t best = diag + (typed[i-1] != known[j-1]);\n\
+             \t\t\tif (up + 1 < best) {{\n\
+             \t\t\t\tbest = up + 1;\n\
+             \t\t\t}}\n\
+             \t\t\tif (row[j-1] + 1 < best) {{\n\
+             \t\t\t\tbest = row[j-1] + 1;\n\
+             \t\t\t}}\n\
+             \t\t\trow[j] = best;\n\
+             \t\t\tdiag = up;\n\
+             \t\t}}\n\
+             \t}}\n\
+             \treturn row[known_len];\n\
+             }}\n\
+             \n\
+             static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {{\n\
+             \tstatic const char *names[] = {{\n\
+             {}\
+             \t}};\n\
+             \tconst char *typed = arg + strspn(arg, \"-\");\n\
+             \tsize_t typed_len = strcspn(typed, \"=\");\n\
+             \tconst char *best = NULL;\n\
+             \tsize_t best_dist = 0;\n\
+             {}{}\
+             \tif (opt && strncmp(arg, \"--\", 2) == 0 && typed[typed_len] == '=') {{\n\
+             \t\tfor ({}; longopts[i].name; i++) {{\n\
+             \t\t\tif (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {{\n\
+             \t\t\t\tfprintf(stderr, \"%s: option does not take an argument: --%s\\n\", progname, longopts[i].name);\n\
+             \t\t\t\treturn;\n\
+             \t\t\t}}\n\
+             \t\t}}\n\
+             \t}}\n\
+             \tif (opt > 0 && opt < 128) {{\n\
+             \t\tfprintf(stderr, \"%s: unknown option: -%c\\n\", progname, opt);\n\
+             \t\treturn;\n\
+             \t}}\n\
+             \tif (opt) {{\n\
+             \t\tfprintf(stderr, \"%s: unknown option: -\\\\%03o\\n\", progname, (unsigned char)opt);\n\
+             \t\treturn;\n\
+             \t}}\n\
+             {}\
+             \tfprintf(stderr, \"%s: unknown option: --%.*s\\n\", progname, (int)typed_len, typed);\n\
+             \tfor ({}; i < sizeof(names) / sizeof(names[0]); i++) {{\n\
+             \t\tsize_t dist = edit_distance(typed, typed_len, names[i]);\n\
+             \t\tif (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {{\n\
+             \t\t\tbest = names[i];\n\
+             \t\t\tbest_dist = dist;\n\
+             \t\t}}\n\
+             \t}}\n\
+             \tif (best) {{\n\
+             \t\tfprintf(stderr, \"%s: did you mean --%s?\\n\", progname, best);\n\
+             \t}}\n\
+             }}\n",
+            longest + 1,
+            std.counter_decl("size_t", "i, j"),
+            std.for_init("size_t", "j", "0"),
+            std.for_init("size_t", "i", "1"),
+            std.for_init("size_t", "j", "1"),
+            table,
+            if self.allows_abbrev() {
+                "\tsize_t matches = 0;\n"
+            } else {
+                ""
+            },
+            std.counter_decl("size_t", "i"),
+            std.for_init("size_t", "i", "0"),
+            self.cgen_ambiguous_option(),
+            std.for_init("size_t", "i", "0")
+        )
+    }
+    /// Part of unknown_option that reports a prefix of several names as ambiguous, which is
+    /// what getopt_long rejected it for when prefixes are allowed.
+    fn cgen_ambiguous_option(&self) -> String {
+        if !self.allows_abbrev() {
+            return String::new();
+        }
+        let std = self.c_std();
+        format!(
+            "\tfor ({}; i < sizeof(names) / sizeof(names[0]); i++) {{\n\
+             \t\tif (strncmp(names[i], typed, typed_len) == 0) {{\n\
+             \t\t\tmatches++;\n\
+             \t\t}}\n\
+             \t}}\n\
+             \tif (matches > 1) {{\n\
+             \t\tfprintf(stderr, \"%s: ambiguous option: --%.*s could be\", progname, (int)typed_len, typed);\n\
+             \t\tfor ({}; i < sizeof(names) / sizeof(names[0]); i++) {{\n\
+             \t\t\tif (strncmp(names[i], typed, typed_len) == 0) {{\n\
+             \t\t\t\tfprintf(stderr, \" --%s\", names[i]);\n\
+             \t\t\t}}\n\
+             \t\t}}\n\
+             \t\tfprintf(stderr, \"\\n\");\n\
+             \t\treturn;\n\
+             \t}}\n",
+            std.for_init("size_t", "i", "0"),
+            std.for_init("size_t", "i", "0")
+        )
+    }
+    /// Creates the parse_int function in C, which reads an int argument with strtol and exits
+    /// through usage if it is not a number or does not fit in an int.
+    fn cgen_parse_int(&self) -> String {
+        if !self.has_int_arg() {
+            return String::new();
+        }
+        format!(
+            "static int parse_int(const char *progname, const char *name, const char *arg) {{\n\
+             \tchar *end;\n\
+             \tlong value;\n\
+             \terrno = 0;\n\
+             \tvalue = strtol(arg, &end, 0);\n\
+             \tif (end == arg || *end != '\\0') {{\n\
+             \t\tfprintf(stderr, \"%s: invalid value for %s: '%s'\\n\", progname, name, arg);\n\
+             {usage_exit}\
+             \t}}\n\
+             \tif (errno == ERANGE || value < INT_MIN || value > INT_MAX) {{\n\
+             \t\tfprintf(stderr, \"%s: value out of range for %s: '%s'\\n\", progname, name, arg);\n\
+             {usage_exit}\
+             \t}}\n\
+             \treturn (int)value;\n\
+             }}\n\n",
+            usage_exit = self.cgen_usage_exit(2)
+        )
+    }
+    /// Prototypes of the user-supplied functions, each once, in the order items use them.
+    fn user_function_decls(&self) -> Vec<String> {
+        let mut validators = Vec::new();
+        let mut converters = Vec::new();
+        let items = self
+            .positional
+            .iter()
+            .map(|pi| (&pi.validator, &pi.converter, pi.c_decl_type()))
+            .chain(
+                self.non_positional
+                    .iter()
+                    .map(|npi| (&npi.validator, &npi.converter, npi.c_decl_type())),
+            );
+        for (validator, converter, c_decl_type) in items {
+            if let Some(validator) = validator {
+                validators.push(format!(
+                    "int {}(const char *value, char *errbuf, size_t errlen);\n",
+                    validator
+                ));
+            }
+            if let Some(converter) = converter {
+                converters.push(format!(
+                    "int {}(const char *value, {} *out, char *errbuf, size_t errlen);\n",
+                    converter, c_decl_type
+                ));
+            }
+        }
+        let mut decls: Vec<String> = Vec::new();
+        for decl in validators.into_iter().chain(converters) {
+            if !decls.contains(&decl) {
+                decls.push(decl);
+            }
+        }
+        decls
+    }
+    /// Declares the validators and converters the user implements. Creates the reject_value
+    /// function in C, which exits through usage with the message a validator or converter left
+    /// in errbuf, and the run_validator function, which checks each of values.
+    fn cgen_user_functions(&self) -> String {
+        let decls = self.user_function_decls();
+        if decls.is_empty() {
+            return String::new();
+        }
+        let mut code = String::from(
+            "/* implement these in your own code: nonzero rejects value, with a message in errbuf */\n",
+        );
+        code.push_str(&decls.concat());
+        code.push_str(&format!(
+            "\nstatic void reject_value(const char *progname, const char *name, const char *value,\n\
+             \t                 const char *errbuf) {{\n\
+             \tif (errbuf[0] == '\\0') {{\n\
+             \t\tfprintf(stderr, \"%s: invalid value for %s: '%s'\\n\", progname, name, value);\n\
+             \t}} else {{\n\
+             \t\tfprintf(stderr, \"%s: %s: %s\\n\", progname, name, errbuf);\n\
+             \t}}\n\
+             {}\
+             }}\n\n",
+            self.cgen_usage_exit(1)
+        ));
+        let has_validators = self.positional.iter().any(|pi| pi.validator.is_some())
+            || self
+                .non_positional
+                .iter()
+                .any(|npi| npi.validator.is_some());
+        if !has_validators {
+            return code;
+        }
+        let std = self.c_std();
+        code.push_str(&format!(
+            "static void run_validator(const char *progname, const char *name, int count, char **values,\n\
+             \t                  int (*validator)(const char *, char *, size_t)) {{\n\
+             \tchar errbuf[256];\n\
+             {}\
+             \tfor ({}; i < count; i++) {{\n\
+             \t\terrbuf[0] = '\\0';\n\
+             \t\tif (validator(values[i], errbuf, sizeof(errbuf)) != 0) {{\n\
+             \t\t\treject_value(progname, name, values[i], errbuf);\n\
+             \t\t}}\n\
+             \t}}\n\
+             }}\n\n",
+            std.counter_decl("int", "i"),
+            std.for_init("int", "i", "0")
+        ));
+        code
+    }
+    /// Creates copy_string and copy_strings, which copy values out of argv for the
+    /// copy_strings mode, exiting if there is no memory for them.
+    fn cgen_copy_strings(&self) -> String {
+        if !self.copies_strings() {
+            return String::new();
+        }
+        format!(
+            "static char *copy_string(const char *value) {{\n\
+             \tchar *copy;\n\
+             \tif (!value) {{\n\
+             \t\treturn NULL;\n\
+             \t}}\n\
+             \tcopy = malloc(strlen(value) + 1);\n\
+             \tif (!copy) {{\n\
+             \t\tperror(\"malloc\");\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \treturn strcpy(copy, value);\n\
+             }}\n\
+             \n\
+             static char **copy_strings(char **values, size_t size) {{\n\
+             \tchar **copies;\n\
+             {}\
+             \tif (size == 0) {{\n\
+             \t\treturn NULL;\n\
+             \t}}\n\
+             \tcopies = malloc(size * sizeof(char *));\n\
+             \tif (!copies) {{\n\
+             \t\tperror(\"malloc\");\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \tfor ({}; i < size; i++) {{\n\
+             \t\tcopies[i] = copy_string(values[i]);\n\
+             \t}}\n\
+             \treturn copies;\n\
+             }}\n\n",
+            self.c_std().counter_decl("size_t", "i"),
+            self.c_std().for_init("size_t", "i", "0"),
+            error = self.error_exit_code()
+        )
+    }
+    /// Creates the map_push function in C, which splits a NAME=VALUE argument into the key and
+    /// value arrays of a map option. The arrays are allocated with realloc and left to the caller.
+    fn cgen_map_push(&self) -> String {
+        if !self.has_map() {
+            return String::new();
+        }
+        // the pair is copied whole, so its key and value share the copy
+        let copy = if self.copies_strings() {
+            "\targ = copy_string(arg);\n\teq = strchr(arg, '=');\n"
+        } else {
+            ""
+        };
+        format!(
+            "static void map_push(const char *progname, const char *opt, char *arg,\n\
+             \t             char ***keys, char ***values, size_t *size, size_t *cap) {{\n\
+             \tchar *eq = strchr(arg, '=');\n\
+             \tif (!eq) {{\n\
+             \t\tfprintf(stderr, \"%s: expected NAME=VALUE for --%s, got '%s'\\n\", progname, opt, arg);\n\
+             {usage_exit}\
+             \t}}\n\
+             \tif (*cap == 0) {{\n\
+             \t\t*keys = NULL;\n\
+             \t\t*values = NULL;\n\
+             \t\t*size = 0;\n\
+             \t}}\n\
+             \tif (*size == *cap) {{\n\
+             \t\tsize_t new_cap = *cap ? *cap * 2 : 8;\n\
+             \t\tchar **grown_keys = realloc(*keys, new_cap * sizeof(char *));\n\
+             \t\tchar **grown_values;\n\
+             \t\tif (!grown_keys) {{\n\
+             \t\t\tperror(\"realloc\");\n\
+             \t\t\texit({error});\n\
+             \t\t}}\n\
+             \t\t*keys = grown_keys;\n\
+             \t\tgrown_values = realloc(*values, new_cap * sizeof(char *));\n\
+             \t\tif (!grown_values) {{\n\
+             \t\t\tperror(\"realloc\");\n\
+             \t\t\texit({error});\n\
+             \t\t}}\n\
+             \t\t*values = grown_values;\n\
+             \t\t*cap = new_cap;\n\
+             \t}}\n\
+             {copy}\
+             \t*eq = '\\0';\n\
+             \t(*keys)[*size] = arg;\n\
+             \t(*values)[*size] = eq + 1;\n\
+             \t(*size)++;\n\
+             }}\n\n",
+            copy = copy,
+            usage_exit = self.cgen_usage_exit(2),
+            error = self.error_exit_code()
+        )
+    }
+    /// The parameters of free_args, each starting with ", ", and the statements freeing them.
+    fn free_args_params(&self) -> (String, String) {
+        let std = self.c_std();
+        let non_positional = self.non_positional.iter().map(|npi| npi.cgen_free(std));
+        let positional = self.positional.iter().map(|pi| pi.cgen_free(std));
+        non_positional.chain(positional).unzip()
+    }
+    /// Creates free_args, which frees the copies parse_args made in copy_strings mode. It
+    /// takes the variables main passed parse_args the addresses of.
+    fn cgen_free_args(&self) -> String {
+        if !self.copies_strings() {
+            return String::new();
+        }
+        let std = self.c_std();
+        let (params, frees) = self.free_args_params();
+        let counter = if frees.contains("[i]") {
+            std.counter_decl("size_t", "i")
+        } else {
+            String::new()
+        };
+        let mut body = self.cgen_doc(&[String::from(
+            "Frees the copies of string values parse_args made, given the variables it \
+             filled in.",
+        )]);
+        body.push_str(&format!(
+            "void free_args({}) {{\n{}{}}}\n\n",
+            params.strip_prefix(", ").unwrap_or("void"),
+            counter,
+            frees
+        ));
+        body
+    }
+    /// Creates the expand_response_files function in C, which replaces each @file argument with
+    /// the words in file. The expanded argv is never freed, since parsed values point into it.
+    fn cgen_response_files(&self) -> String {
+        if !self.has_response_files() {
+            return String::new();
+        }
+        format!(
+            "static void argv_push(int *argc, char ***argv, size_t *cap, char *arg) {{\n\
+             \tif ((size_t)*argc + 1 >= *cap) {{\n\
+             \t\tsize_t new_cap = *cap ? *cap * 2 : 16;\n\
+             \t\tchar **grown = realloc(*argv, new_cap * sizeof(char *));\n\
+             \t\tif (!grown) {{\n\
+             \t\t\tperror(\"realloc\");\n\
+             \t\t\texit({error});\n\
+             \t\t}}\n\
+             \t\t*argv = grown;\n\
+             \t\t*cap = new_cap;\n\
+             \t}}\n\
+             \t(*argv)[(*argc)++] = arg;\n\
+             \t(*argv)[*argc] = NULL;\n\
+             }}\n\
+             \n\
+             static void expand_response_file(const char *progname, const char *path, int depth,\n\
+             \t                                 int *argc, char ***argv, size_t *cap) {{\n\
+             \tFILE *f;\n\
+             \tchar *word = NULL;\n\
+             \tsize_t len = 0, size = 0;\n\
+             \tint c;\n\
+             \tif (depth > {}) {{\n\
+             \t\tfprintf(stderr, \"%s: response files nested too deeply at @%s\\n\", progname, path);\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \tf = fopen(path, \"r\");\n\
+             \tif (!f) {{\n\
+             \t\tfprintf(stderr, \"%s: cannot read @%s: %s\\n\", progname, path, strerror(errno));\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \tdo {{\n\
+             \t\tc = fgetc(f);\n\
+             \t\tif (c == EOF || isspace(c)) {{\n\
+             \t\t\tif (len == 0) {{\n\
+             \t\t\t\tcontinue;\n\
+             \t\t\t}}\n\
+             \t\t\tword[len] = '\\0';\n\
+             \t\t\tif (word[0] == '@' && word[1] != '\\0') {{\n\
+             \t\t\t\texpand_response_file(progname, word + 1, depth + 1, argc, argv, cap);\n\
+             \t\t\t\tfree(word);\n\
+             \t\t\t}} else {{\n\
+             \t\t\t\targv_push(argc, argv, cap, word);\n\
+             \t\t\t}}\n\
+             \t\t\tword = NULL;\n\
+             \t\t\tlen = size = 0;\n\
+             \t\t\tcontinue;\n\
+             \t\t}}\n\
+             \t\tif (len + 1 >= size) {{\n\
+             \t\t\tchar *grown;\n\
+             \t\t\tsize = size ? size * 2 : 32;\n\
+             \t\t\tgrown = realloc(word, size);\n\
+             \t\t\tif (!grown) {{\n\
+             \t\t\t\tperror(\"realloc\");\n\
+             \t\t\t\texit({error});\n\
+             \t\t\t}}\n\
+             \t\t\tword = grown;\n\
+             \t\t}}\n\
+             \t\tword[len++] = (char)c;\n\
+             \t}} while (c != EOF);\n\
+             \tif (ferror(f)) {{\n\
+             \t\tfprintf(stderr, \"%s: cannot read @%s\\n\", progname, path);\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \tfclose(f);\n\
+             }}\n\
+             \n\
+             static void expand_response_files(const char *progname, int *argc, char ***argv) {{\n\
+             \tchar **expanded = NULL;\n\
+             \tint nexpanded = 0;\n\
+             \tsize_t cap = 0;\n\
+             {}\
+             \tfor ({}; i < *argc; i++) {{\n\
+             \t\tchar *arg = (*argv)[i];\n\
+             \t\tif (i > 0 && arg[0] == '@' && arg[1] != '\\0') {{\n\
+             \t\t\texpand_response_file(progname, arg + 1, 1, &nexpanded, &expanded, &cap);\n\
+             \t\t}} else {{\n\
+             \t\t\targv_push(&nexpanded, &expanded, &cap, arg);\n\
+             \t\t}}\n\
+             \t}}\n\
+             \t*argc = nexpanded;\n\
+             \t*argv = expanded;\n\
+             }}\n\n",
+            RESPONSE_FILE_DEPTH,
+            self.c_std().counter_decl("int", "i"),
+            self.c_std().for_init("int", "i", "0"),
+            error = self.error_exit_code()
+        )
+    }
+    /// Creates is_negative_number, which tells whether an argument is a negative number, as -5
+    /// or -1.5, rather than short options for getopt_long.
+    fn cgen_is_negative_number(&self) -> String {
+        if !self.takes_negative_positionals() {
+            return String::new();
+        }
+        String::from(
+            "static int is_digit(char c) {\n\
+             \treturn c >= '0' && c <= '9';\n\
+             }\n\
+             \n\
+             static int is_negative_number(const char *arg) {\n\
+             \tif (arg[0] != '-' || !is_digit(arg[1])) {\n\
+             \t\treturn 0;\n\
+             \t}\n\
+             \targ++;\n\
+             \twhile (is_digit(*arg)) {\n\
+             \t\targ++;\n\
+             \t}\n\
+             \tif (arg[0] == '.' && is_digit(arg[1])) {\n\
+             \t\targ++;\n\
+             \t\twhile (is_digit(*arg)) {\n\
+             \t\t\targ++;\n\
+             \t\t}\n\
+             \t}\n\
+             \treturn *arg == '\\0';\n\
+             }\n\n",
+        )
+    }
+    /// Creates fold_option_case, which gives a long option in argv[at] the case of the
+    /// case-insensitive name it matches, so getopt_long finds it. The names are ASCII, so
+    /// case is folded here rather than with strcasecmp, which not every C library has.
+    fn cgen_fold_option_case(&self) -> String {
+        let names = self.case_insensitive_names();
+        if names.is_empty() {
+            return String::new();
+        }
+        let std = self.c_std();
+        let table: String = names
+            .iter()
+            .map(|name| format!("\t\t\"{}\",\n", c_quote(name)))
+            .collect();
+        // with prefixes allowed, exact matches go first so a prefix cannot take their place
+        let (passes, fits) = if self.allows_abbrev() {
+            (
+                "2",
+                "pass == 0 ? known_len == typed_len : known_len > typed_len",
+            )
+        } else {
+            ("1", "known_len == typed_len")
+        };
+        format!(
+            "static int ascii_tolower(int c) {{\n\
+             \treturn c >= 'A' && c <= 'Z' ? c - 'A' + 'a' : c;\n\
+             }}\n\
+             \n\
+             static void fold_option_case(char **argv, int at, int argc) {{\n\
+             \tstatic const char *names[] = {{\n\
+             {}\
+             \t}};\n\
+             \tchar *typed;\n\
+             \tsize_t typed_len;\n\
+             {}{}\
+             \tif (at >= argc || strncmp(argv[at], \"--\", 2) != 0) {{\n\
+             \t\treturn;\n\
+             \t}}\n\
+             \ttyped = argv[at] + 2;\n\
+             \ttyped_len = strcspn(typed, \"=\");\n\
+             \tfor ({}; pass < {}; pass++) {{\n\
+             \t\tfor ({}; i < sizeof(names) / sizeof(names[0]); i++) {{\n\
+             \t\t\tsize_t known_len = strlen(names[i]);\n\
+             \t\t\tsize_t j = 0;\n\
+             \t\t\tif (!({})) {{\n\
+             \t\t\t\tcontinue;\n\
+             \t\t\t}}\n\
+             \t\t\twhile (j < typed_len && ascii_tolower((unsigned char)typed[j]) == ascii_tolower((unsigned char)names[i][j])) {{\n\
+             \t\t\t\tj++;\n\
+             \t\t\t}}\n\
+             \t\t\tif (j == typed_len) {{\n\
+             \t\t\t\tmemcpy(typed, names[i], typed_len);\n\
+             \t\t\t\treturn;\n\
+             \t\t\t}}\n\
+             \t\t}}\n\
+             \t}}\n\
+             }}\n\n",
+            table,
+            std.counter_decl("int", "pass"),
+            std.counter_decl("size_t", "i"),
+            std.for_init("int", "pass", "0"),
+            passes,
+            std.for_init("size_t", "i", "0"),
+            fits,
+        )
+    }
+    /// Creates the functions reading a config file of KEY = VALUE lines, if the spec has one.
+    /// Blank lines, lines starting with # or ; and [section] headers are skipped, and a key
+    /// given twice takes its last value. Lines are kept for as long as the program runs, as
+    /// the values are assigned to char* options as they are.
+    fn cgen_config_file(&self) -> String {
+        if self.config_file.is_none() {
+            return String::new();
+        }
+        format!(
+            "struct config_entry {{\n\
+             \tchar *key;\n\
+             \tchar *value;\n\
+             \tint line;\n\
+             \tint used;\n\
+             }};\n\
+             \n\
+             struct config {{\n\
+             \tconst char *path;\n\
+             \tstruct config_entry *entries;\n\
+             \tsize_t size;\n\
+             }};\n\
+             \n\
+             static char *config_trim(char *s) {{\n\
+             \tchar *end = s + strlen(s);\n\
+             \twhile (isspace((unsigned char)*s)) {{\n\
+             \t\ts++;\n\
+             \t}}\n\
+             \twhile (end > s && isspace((unsigned char)end[-1])) {{\n\
+             \t\tend--;\n\
+             \t}}\n\
+             \t*end = '\\0';\n\
+             \treturn s;\n\
+             }}\n\
+             \n\
+             static int config_add(const char *progname, struct config *config, char *line, int lineno) {{\n\
+             \tchar *key = config_trim(line);\n\
+             \tchar *eq;\n\
+             \tstruct config_entry *grown;\n\
+             \tif (*key == '\\0' || *key == '#' || *key == ';' || *key == '[') {{\n\
+             \t\treturn 0;\n\
+             \t}}\n\
+             \teq = strchr(key, '=');\n\
+             \tif (!eq) {{\n\
+             \t\tfprintf(stderr, \"%s: %s:%d: expected KEY = VALUE\\n\", progname, config->path, lineno);\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \t*eq = '\\0';\n\
+             \tgrown = realloc(config->entries, (config->size + 1) * sizeof(struct config_entry));\n\
+             \tif (!grown) {{\n\
+             \t\tperror(\"realloc\");\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \tconfig->entries = grown;\n\
+             \tgrown[config->size].key = config_trim(key);\n\
+             \tgrown[config->size].value = config_trim(eq + 1);\n\
+             \tgrown[config->size].line = lineno;\n\
+             \tgrown[config->size].used = 0;\n\
+             \tconfig->size++;\n\
+             \treturn 1;\n\
+             }}\n\
+             \n\
+             static void read_config(const char *progname, const char *path, int must_exist,\n\
+             \t                        struct config *config) {{\n\
+             \tFILE *f;\n\
+             \tchar *line = NULL;\n\
+             \tsize_t len = 0, size = 0;\n\
+             \tint c, lineno = 1;\n\
+             \tconfig->path = path;\n\
+             \tconfig->entries = NULL;\n\
+             \tconfig->size = 0;\n\
+             \tif (!path) {{\n\
+             \t\treturn;\n\
+             \t}}\n\
+             \tf = fopen(path, \"r\");\n\
+             \tif (!f) {{\n\
+             \t\tif (!must_exist && errno == ENOENT) {{\n\
+             \t\t\treturn;\n\
+             \t\t}}\n\
+             \t\tfprintf(stderr, \"%s: cannot read %s: %s\\n\", progname, path, strerror(errno));\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \tdo {{\n\
+             \t\tc = fgetc(f);\n\
+             \t\tif (len + 1 >= size) {{\n\
+             \t\t\tchar *grown;\n\
+             \t\t\tsize = size ? size * 2 : 64;\n\
+             \t\t\tgrown = realloc(line, size);\n\
+             \t\t\tif (!grown) {{\n\
+             \t\t\t\tperror(\"realloc\");\n\
+             \t\t\t\texit({error});\n\
+             \t\t\t}}\n\
+             \t\t\tline = grown;\n\
+             \t\t}}\n\
+             \t\tif (c != EOF && c != '\\n') {{\n\
+             \t\t\tline[len++] = (char)c;\n\
+             \t\t\tcontinue;\n\
+             \t\t}}\n\
+             \t\tline[len] = '\\0';\n\
+             \t\tif (config_add(progname, config, line, lineno)) {{\n\
+             \t\t\tline = NULL;\n\
+             \t\t\tsize = 0;\n\
+             \t\t}}\n\
+             \t\tlen = 0;\n\
+             \t\tlineno++;\n\
+             \t}} while (c != EOF);\n\
+             \tfree(line);\n\
+             \tif (ferror(f)) {{\n\
+             \t\tfprintf(stderr, \"%s: cannot read %s\\n\", progname, path);\n\
+             \t\texit({error});\n\
+             \t}}\n\
+             \tfclose(f);\n\
+             }}\n\
+             \n\
+             static char *config_get(struct config *config, const char *key) {{\n\
+             \tchar *value = NULL;\n\
+             {}\
+             \tfor ({}; i < config->size; i++) {{\n\
+             \t\tif (strcmp(config->entries[i].key, key) == 0) {{\n\
+             \t\t\tconfig->entries[i].used = 1;\n\
+             \t\t\tvalue = config->entries[i].value;\n\
+             \t\t}}\n\
+             \t}}\n\
+             \treturn value;\n\
+             }}\n\
+             \n\
+             static int config_flag(const char *progname, const struct config *config, const char *key,\n\
+             \t                       const char *value) {{\n\
+             \tif (!strcmp(value, \"1\") || !strcmp(value, \"true\") || !strcmp(value, \"yes\") || !strcmp(value, \"on\")) {{\n\
+             \t\treturn 1;\n\
+             \t}}\n\
+             \tif (!strcmp(value, \"0\") || !strcmp(value, \"false\") || !strcmp(value, \"no\") || !strcmp(value, \"off\")) {{\n\
+             \t\treturn 0;\n\
+             \t}}\n\
+             \tfprintf(stderr, \"%s: %s: expected true or false for %s, got '%s'\\n\", progname, config->path, key, value);\n\
+             \texit({error});\n\
+             }}\n\
+             \n\
+             static void config_check_used(const char *progname, const struct config *config) {{\n\
+             {}\
+             \tfor ({}; i < config->size; i++) {{\n\
+             \t\tif (!config->entries[i].used) {{\n\
+             \t\t\tfprintf(stderr, \"%s: %s:%d: unknown key '%s'\\n\", progname, config->path,\n\
+             \t\t\t        config->entries[i].line, config->entries[i].key);\n\
+             \t\t\texit({error});\n\
+             \t\t}}\n\
+             \t}}\n\
+             }}\n\n",
+            self.c_std().counter_decl("size_t", "i"),
+            self.c_std().for_init("size_t", "i", "0"),
+            self.c_std().counter_decl("size_t", "i"),
+            self.c_std().for_init("size_t", "i", "0"),
+            error = self.error_exit_code()
+        )
+    }
+    /// Reads the config file, if the spec has one, and gives each option that was not on the
+    /// command line the value the file has for it.
+    fn cgen_apply_config(&self) -> String {
+        let config = match &self.config_file {
+            Some(config) => config,
+            None => return String::new(),
+        };
+        let default = match &config.path {
+            Some(path) => format!("\"{}\"", c_quote(path)),
+            None => String::from("NULL"),
+        };
+        let mut body = match config
+            .option
+            .as_ref()
+            .and_then(|l| self.find_non_positional(l))
+        {
+            Some(npi) => format!(
+                "\tread_config(progname, {0}__isset ? *{0} : {1}, {0}__isset, &file_config);\n",
+                npi.c_var, default
+            ),
+            None => format!("\tread_config(progname, {}, 0, &file_config);\n", default),
+        };
+        let std = self.c_std();
+        let configurable = self
+            .non_positional
+            .iter()
+            .filter(|npi| npi.is_configurable() && Some(&npi.long) != config.option.as_ref());
+        for npi in configurable {
+            let mut condition = format!(
+                "(optarg = config_get(&file_config, \"{}\")) != NULL && !{}__isset",
+                c_quote(&npi.long),
+                npi.c_var
+            );
+            if npi.is_flag() {
+                condition.push_str(&format!(
+                    " && config_flag(progname, &file_config, \"{}\", optarg)",
+                    c_quote(&npi.long)
+                ));
+            }
+            // assigned as from the command line, a level further out than in the parse loop
+            let assign: String = npi
+                .cgen_assign_optarg(std, true)
+                .lines()
+                .map(|line| format!("{}\n", &line[1..]))
+                .collect();
+            body.push_str(&format!("\tif ({}) {{\n{}\t}}\n", condition, assign));
+        }
+        body.push_str("\tconfig_check_used(progname, &file_config);\n");
+        body
+    }
+    /// Creates the start of the parse_args function in C, up to the getopt_long loop.
+    fn cgen_decl(&self) -> String {
+        let mut doc = vec![
+            String::from(
+                "Parses the command line into the variables pointed to, printing a message and \
+                 exiting if it does not match the spec.",
+            ),
+            String::new(),
+            String::from("@param argc Number of arguments, as main was given it."),
+            String::from("@param argv The arguments, as main was given them."),
+        ];
+        for npi in &self.non_positional {
+            doc.extend(npi.cgen_doc_params());
+        }
+        for pi in &self.positional {
+            doc.extend(pi.cgen_doc_params());
+        }
+        let mut body = String::new();
+        if self.has_selftest() {
+            // defined after parse_args, which it calls
+            body.push_str("static int argen_selftest(void);\n\n");
+        }
+        body.push_str(&self.cgen_doc(&doc));
+        body.push_str("void parse_args(int argc, char **argv");
+        for npi in &self.non_positional {
+            body.push_str(&npi.cgen_decl_arg())
+        }
+        for pi in &self.positional {
+            body.push_str(&pi.cgen_decl_arg())
+        }
+        body.push_str(") {\n");
+        match &self.program_name {
+            Some(name) => body.push_str(&format!(
+                "\tconst char *progname = \"{}\";\n",
+                c_quote(name)
+            )),
+            None => body.push_str("\tconst char *progname = argv[0];\n"),
+        }
+        if self.has_custom() {
+            body.push_str("\tchar errbuf[256];\n");
+        }
+
+        // decls for __isset
+        let std = self.c_std();
+        let constrained = self.constrained_options();
+        for npi in &self.non_positional {
+            body.push_str(&npi.cgen_isset_decl(std, self.c_tracks_isset(npi, &constrained)));
+        }
+        for pi in &self.positional {
+            body.push_str(&pi.cgen_isset_decl(std));
+        }
+        // defs for __default
+        for npi in &self.non_positional {
+            body.push_str(&npi.cgen_default_decl());
+        }
+        for pi in &self.positional {
+            body.push_str(&pi.cgen_default_decl());
+        }
+
+        // longopts
+        let uniqs = self.getopt_uniqs();
+        body.push_str("\tstatic struct option longopts[] = {\n");
+        for (i, npi) in self.non_positional.iter().enumerate() {
+            body.push_str(&npi.cgen_getopt(&uniqs[i]));
+        }
+        body.push_str("\t\t{\"help\", 0, 0, 'h'},\n");
+        if self.version.is_some() {
+            body.push_str("\t\t{\"version\", 0, 0, 'V'},\n");
+        }
+        body.push_str("\t\t{0, 0, 0, 0}\n\t};\n");
+
+        body
+    }
+    /// Creates the getopt_long loop of parse_args in C, given a case for each non-positional.
+    fn cgen_parse_loop(&self, cases: &str) -> String {
+        let mut body = String::new();
+        // parse loop, optional
+        if !self.is_strict_order() {
+            body.push_str("\tint npos = 0;\n");
+        }
+        body.push_str("\tint ch;\n");
+        if !self.allows_abbrev() {
+            body.push_str("\tint at;\n\tint longindex;\n");
+        }
+        if self.config_file.is_some() {
+            body.push_str("\tstruct config file_config;\n");
+        }
+        // declarations all come first, as C89 wants
+        for npi in &self.non_positional {
+            body.push_str(&npi.cgen_zero());
+        }
+        for pi in &self.positional {
+            body.push_str(&pi.cgen_zero());
+        }
+        if self.has_selftest() {
+            body.push_str(
+                "\tif (argc == 2 && strcmp(argv[1], \"--argen-selftest\") == 0) {\n\
+                 \t\texit(argen_selftest());\n\
+                 \t}\n",
+            );
+        }
+        if self.has_response_files() {
+            body.push_str("\texpand_response_files(progname, &argc, &argv);\n");
+        }
+        body.push_str("\topterr = 0;\n");
+        let optstring = c_quote(&self.getopt_optstring());
+        // argv[optind] is next for getopt_long to look at, so it is never an option's value
+        let mut fold = String::new();
+        if !self.case_insensitive_names().is_empty() {
+            fold.push_str("\t\tfold_option_case(argv, optind, argc);\n");
+        }
+        if self.takes_negative_positionals() {
+            // getopt_long would take -5 for an option 5, so it never sees them
+            fold.push_str(if self.is_strict_order() {
+                "\t\tif (optind < argc && is_negative_number(argv[optind])) {\n\
+                 \t\t\tbreak;\n\
+                 \t\t}\n"
+            } else {
+                "\t\tif (optind < argc && is_negative_number(argv[optind])) {\n\
+                 \t\t\targv[++npos] = argv[optind++];\n\
+                 \t\t\tcontinue;\n\
+                 \t\t}\n"
+            });
+        }
+        if self.allows_abbrev() && fold.is_empty() {
+            // getopt_long takes unambiguous prefixes by itself
+            body.push_str(&format!(
+                "\twhile ((ch = getopt_long(argc, argv, \"{}\", longopts, NULL)) != -1) {{\n",
+                optstring
+            ));
+        } else if self.allows_abbrev() {
+            body.push_str(&format!(
+                "\tfor (;;) {{\n\
+                 {}\
+                 \t\tch = getopt_long(argc, argv, \"{}\", longopts, NULL);\n\
+                 \t\tif (ch == -1) {{\n\
+                 \t\t\tbreak;\n\
+                 \t\t}}\n",
+                fold, optstring
+            ));
+        } else {
+            // long options start an element, so argv[at] is the one getopt_long matched, and only
+            // an exact match may stand for the name it matched
+            body.push_str(&format!(
+                "\tfor (;;) {{\n\
+                 {}\
+                 \t\tat = optind;\n\
+                 \t\tlongindex = -1;\n\
+                 \t\tch = getopt_long(argc, argv, \"{}\", longopts, &longindex);\n\
+                 \t\tif (ch == -1) {{\n\
+                 \t\t\tbreak;\n\
+                 \t\t}}\n\
+                 \t\tif (longindex >= 0 && strcspn(argv[at] + 2, \"=\") != strlen(longopts[longindex].name)) {{\n\
+                 \t\t\tunknown_option(progname, longopts, 0, argv[at]);\n\
+                 {}\
+                 \t\t}}\n",
+                fold,
+                optstring,
+                self.cgen_usage_exit(3)
+            ));
+        }
+        body.push_str("\t\tswitch (ch) {\n");
+        body.push_str(cases);
+        if let Some(version) = &self.version {
+            body.push_str(&format!(
+                "\t\tcase 'V':\n\
+                 \t\t\tprintf(\"%s {}\\n\", progname);\n\
+                 \t\t\texit(0);\n",
+                c_format_quote(version)
+            ));
+        }
+        if !self.is_strict_order() {
+            // positionals are packed into argv as they come, which never passes getopt
+            body.push_str("\t\tcase 1:\n\t\t\targv[++npos] = optarg;\n\t\t\tbreak;\n");
+        }
+        let usage_exit = self.cgen_usage_exit(3);
+        body.push_str(&format!(
+            "\t\tcase ':':\n\
+             \t\t\tif (strncmp(argv[optind-1], \"--\", 2) == 0) {{\n\
+             \t\t\t\tfprintf(stderr, \"%s: option requires an argument: %s\\n\", progname, argv[optind-1]);\n\
+             \t\t\t}} else {{\n\
+             \t\t\t\tfprintf(stderr, \"%s: option requires an argument: -%c\\n\", progname, optopt);\n\
+             \t\t\t}}\n\
+             {0}\
+             \t\tcase '?':\n\
+             \t\t\tunknown_option(progname, longopts, optopt, argv[optind-1]);\n\
+             {0}\
+             \t\tcase 0:\n\t\t\tbreak;\n\
+             \t\tcase 'h':\n\
+             \t\t\tusage(stdout, progname);\n\
+             \t\t\texit(0);\n\
+             \t\tdefault:\n\
+             {0}\
+             \t\t}}\n\t}}\n",
+            usage_exit
+        ));
+        if !self.is_strict_order() {
+            // everything after "--" is positional too
+            body.push_str(
+                "\twhile (optind < argc) {\n\
+                 \t\targv[++npos] = argv[optind++];\n\
+                 \t}\n\
+                 \targc = npos + 1;\n\
+                 \toptind = 1;\n",
+            );
+        }
+        body
+    }
+    /// Creates the rest of parse_args in C, which checks and assigns what the loop found.
+    fn cgen_post_loop(&self) -> String {
+        let mut body = self.cgen_apply_config();
+        let usage_exit = self.cgen_usage_exit(2);
+        // post loop, optional
+        for npi in &self.non_positional {
+            body.push_str(&npi.cgen_post_loop(&usage_exit));
+        }
+        for group in self.groups.iter().flatten() {
+            let c_vars: Vec<&str> = group
+                .options
+                .iter()
+                .filter_map(|long| self.find_non_positional(long))
+                .map(|npi| npi.c_var.as_str())
+                .collect();
+            body.push_str(&group.cgen_post_loop(&c_vars, &usage_exit));
+        }
+        for npi in &self.non_positional {
+            let requires = npi.requires.iter().flatten();
+            for other in requires.filter_map(|long| self.find_non_positional(long)) {
+                body.push_str(&npi.cgen_requires(other, &usage_exit));
+            }
+            let conflicts = npi.conflicts_with.iter().flatten();
+            for other in conflicts.filter_map(|long| self.find_non_positional(long)) {
+                body.push_str(&npi.cgen_conflicts(other, &usage_exit));
+            }
+        }
+
+        // parse+post loop, positional
+        let std = self.c_std();
+        let required: Vec<&PositionalItem> = self
+            .positional
+            .iter()
+            .filter(|p| p.is_required() && !p.is_multi())
+            .collect();
+        let (min, max) = self.positional_arity();
+        if min > 0 {
+            body.push_str(&format!(
+                "\n\tif (argc-optind < {}) {{\n\
+                   {}\
+                   \t}}\n",
+                min,
+                self.cgen_usage_exit(2)
+            ));
+        } else if !self.positional.is_empty() {
+            body.push('\n');
+        }
+        match max {
+            Some(max) if !self.positional.is_empty() => body.push_str(&format!(
+                "\tif (argc-optind > {0}) {{\n\
+                 \t\tfprintf(stderr, \"%s: unexpected argument: %s\\n\", progname, argv[optind+{0}]);\n\
+                 {1}\
+                 \t}}\n",
+                max,
+                self.cgen_usage_exit(2)
+            )),
+            _ => {}
+        }
+        if !self.positional.is_empty() {
+            body.push_str("\targv += optind;\n\targc -= optind;\n\n");
+        }
+        if !required.is_empty() {
+            for pi in &required {
+                body.push_str(&format!("{}\targv++;\n", pi.cgen_assign_argv0(std)));
+            }
+            if required.len() == 1 {
+                body.push_str("\targc--;\n\n");
+            } else {
+                body.push_str(&format!("\targc -= {};\n\n", required.len()));
+            }
+            for pi in &required {
+                body.push_str(&pi.cgen_post_loop());
+            }
+        }
+
+        // parse+post loop, positional optional
+        let optional: Vec<&PositionalItem> = self
+            .positional
+            .iter()
+            .filter(|p| !p.is_required() && !p.is_multi())
+            .collect();
+        for pi in &optional {
+            body.push_str("\tif (argc > 0) {\n");
+            body.push_str(&pi.cgen_assign_argv0(std));
+            body.push_str("\t\targv++; argc--;\n\t}\n");
+        }
+        for pi in &optional {
+            body.push_str(&pi.cgen_post_loop());
+        }
+
+        // multi item
+        let multi: Option<&PositionalItem> = self.positional.iter().find(|p| p.is_multi());
+        if let Some(pi) = multi {
+            if pi.is_required() {
+                body.push_str(&pi.cgen_assign_argv0(std));
+            } else {
+                body.push_str("\tif (argc > 0) {\n");
+                body.push_str(&pi.cgen_assign_argv0(std));
+                body.push_str("\t}\n");
+            }
+            body.push_str(&pi.cgen_post_loop());
+        }
+
+        if self.copies_strings() {
+            body.push('\n');
+            for npi in &self.non_positional {
+                body.push_str(&npi.cgen_copy());
+            }
+            for pi in &self.positional {
+                body.push_str(&pi.cgen_copy());
+            }
+        }
+        body.push_str("}\n\n");
+        body.push_str(&self.cgen_free_args());
+        body.push_str(&self.cgen_selftest());
+        body
+    }
+    /// Creates the case of the getopt_long loop for npi, whose getopt_long values are uniqs.
+    fn cgen_parse_item(
+        &self,
+        npi: &NonPositionalItem,
+        uniqs: &[i32],
+        tracks_isset: bool,
+    ) -> String {
+        let warn = |warning: &str| {
+            format!(
+                "\t\t\tfprintf(stderr, \"%s: {}\\n\", progname);\n",
+                c_format_quote(warning)
+            )
+        };
+        // deprecated aliases warn, then go on to what the option does
+        let aliases: String = npi
+            .deprecated_aliases()
+            .zip(&uniqs[1..])
+            .map(|(alias, uniq)| {
+                format!(
+                    "\t\tcase {}:\n{}\t\t\t/* fall through */\n",
+                    uniq,
+                    warn(&npi.alias_warning(alias))
+                )
+            })
+            .collect();
+        format!(
+            "{}\t\tcase {}:\n{}{}\t\t\tbreak;\n",
+            aliases,
+            uniqs[0],
+            npi.deprecation_warning()
+                .map_or_else(String::new, |warning| warn(&warning)),
+            npi.cgen_assign_optarg(self.c_std(), tracks_isset)
+        )
+    }
+    /// The values getopt_long returns for each non-positional: first its short name if it has
+    /// one, otherwise an int past every byte, then another such int for each deprecated alias, so
+    /// parse_args can tell when one was given. getopt_long returns an int, so they never run out.
+    fn getopt_uniqs(&self) -> Vec<Vec<i32>> {
+        let mut past_bytes = 256..;
+        self.non_positional
+            .iter()
+            .map(|npi| {
+                let first = match &npi.short {
+                    Some(s) => i32::from(s.as_bytes()[0]),
+                    None => past_bytes.next().unwrap(),
+                };
+                let aliases = npi.deprecated_aliases().map(|_| past_bytes.next().unwrap());
+                std::iter::once(first).chain(aliases).collect()
+            })
+            .collect()
+    }
+    /// The optstring argument to getopt_long.
+    fn getopt_optstring(&self) -> String {
+        // '+' stops at the first positional. '-' hands positionals to the loop as ch == 1 in the
+        // order given, so interleaving works the same whether or not getopt would permute argv.
+        // The ':' that follows makes getopt report missing arguments as ':' rather than '?'.
+        let mut optstring = String::from(if self.is_strict_order() { "+:" } else { "-:" });
+        optstring += &String::from_utf8(
+            self.non_positional
+                .iter()
+                .filter(|npi| npi.short.is_some())
+                .flat_map(|npi| {
+                    let s = npi.short.clone();
+                    let mut v = Vec::new();
+                    v.push(s.unwrap().as_bytes()[0]);
+                    if !npi.is_flag() {
+                        v.push(b':');
+                    }
+                    v.into_iter().collect::<Vec<u8>>()
+                })
+                .collect(),
+        )
+        .unwrap();
+        optstring.push('h');
+        if self.version.is_some() {
+            optstring.push('V');
+        }
+        optstring
+    }
+    /// Creates wmain, which Windows passes the arguments as UTF-16, to hand them to utf8_main as
+    /// UTF-8.
+    fn cgen_wmain(&self) -> String {
+        let std = self.c_std();
+        format!(
+            "\n\
+             int wmain(int argc, wchar_t **wargv) {{\n\
+             \tchar **argv = malloc((argc + 1) * sizeof(char *));\n\
+             {}\
+             \tif (!argv) {{\n\
+             \t\tperror(\"malloc\");\n\
+             \t\treturn 1;\n\
+             \t}}\n\
+             \tfor ({}; i < argc; i++) {{\n\
+             \t\tint size = WideCharToMultiByte(CP_UTF8, 0, wargv[i], -1, NULL, 0, NULL, NULL);\n\
+             \t\targv[i] = size > 0 ? malloc(size) : NULL;\n\
+             \t\tif (!argv[i] || !WideCharToMultiByte(CP_UTF8, 0, wargv[i], -1, argv[i], size, NULL, NULL)) {{\n\
+             \t\t\tfprintf(stderr, \"cannot convert argument %d to UTF-8\\n\", i);\n\
+             \t\t\treturn 1;\n\
+             \t\t}}\n\
+             \t}}\n\
+             \targv[argc] = NULL;\n\
+             \treturn utf8_main(argc, argv);\n\
+             }}\n",
+            std.counter_decl("int", "i"),
+            std.for_init("int", "i", "0")
+        )
+    }
+    /// Creates the main function in C.
+    fn cgen_main(&self) -> String {
+        let mut main = user_region("/*", "", "user-helpers", "");
+        if self.style().has_wmain() {
+            main.push_str("\nstatic int utf8_main(int argc, char **argv) {\n");
+        } else {
+            main.push_str("\nint main(int argc, char **argv) {\n");
+        }
+
+        for npi in &self.non_positional {
+            main.push_str(&npi.cgen_main_decl())
+        }
+        for pi in &self.positional {
+            main.push_str(&pi.cgen_main_decls())
+        }
+
+        if let Some(domain) = &self.text_domain {
+            main.push_str("\n\tsetlocale(LC_ALL, \"\");\n");
+            if let Some(dir) = &self.locale_dir {
+                main.push_str(&format!(
+                    "\tbindtextdomain(\"{}\", \"{}\");\n",
+                    c_quote(domain),
+                    c_quote(dir)
+                ));
+            }
+            main.push_str(&format!("\ttextdomain(\"{}\");\n", c_quote(domain)));
+        }
+        main.push_str("\n\tparse_args(argc, argv");
+        for npi in &self.non_positional {
+            main.push_str(&npi.cgen_call_arg())
+        }
+        for pi in &self.positional {
+            main.push_str(&pi.cgen_call_arg())
+        }
+        main.push_str(");\n\n");
+        let mut code = String::from("\t/* call your code here */\n");
+        if self.copies_strings() {
+            let (params, _) = self.free_args_params();
+            let args: Vec<&str> = params
+                .split(", ")
+                .skip(1)
+                .map(|param| param.rsplit(&[' ', '*'][..]).next().unwrap())
+                .collect();
+            code.push_str(&format!("\tfree_args({});\n", args.join(", ")));
+        }
+        code.push_str("\treturn 0;\n");
+        main.push_str(&user_region("/*", "\t", "user-code", &code));
+        main.push_str("}\n");
+        if self.style().has_wmain() {
+            main.push_str(&self.cgen_wmain());
+        }
+        main
+    }
+}
+
+impl CodeGenerator for C {
+    fn prelude(&self, spec: &Spec) -> String {
+        format!("{}\n\n{}", spec.cgen_headers(), spec.cgen_getopt())
+    }
+    fn usage(&self, spec: &Spec) -> String {
+        format!("{}\n", spec.cgen_usage())
+    }
+    fn helpers(&self, spec: &Spec) -> String {
+        format!(
+            "{}\n{}{}{}{}{}{}{}{}",
+            spec.cgen_unknown_option(),
+            spec.cgen_response_files(),
+            spec.cgen_config_file(),
+            spec.cgen_fold_option_case(),
+            spec.cgen_is_negative_number(),
+            spec.cgen_parse_int(),
+            spec.cgen_user_functions(),
+            spec.cgen_copy_strings(),
+            spec.cgen_map_push()
+        )
+    }
+    fn decls(&self, spec: &Spec) -> String {
+        spec.cgen_decl()
+    }
+    fn parse_item(&self, spec: &Spec, i: usize) -> String {
+        let npi = &spec.non_positional[i];
+        let tracks_isset = spec.c_tracks_isset(npi, &spec.constrained_options());
+        spec.cgen_parse_item(npi, &spec.getopt_uniqs()[i], tracks_isset)
+    }
+    fn parse_items(&self, spec: &Spec) -> String {
+        let constrained = spec.constrained_options();
+        spec.non_positional
+            .iter()
+            .zip(spec.getopt_uniqs())
+            .map(|(npi, uniqs)| {
+                spec.cgen_parse_item(npi, &uniqs, spec.c_tracks_isset(npi, &constrained))
+            })
+            .collect()
+    }
+    fn parse_loop(&self, spec: &Spec, items: &str) -> String {
+        spec.cgen_parse_loop(items)
+    }
+    fn post_loop(&self, spec: &Spec) -> String {
+        spec.cgen_post_loop()
+    }
+    fn main(&self, spec: &Spec) -> String {
+        spec.cgen_main()
+    }
+    /// Pieces are generated with tabs and attached braces, then laid out as the spec's style asks.
+    fn finish_lines(&self, spec: &Spec) -> Box<dyn FnMut(&str) -> String> {
+        let mut styler = spec.style().styler();
+        Box::new(move |lines| styler.lines(lines))
+    }
+}
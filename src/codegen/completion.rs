@@ -0,0 +1,329 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{CType, Spec};
+use std::str::FromStr;
+
+/// Shells that completion scripts can be generated for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+impl FromStr for Shell {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Shell, String> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "unsupported shell \"{}\" (try bash, zsh, or fish)",
+                s
+            )),
+        }
+    }
+}
+
+/// An option as completion scripts see it, including the generated -h and -V.
+struct Completable<'a> {
+    short: Option<&'a str>,
+    longs: Vec<&'a str>,
+    descr: Option<&'a str>,
+    /// Placeholder and c_type of the value, if the option takes one.
+    arg: Option<(&'a str, CType)>,
+}
+
+impl<'a> Completable<'a> {
+    /// All spellings of the option, dashes included.
+    fn names(&self) -> Vec<String> {
+        let short = self.short.iter().map(|s| format!("-{}", s));
+        short
+            .chain(self.longs.iter().map(|l| format!("--{}", l)))
+            .collect()
+    }
+    fn takes_file(&self) -> bool {
+        matches!(self.arg, Some((_, CType::Chars)))
+    }
+}
+
+/// sh_quote wraps a string in single quotes for bash and zsh.
+fn sh_quote(i: &str) -> String {
+    format!("'{}'", i.replace('\'', "'\\''"))
+}
+
+/// fish_quote wraps a string in single quotes for fish, which allows escapes inside them.
+fn fish_quote(i: &str) -> String {
+    format!("'{}'", i.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// zsh_escape escapes the characters _arguments treats specially in descriptions.
+fn zsh_escape(i: &str) -> String {
+    let mut escaped = String::with_capacity(i.len());
+    for c in i.chars() {
+        if let '[' | ']' | ':' | '\\' = c {
+            escaped.push('\\');
+        }
+        escaped.push(if c == '\n' { ' ' } else { c });
+    }
+    escaped
+}
+
+impl Spec {
+    /// Options in the order they appear in --help.
+    fn completables(&self) -> Vec<Completable<'_>> {
+        let mut opts = vec![Completable {
+            short: Some("h"),
+            longs: vec!["help"],
+            descr: Some("print this usage and exit"),
+            arg: None,
+        }];
+        if self.version.is_some() {
+            opts.push(Completable {
+                short: Some("V"),
+                longs: vec!["version"],
+                descr: Some("print version and exit"),
+                arg: None,
+            });
+        }
+        for npi in self.non_positional.iter().filter(|npi| !npi.is_hidden()) {
+            let metavar = npi.metavar();
+            opts.push(Completable {
+                short: npi.short.as_deref(),
+                longs: npi.current_long_names().map(String::as_str).collect(),
+                descr: npi.help_descr.as_deref(),
+                arg: if npi.is_flag() {
+                    None
+                } else {
+                    Some((metavar, npi.c_type))
+                },
+            });
+        }
+        opts
+    }
+    /// Whether any positional argument could be a file.
+    fn positional_files(&self) -> bool {
+        self.positional
+            .iter()
+            .any(|pi| matches!(pi.c_type, CType::Chars))
+    }
+    /// Generates a completion script for the given shell. The script completes program_name, or
+    /// default_name if the spec does not set one.
+    pub fn gen_completion(&self, shell: Shell, default_name: &str) -> String {
+        let name = self.program_name.as_deref().unwrap_or(default_name);
+        match shell {
+            Shell::Bash => self.gen_bash(name),
+            Shell::Zsh => self.gen_zsh(name),
+            Shell::Fish => self.gen_fish(name),
+        }
+    }
+    fn gen_bash(&self, name: &str) -> String {
+        let func: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let opts = self.completables();
+        let mut script = format!(
+            "# bash completion for {}, generated by argen\n\
+             _argen_{}() {{\n\
+             \tlocal cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n\
+             \tlocal prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n\
+             \tcase \"$prev\" in\n",
+            name, func
+        );
+        for opt in opts.iter().filter(|opt| opt.arg.is_some()) {
+            let reply = if opt.takes_file() {
+                "COMPREPLY=($(compgen -f -- \"$cur\"))"
+            } else {
+                "COMPREPLY=()"
+            };
+            script.push_str(&format!(
+                "\t{})\n\t\t{}\n\t\treturn\n\t\t;;\n",
+                opt.names()
+                    .iter()
+                    .map(|n| sh_quote(n))
+                    .collect::<Vec<_>>()
+                    .join("|"),
+                reply
+            ));
+        }
+        let words: Vec<String> = opts.iter().flat_map(Completable::names).collect();
+        script.push_str(&format!(
+            "\tesac\n\
+             \tif [[ \"$cur\" == -* ]]; then\n\
+             \t\tCOMPREPLY=($(compgen -W {} -- \"$cur\"))\n\
+             \t\treturn\n\
+             \tfi\n",
+            sh_quote(&words.join(" "))
+        ));
+        if self.positional_files() {
+            script.push_str("\tCOMPREPLY=($(compgen -f -- \"$cur\"))\n");
+        } else {
+            script.push_str("\tCOMPREPLY=()\n");
+        }
+        script.push_str(&format!(
+            "}}\ncomplete -F _argen_{} {}\n",
+            func,
+            sh_quote(name)
+        ));
+        script
+    }
+    fn gen_zsh(&self, name: &str) -> String {
+        let mut specs = Vec::new();
+        for opt in self.completables() {
+            let names = opt.names();
+            let exclusions = if names.len() > 1 {
+                format!("({})", names.join(" "))
+            } else {
+                String::new()
+            };
+            let descr = opt.descr.map(zsh_escape).unwrap_or_default();
+            let arg = match opt.arg {
+                Some((metavar, ctype)) => format!(
+                    ":{}:{}",
+                    zsh_escape(metavar),
+                    if let CType::Chars = ctype {
+                        "_files"
+                    } else {
+                        ""
+                    }
+                ),
+                None => String::new(),
+            };
+            for n in &names {
+                specs.push(sh_quote(&format!("{}{}[{}]{}", exclusions, n, descr, arg)));
+            }
+        }
+        for (i, pi) in self.positional.iter().enumerate() {
+            let position = if pi.is_multi() {
+                String::from("*")
+            } else {
+                (i + 1).to_string()
+            };
+            // "*::" means something else to _arguments, so multi never gets the extra colon
+            let optional = if pi.is_required() || pi.is_multi() {
+                ""
+            } else {
+                ":"
+            };
+            let action = if let CType::Chars = pi.c_type {
+                "_files"
+            } else {
+                ""
+            };
+            specs.push(sh_quote(&format!(
+                "{}:{}{}:{}",
+                position,
+                optional,
+                zsh_escape(&pi.help_name),
+                action
+            )));
+        }
+        format!(
+            "#compdef {}\n# zsh completion for {0}, generated by argen\n\n_arguments -s \\\n\t{}\n",
+            name,
+            specs.join(" \\\n\t")
+        )
+    }
+    fn gen_fish(&self, name: &str) -> String {
+        let cmd = fish_quote(name);
+        let mut script = format!("# fish completion for {}, generated by argen\n", name);
+        if !self.positional_files() {
+            script.push_str(&format!("complete -c {} -f\n", cmd));
+        }
+        for opt in self.completables() {
+            let mut line = format!("complete -c {}", cmd);
+            if let Some(short) = opt.short {
+                line.push_str(&format!(" -s {}", fish_quote(short)));
+            }
+            for long in &opt.longs {
+                line.push_str(&format!(" -l {}", fish_quote(long)));
+            }
+            if let Some(descr) = opt.descr {
+                line.push_str(&format!(" -d {}", fish_quote(&descr.replace('\n', " "))));
+            }
+            match opt.arg {
+                Some((_, CType::Chars)) => line.push_str(" -r -F"),
+                Some((_, CType::Int | CType::Custom)) => line.push_str(" -x"),
+                None => (),
+            }
+            script.push_str(&line);
+            script.push('\n');
+        }
+        script
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shell, Spec};
+    use std::str::FromStr;
+
+    const SPEC: &str = r#"
+        program_name = "demo"
+        [[positional]]
+        c_var = "input"
+        c_type = "char*"
+        help_name = "FILE"
+        required = true
+        [[non_positional]]
+        c_var = "level"
+        c_type = "int"
+        long = "level"
+        short = "l"
+        aliases = ["lvl"]
+        help_descr = "how [much] it's worth"
+        [[non_positional]]
+        c_var = "out"
+        c_type = "char*"
+        long = "out"
+        "#;
+
+    #[test]
+    fn bash_completion() {
+        let script = Spec::from_str(SPEC)
+            .unwrap()
+            .gen_completion(Shell::Bash, "unused");
+        assert!(script.contains("\t'-l'|'--level'|'--lvl')\n\t\tCOMPREPLY=()\n"));
+        assert!(script.contains("\t'--out')\n\t\tCOMPREPLY=($(compgen -f -- \"$cur\"))\n"));
+        assert!(script.contains("compgen -W '-h --help -l --level --lvl --out'"));
+        assert!(script.ends_with("complete -F _argen_demo 'demo'\n"));
+    }
+
+    #[test]
+    fn zsh_completion() {
+        let script = Spec::from_str(SPEC)
+            .unwrap()
+            .gen_completion(Shell::Zsh, "unused");
+        assert!(script.starts_with("#compdef demo\n"));
+        assert!(script.contains(r#"'(-l --level --lvl)--lvl[how \[much\] it'\''s worth]:NUM:'"#));
+        assert!(script.contains("'--out[]:STR:_files'"));
+        assert!(script.contains("'1:FILE:_files'"));
+    }
+
+    #[test]
+    fn fish_completion() {
+        let script = Spec::from_str(SPEC)
+            .unwrap()
+            .gen_completion(Shell::Fish, "unused");
+        assert!(script.contains(
+            r#"complete -c 'demo' -s 'l' -l 'level' -l 'lvl' -d 'how [much] it\'s worth' -x"#
+        ));
+        assert!(script.contains("complete -c 'demo' -l 'out' -r -F\n"));
+        assert!(!script.contains("complete -c 'demo' -f\n"));
+    }
+}
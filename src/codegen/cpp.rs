@@ -0,0 +1,842 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::regions::user_region;
+use super::{
+    c_quote, wrap, CType, CodeGenerator, NonPositionalItem, PositionalItem, Spec,
+    DEFAULT_HELP_WIDTH, HELP_PREFIX, RESPONSE_FILE_DEPTH,
+};
+use std::collections::HashSet;
+
+const CPP_INCLUDES: [&str; 11] = [
+    "algorithm",
+    "cerrno",
+    "cstdint",
+    "cstdlib",
+    "iostream",
+    "iterator",
+    "optional",
+    "stdexcept",
+    "string",
+    "utility",
+    "vector",
+];
+
+/// Generates C++17 with an Args class whose parse function reads argv itself.
+pub struct Cpp;
+
+/// A /// comment of text, wrapped to the default help width, or nothing if the spec turns docs
+/// off.
+fn cpp_doc(spec: &Spec, indent: &str, text: &str) -> String {
+    if !spec.has_docs() {
+        return String::new();
+    }
+    wrap(text, DEFAULT_HELP_WIDTH - 4 - indent.len())
+        .iter()
+        .map(|line| format!("{}/// {}\n", indent, line))
+        .collect()
+}
+
+/// Ids of -h and -V in the options table. Option i of the spec has id i + 2.
+const CPP_HELP_ID: usize = 0;
+const CPP_VERSION_ID: usize = 1;
+
+fn cpp_option_id(i: usize) -> usize {
+    i + 2
+}
+
+/// The C++ type a value of c_type is stored as. Custom values are left as text, since their
+/// converters are C.
+fn cpp_base_type(c_type: CType) -> &'static str {
+    match c_type {
+        CType::Chars | CType::Custom => "std::string",
+        CType::Int => "int64_t",
+    }
+}
+
+/// A default as a C++ literal of the given type.
+fn cpp_value(c_type: CType, lit: &str) -> String {
+    match c_type {
+        CType::Chars | CType::Custom => format!("\"{}\"", c_quote(lit)),
+        CType::Int => lit.to_owned(),
+    }
+}
+
+/// Expression that converts the std::string text to c_type, naming what in errors.
+fn cpp_convert(c_type: CType, what: &str, text: &str) -> String {
+    match c_type {
+        CType::Chars | CType::Custom => text.to_owned(),
+        CType::Int => format!("parse_int(progname, {}, {})", what, text),
+    }
+}
+
+impl PositionalItem {
+    /// Declaration of the item as a member of Args.
+    fn cppgen_member(&self) -> String {
+        let base = cpp_base_type(self.c_type);
+        match &self.default {
+            Some(default) if self.is_multi() => format!(
+                "\tstd::vector<{}> {} = {{{}}};\n",
+                base,
+                self.c_var,
+                cpp_value(self.c_type, default)
+            ),
+            Some(default) => format!(
+                "\t{} {} = {};\n",
+                base,
+                self.c_var,
+                cpp_value(self.c_type, default)
+            ),
+            None if self.is_multi() => format!("\tstd::vector<{}> {};\n", base, self.c_var),
+            None if self.is_required() => format!("\t{} {}{{}};\n", base, self.c_var),
+            None => format!("\tstd::optional<{}> {};\n", base, self.c_var),
+        }
+    }
+    /// Takes the item from the positional arguments that are left, starting at next.
+    fn cppgen_assign(&self) -> String {
+        let what = format!("\"{}\"", c_quote(&self.help_name));
+        let missing = format!(
+            "\tif (next >= positional.size()) {{\n\
+             \t\tthrow ArgsError(progname, \"missing argument {}\");\n\
+             \t}}\n",
+            c_quote(&self.help_name)
+        );
+        if self.is_multi() {
+            let assign = match self.max_values() {
+                // what is left over is unexpected, as it would be after any other item
+                Some(max) => format!(
+                    "\tsize_t end = std::min(positional.size(), next + {});\n\
+                     \targs.{}.assign(positional.begin() + next, positional.begin() + end);\n\
+                     \tnext = end;\n",
+                    max, self.c_var
+                ),
+                None => "\targs.C_VAR.assign(positional.begin() + next, positional.end());\n\
+                         \tnext = positional.size();\n"
+                    .replace("C_VAR", &self.c_var),
+            };
+            let min = self.min_values();
+            if min > 1 {
+                format!(
+                    "\tif (positional.size() - next < {}) {{\n\
+                     \t\tthrow ArgsError(progname, \"missing argument {}\");\n\
+                     \t}}\n{}",
+                    min,
+                    c_quote(&self.help_name),
+                    assign
+                )
+            } else if self.is_required() {
+                format!("{}{}", missing, assign)
+            } else {
+                format!(
+                    "\tif (next < positional.size()) {{\n\t{}\t}}\n",
+                    assign.replace("\n\t", "\n\t\t")
+                )
+            }
+        } else {
+            let value = cpp_convert(self.c_type, &what, "positional[next++]");
+            if self.is_required() {
+                format!("{}\targs.{} = {};\n", missing, self.c_var, value)
+            } else {
+                format!(
+                    "\tif (next < positional.size()) {{\n\
+                     \t\targs.{} = {};\n\
+                     \t}}\n",
+                    self.c_var, value
+                )
+            }
+        }
+    }
+}
+
+impl NonPositionalItem {
+    /// Declaration of the item as a member of Args.
+    fn cppgen_member(&self) -> String {
+        let base = cpp_base_type(self.c_type);
+        if self.is_map() {
+            format!(
+                "\tstd::vector<std::pair<std::string, std::string>> {};\n",
+                self.c_var
+            )
+        } else if self.is_count() {
+            format!("\tint64_t {} = 0;\n", self.c_var)
+        } else if self.is_flag() {
+            format!("\tbool {} = false;\n", self.c_var)
+        } else if let Some(default) = &self.default {
+            format!(
+                "\t{} {} = {};\n",
+                base,
+                self.c_var,
+                cpp_value(self.c_type, default)
+            )
+        } else if self.is_required() {
+            format!("\t{} {}{{}};\n", base, self.c_var)
+        } else {
+            format!("\tstd::optional<{}> {};\n", base, self.c_var)
+        }
+    }
+    /// Entries of the options table for the item's spellings, which have the given id.
+    fn cppgen_table_entries(&self, id: usize) -> Vec<(String, usize, bool)> {
+        let short = self.short.iter().map(|s| format!("-{}", s));
+        short
+            .chain(self.long_names().map(|long| format!("--{}", long)))
+            .map(|name| (name, id, !self.is_flag()))
+            .collect()
+    }
+    /// Case of the parse loop's switch for the item, which has the given id, recording that it
+    /// was given if track_isset. Its value, if it takes one, is in value by then.
+    fn cppgen_parse(&self, id: usize, track_isset: bool) -> String {
+        let assign = if self.is_map() {
+            format!(
+                "\t\t\targs.{}.push_back(split_pair(progname, name, value));\n",
+                self.c_var
+            )
+        } else if self.is_count() {
+            format!("\t\t\targs.{}++;\n", self.c_var)
+        } else if self.is_flag() {
+            format!("\t\t\targs.{} = true;\n", self.c_var)
+        } else {
+            format!(
+                "\t\t\targs.{} = {};\n",
+                self.c_var,
+                cpp_convert(self.c_type, "name", "value")
+            )
+        };
+        let mut warn = match self.deprecation_warning() {
+            Some(warning) => format!(
+                "\t\t\tstd::cerr << progname << \": {}\\n\";\n",
+                c_quote(&warning)
+            ),
+            None => String::new(),
+        };
+        // name is the spelling given, with its case folded and any prefix expanded by now
+        for alias in self.deprecated_aliases() {
+            warn.push_str(&format!(
+                "\t\t\tif (name == \"--{}\") {{\n\
+                 \t\t\t\tstd::cerr << progname << \": {}\\n\";\n\
+                 \t\t\t}}\n",
+                c_quote(alias),
+                c_quote(&self.alias_warning(alias))
+            ));
+        }
+        let set_isset = if track_isset {
+            format!("\t\t\t{}__isset = true;\n", self.c_var)
+        } else {
+            String::new()
+        };
+        format!(
+            "\t\tcase {}: // --{}\n{}{}{}\t\t\tbreak;\n",
+            id, self.long, warn, assign, set_isset
+        )
+    }
+    /// Errors after the parse loop if a required item was not given.
+    fn cppgen_post_loop(&self) -> String {
+        if self.is_required() {
+            format!(
+                "\tif (!{}__isset) {{\n\
+                 \t\tthrow ArgsError(progname, \"missing required option --{}\");\n\
+                 \t}}\n",
+                self.c_var,
+                c_quote(&self.long)
+            )
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl Spec {
+    /// Whether the parser needs to remember if npi was given. Members alone do not say, since
+    /// flags and defaults make them look set either way.
+    fn cpp_tracks_isset(&self, npi: &NonPositionalItem, constrained: &HashSet<&str>) -> bool {
+        npi.is_required() || constrained.contains(npi.long.as_str())
+    }
+    /// Checks groups, requires and conflicts_with once the loop is done.
+    fn cppgen_constraints(&self) -> String {
+        let mut checks = String::new();
+        for group in self.groups.iter().flatten() {
+            let members: Vec<&NonPositionalItem> = group
+                .options
+                .iter()
+                .filter_map(|long| self.find_non_positional(long))
+                .collect();
+            let count = members
+                .iter()
+                .map(|npi| format!("{}__isset", npi.c_var))
+                .collect::<Vec<_>>()
+                .join(" + ");
+            if group.is_exclusive() {
+                checks.push_str(&format!(
+                    "\tif ({} > 1) {{\n\
+                     \t\tstd::string given;\n",
+                    count
+                ));
+                for npi in &members {
+                    checks.push_str(&format!(
+                        "\t\tif ({}__isset) {{\n\
+                         \t\t\tgiven += \" --{}\";\n\
+                         \t\t}}\n",
+                        npi.c_var,
+                        c_quote(&npi.long)
+                    ));
+                }
+                checks.push_str(
+                    "\t\tthrow ArgsError(progname, \"mutually exclusive options given:\" + given);\n\
+                     \t}\n",
+                );
+            }
+            if group.is_required() {
+                let names = group
+                    .options
+                    .iter()
+                    .map(|long| format!("--{}", long))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                checks.push_str(&format!(
+                    "\tif ({} < 1) {{\n\
+                     \t\tthrow ArgsError(progname, \"one of these options is required: {}\");\n\
+                     \t}}\n",
+                    count,
+                    c_quote(&names)
+                ));
+            }
+        }
+        for npi in &self.non_positional {
+            let requires = npi.requires.iter().flatten();
+            for other in requires.filter_map(|long| self.find_non_positional(long)) {
+                checks.push_str(&format!(
+                    "\tif ({}__isset && !{}__isset) {{\n\
+                     \t\tthrow ArgsError(progname, \"option --{} requires --{}\");\n\
+                     \t}}\n",
+                    npi.c_var,
+                    other.c_var,
+                    c_quote(&npi.long),
+                    c_quote(&other.long)
+                ));
+            }
+            let conflicts = npi.conflicts_with.iter().flatten();
+            for other in conflicts.filter_map(|long| self.find_non_positional(long)) {
+                checks.push_str(&format!(
+                    "\tif ({}__isset && {}__isset) {{\n\
+                     \t\tthrow ArgsError(progname, \"option --{} conflicts with --{}\");\n\
+                     \t}}\n",
+                    npi.c_var,
+                    other.c_var,
+                    c_quote(&npi.long),
+                    c_quote(&other.long)
+                ));
+            }
+        }
+        checks
+    }
+    /// Creates the options table in C++, every spelling of every option sorted by name, with
+    /// find_option to look names up in it.
+    fn cppgen_options(&self) -> String {
+        let mut entries = vec![
+            (String::from("-h"), CPP_HELP_ID, false),
+            (String::from("--help"), CPP_HELP_ID, false),
+        ];
+        if self.version.is_some() {
+            entries.push((String::from("-V"), CPP_VERSION_ID, false));
+            entries.push((String::from("--version"), CPP_VERSION_ID, false));
+        }
+        for (i, npi) in self.non_positional.iter().enumerate() {
+            entries.extend(npi.cppgen_table_entries(cpp_option_id(i)));
+        }
+        entries.sort();
+        let table: String = entries
+            .iter()
+            .map(|(name, id, takes_value)| {
+                format!("\t{{\"{}\", {}, {}}},\n", c_quote(name), id, takes_value)
+            })
+            .collect();
+        format!(
+            "/// A spelling of an option, and which option it is.\n\
+             struct OptionSpec {{\n\
+             \tconst char *name;\n\
+             \tint id;\n\
+             \tbool takes_value;\n\
+             }};\n\n\
+             static const OptionSpec options[] = {{\n\
+             {}\
+             }};\n\n\
+             static bool option_before(const OptionSpec &option, const std::string &name) {{\n\
+             \treturn option.name < name;\n\
+             }}\n\n\
+             /// The entry for name in options, or nullptr if it names no option.\n\
+             static const OptionSpec *find_option(const std::string &name) {{\n\
+             \tconst OptionSpec *found = std::lower_bound(std::begin(options), std::end(options), name, option_before);\n\
+             \tif (found == std::end(options) || found->name != name) {{\n\
+             \t\treturn nullptr;\n\
+             \t}}\n\
+             \treturn found;\n\
+             }}\n\n",
+            table
+        )
+    }
+    /// Creates the fold_option_case function in C++, which gives a long option the case of the
+    /// case-insensitive name it matches, so the options table finds it.
+    fn cppgen_fold_option_case(&self) -> String {
+        let names: String = self
+            .case_insensitive_names()
+            .iter()
+            .map(|name| format!("\t\t\"--{}\",\n", c_quote(name)))
+            .collect();
+        // with prefixes allowed, exact matches go first so a prefix cannot take their place
+        let (passes, fits) = if self.allows_abbrev() {
+            (
+                "2",
+                "pass == 0 ? known.size() == name.size() : known.size() > name.size()",
+            )
+        } else {
+            ("1", "known.size() == name.size()")
+        };
+        format!(
+            "static char ascii_tolower(char c) {{\n\
+             \treturn c >= 'A' && c <= 'Z' ? c - 'A' + 'a' : c;\n\
+             }}\n\n\
+             static std::string fold_option_case(const std::string &name) {{\n\
+             \tstatic const std::string names[] = {{\n\
+             {}\
+             \t}};\n\
+             \tfor (int pass = 0; pass < {}; pass++) {{\n\
+             \t\tfor (const std::string &known : names) {{\n\
+             \t\t\tif (!({})) {{\n\
+             \t\t\t\tcontinue;\n\
+             \t\t\t}}\n\
+             \t\t\tsize_t j = 0;\n\
+             \t\t\twhile (j < name.size() && ascii_tolower(name[j]) == ascii_tolower(known[j])) {{\n\
+             \t\t\t\tj++;\n\
+             \t\t\t}}\n\
+             \t\t\tif (j == name.size()) {{\n\
+             \t\t\t\treturn known.substr(0, name.size());\n\
+             \t\t\t}}\n\
+             \t\t}}\n\
+             \t}}\n\
+             \treturn name;\n\
+             }}\n\n",
+            names, passes, fits
+        )
+    }
+    /// Creates the expand_abbrev function in C++, which turns a prefix of just one option's
+    /// long names into that name. Names with the prefix are together in the options table.
+    fn cppgen_expand_abbrev(&self) -> String {
+        String::from(
+            "static std::string expand_abbrev(const std::string &progname, const std::string &name) {\n\
+             \tconst OptionSpec *found = nullptr;\n\
+             \tstd::string candidates;\n\
+             \tbool ambiguous = false;\n\
+             \tfor (const OptionSpec *known = std::lower_bound(std::begin(options), std::end(options), name, option_before);\n\
+             \t     known != std::end(options) && std::string(known->name).compare(0, name.size(), name) == 0;\n\
+             \t     known++) {\n\
+             \t\tif (known->name == name) {\n\
+             \t\t\treturn name;\n\
+             \t\t}\n\
+             \t\tcandidates += std::string(\" \") + known->name;\n\
+             \t\tif (!found) {\n\
+             \t\t\tfound = known;\n\
+             \t\t} else if (found->id != known->id) {\n\
+             \t\t\tambiguous = true;\n\
+             \t\t}\n\
+             \t}\n\
+             \tif (ambiguous) {\n\
+             \t\tthrow ArgsError(progname, \"ambiguous option: \" + name + \" could be\" + candidates);\n\
+             \t}\n\
+             \treturn found ? found->name : name;\n\
+             }\n\n",
+        )
+    }
+}
+
+impl CodeGenerator for Cpp {
+    fn prelude(&self, spec: &Spec) -> String {
+        let mut includes = CPP_INCLUDES.to_vec();
+        if spec.has_response_files() {
+            includes.push("fstream");
+            includes.sort_unstable();
+        }
+        let mut prelude: String = includes
+            .iter()
+            .map(|s| format!("#include <{}>\n", s))
+            .collect();
+        prelude.push_str(
+            "\n\
+             /// Thrown by Args::parse when the arguments do not match the spec.\n\
+             class ArgsError : public std::runtime_error {\n\
+             public:\n\
+             \tArgsError(const std::string &progname, const std::string &msg)\n\
+             \t\t: std::runtime_error(progname + \": \" + msg), progname(progname) {}\n\
+             \tstd::string progname;\n\
+             };\n\n",
+        );
+        prelude
+    }
+    fn usage(&self, spec: &Spec) -> String {
+        let help: String = spec
+            .help_text()
+            .iter()
+            .map(|line| format!("\n{}{}\\n\"", HELP_PREFIX, c_quote(line)))
+            .collect();
+        format!(
+            "{}static void usage(std::ostream &out, const std::string &progname) {{\n\
+             \tout << \"usage: \" << progname << \" [options]{}\\n\"{};\n\
+             }}\n\n",
+            cpp_doc(
+                spec,
+                "",
+                "Writes the usage line and help for each argument to out."
+            ),
+            c_quote(&spec.usage_synopsis()),
+            help
+        )
+    }
+    fn helpers(&self, spec: &Spec) -> String {
+        let mut helpers = String::new();
+        if spec.has_int_arg() {
+            helpers.push_str(
+                "static int64_t parse_int(const std::string &progname, const std::string &what,\n\
+                 \t                        const std::string &text) {\n\
+                 \tchar *end;\n\
+                 \terrno = 0;\n\
+                 \tlong long value = std::strtoll(text.c_str(), &end, 10);\n\
+                 \tif (text.empty() || *end != '\\0' || errno == ERANGE) {\n\
+                 \t\tthrow ArgsError(progname, \"invalid number for \" + what + \": '\" + text + \"'\");\n\
+                 \t}\n\
+                 \treturn value;\n\
+                 }\n\n",
+            );
+        }
+        if spec.has_map() {
+            helpers.push_str(
+                "static std::pair<std::string, std::string> split_pair(const std::string &progname,\n\
+                 \t                                                      const std::string &name,\n\
+                 \t                                                      const std::string &arg) {\n\
+                 \tsize_t eq = arg.find('=');\n\
+                 \tif (eq == std::string::npos) {\n\
+                 \t\tthrow ArgsError(progname, \"expected NAME=VALUE for \" + name + \", got '\" + arg + \"'\");\n\
+                 \t}\n\
+                 \treturn {arg.substr(0, eq), arg.substr(eq + 1)};\n\
+                 }\n\n",
+            );
+        }
+        if spec.has_response_files() {
+            helpers.push_str(&format!(
+                "static std::vector<std::string> expand_response_files(const std::string &progname,\n\
+                 \t                                                    const std::vector<std::string> &in,\n\
+                 \t                                                    int depth) {{\n\
+                 \tstd::vector<std::string> out;\n\
+                 \tfor (const std::string &arg : in) {{\n\
+                 \t\tif (arg.size() < 2 || arg[0] != '@') {{\n\
+                 \t\t\tout.push_back(arg);\n\
+                 \t\t\tcontinue;\n\
+                 \t\t}}\n\
+                 \t\tstd::string path = arg.substr(1);\n\
+                 \t\tif (depth > {}) {{\n\
+                 \t\t\tthrow ArgsError(progname, \"response files nested too deeply at @\" + path);\n\
+                 \t\t}}\n\
+                 \t\tstd::ifstream file(path);\n\
+                 \t\tif (!file) {{\n\
+                 \t\t\tthrow ArgsError(progname, \"cannot read @\" + path);\n\
+                 \t\t}}\n\
+                 \t\tstd::vector<std::string> words;\n\
+                 \t\tstd::string word;\n\
+                 \t\twhile (file >> word) {{\n\
+                 \t\t\twords.push_back(word);\n\
+                 \t\t}}\n\
+                 \t\tstd::vector<std::string> expanded = expand_response_files(progname, words, depth + 1);\n\
+                 \t\tout.insert(out.end(), expanded.begin(), expanded.end());\n\
+                 \t}}\n\
+                 \treturn out;\n\
+                 }}\n\n",
+                RESPONSE_FILE_DEPTH
+            ));
+        }
+        helpers.push_str(&spec.cppgen_options());
+        if !spec.case_insensitive_names().is_empty() {
+            helpers.push_str(&spec.cppgen_fold_option_case());
+        }
+        if spec.allows_abbrev() {
+            helpers.push_str(&spec.cppgen_expand_abbrev());
+        }
+        if spec.takes_negative_positionals() {
+            // arg starts with '-' and has more after it when this is called
+            helpers.push_str(
+                "static bool is_negative_number(const std::string &arg) {\n\
+                 \tsize_t end = arg.find_first_not_of(\"0123456789\", 1);\n\
+                 \tif (end == 1) {\n\
+                 \t\treturn false;\n\
+                 \t}\n\
+                 \tif (end != std::string::npos && arg[end] == '.') {\n\
+                 \t\tsize_t frac = arg.find_first_not_of(\"0123456789\", end + 1);\n\
+                 \t\tif (end + 1 == arg.size() || frac == end + 1) {\n\
+                 \t\t\treturn false;\n\
+                 \t\t}\n\
+                 \t\tend = frac;\n\
+                 \t}\n\
+                 \treturn end == std::string::npos;\n\
+                 }\n\n",
+            );
+        }
+        helpers.push_str("class Args {\npublic:\n");
+        for pi in &spec.positional {
+            helpers.push_str(&cpp_doc(spec, "\t", &pi.doc()));
+            helpers.push_str(&pi.cppgen_member());
+        }
+        for npi in &spec.non_positional {
+            helpers.push_str(&cpp_doc(spec, "\t", &npi.doc()));
+            helpers.push_str(&npi.cppgen_member());
+        }
+        helpers.push_str(
+            "\n\
+             \t/// Parses argv, throwing ArgsError if it does not match the spec.\n\
+             \tstatic Args parse(int argc, char **argv);\n\
+             };\n\n",
+        );
+        helpers
+    }
+    fn decls(&self, spec: &Spec) -> String {
+        let mut body = String::from("Args Args::parse(int argc, char **argv) {\n\tArgs args;\n");
+        match &spec.program_name {
+            Some(name) => body.push_str(&format!(
+                "\tstd::string progname = \"{}\";\n",
+                c_quote(name)
+            )),
+            None => body.push_str("\tstd::string progname = argv[0];\n"),
+        }
+        body.push_str("\tstd::vector<std::string> argl(argv + 1, argv + argc);\n");
+        if spec.has_response_files() {
+            body.push_str("\targl = expand_response_files(progname, argl, 1);\n");
+        }
+        body.push_str("\tstd::vector<std::string> positional;\n");
+        let constrained = spec.constrained_options();
+        for npi in spec
+            .non_positional
+            .iter()
+            .filter(|npi| spec.cpp_tracks_isset(npi, &constrained))
+        {
+            body.push_str(&format!("\tbool {}__isset = false;\n", npi.c_var));
+        }
+        body
+    }
+    fn parse_item(&self, spec: &Spec, i: usize) -> String {
+        let npi = &spec.non_positional[i];
+        npi.cppgen_parse(
+            cpp_option_id(i),
+            spec.cpp_tracks_isset(npi, &spec.constrained_options()),
+        )
+    }
+    fn parse_items(&self, spec: &Spec) -> String {
+        let constrained = spec.constrained_options();
+        spec.non_positional
+            .iter()
+            .enumerate()
+            .map(|(i, npi)| {
+                npi.cppgen_parse(cpp_option_id(i), spec.cpp_tracks_isset(npi, &constrained))
+            })
+            .collect()
+    }
+    fn parse_loop(&self, spec: &Spec, items: &str) -> String {
+        let mut body = format!(
+            "\tfor (size_t i = 0; i < argl.size(); i++) {{\n\
+             \t\tconst std::string arg = argl[i];\n\
+             \t\tif (arg == \"--\") {{\n\
+             \t\t\tpositional.insert(positional.end(), argl.begin() + i + 1, argl.end());\n\
+             \t\t\tbreak;\n\
+             \t\t}}\n\
+             \t\tif (arg.size() < 2 || arg[0] != '-'{}) {{\n",
+            if spec.takes_negative_positionals() {
+                " || is_negative_number(arg)"
+            } else {
+                ""
+            }
+        );
+        if spec.is_strict_order() {
+            body.push_str(
+                "\t\t\tpositional.insert(positional.end(), argl.begin() + i, argl.end());\n\
+                 \t\t\tbreak;\n",
+            );
+        } else {
+            body.push_str("\t\t\tpositional.push_back(arg);\n\t\t\tcontinue;\n");
+        }
+        // "-abc" is -a with "bc" as its value, or -a then -bc if -a takes none
+        body.push_str(&format!(
+            "\t\t}}\n\
+             \t\tstd::string name = arg.substr(0, 2);\n\
+             \t\tstd::string value = arg.substr(2);\n\
+             \t\tbool has_value = arg.size() > 2;\n\
+             \t\tif (arg[1] == '-') {{\n\
+             \t\t\tsize_t eq = arg.find('=');\n\
+             \t\t\tname = arg.substr(0, eq);\n\
+             \t\t\tvalue = eq == std::string::npos ? \"\" : arg.substr(eq + 1);\n\
+             \t\t\thas_value = eq != std::string::npos;\n\
+             {}{}\
+             \t\t}}\n\
+             \t\tconst OptionSpec *option = find_option(name);\n\
+             \t\tif (!option) {{\n\
+             \t\t\tthrow ArgsError(progname, \"unknown option: \" + name);\n\
+             \t\t}}\n\
+             \t\tif (option->takes_value && !has_value) {{\n\
+             \t\t\tif (i + 1 >= argl.size()) {{\n\
+             \t\t\t\tthrow ArgsError(progname, \"option requires an argument: \" + name);\n\
+             \t\t\t}}\n\
+             \t\t\tvalue = argl[++i];\n\
+             \t\t}} else if (!option->takes_value && has_value) {{\n\
+             \t\t\tif (arg[1] == '-') {{\n\
+             \t\t\t\tthrow ArgsError(progname, \"option takes no argument: \" + name);\n\
+             \t\t\t}}\n\
+             \t\t\targl[i--] = \"-\" + value;\n\
+             \t\t}}\n\
+             \t\tswitch (option->id) {{\n\
+             \t\tcase {}: // --help\n\
+             \t\t\tusage(std::cout, progname);\n\
+             \t\t\tstd::exit(0);\n",
+            if spec.case_insensitive_names().is_empty() {
+                ""
+            } else {
+                "\t\t\tname = fold_option_case(name);\n"
+            },
+            if spec.allows_abbrev() {
+                "\t\t\tname = expand_abbrev(progname, name);\n"
+            } else {
+                ""
+            },
+            CPP_HELP_ID
+        ));
+        if let Some(version) = &spec.version {
+            body.push_str(&format!(
+                "\t\tcase {}: // --version\n\
+                 \t\t\tstd::cout << progname << \" {}\\n\";\n\
+                 \t\t\tstd::exit(0);\n",
+                CPP_VERSION_ID,
+                c_quote(version)
+            ));
+        }
+        body.push_str(items);
+        body.push_str("\t\t}\n\t}\n");
+        body
+    }
+    fn post_loop(&self, spec: &Spec) -> String {
+        let mut body = String::new();
+        for npi in &spec.non_positional {
+            body.push_str(&npi.cppgen_post_loop());
+        }
+        body.push_str(&spec.cppgen_constraints());
+        body.push_str("\tsize_t next = 0;\n");
+        for pi in &spec.positional {
+            body.push_str(&pi.cppgen_assign());
+        }
+        body.push_str(
+            "\tif (next < positional.size()) {\n\
+             \t\tthrow ArgsError(progname, \"unexpected argument: \" + positional[next]);\n\
+             \t}\n\
+             \treturn args;\n\
+             }\n\n",
+        );
+        body
+    }
+    fn main(&self, spec: &Spec) -> String {
+        let mut main = user_region("/*", "", "user-helpers", "");
+        main.push_str(&format!(
+            "\nint main(int argc, char **argv) {{\n\
+             \tArgs args;\n\
+             \ttry {{\n\
+             \t\targs = Args::parse(argc, argv);\n\
+             \t}} catch (const ArgsError &e) {{\n\
+             \t\tstd::cerr << e.what() << '\\n';\n\
+             \t\tusage({}, e.progname);\n\
+             \t\treturn {};\n\
+             \t}}\n\n",
+            if spec.has_usage_to_stderr() {
+                "std::cerr"
+            } else {
+                "std::cout"
+            },
+            spec.usage_exit_code()
+        ));
+        main.push_str(&user_region(
+            "/*",
+            "\t",
+            "user-code",
+            "\t/* call your code here */\n\treturn 0;\n",
+        ));
+        main.push_str("}\n");
+        main
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodeGenerator, Cpp, Spec};
+    use std::str::FromStr;
+
+    #[test]
+    fn args_class() {
+        let cpp = Cpp.gen(
+            &Spec::from_str(
+                r#"
+                docs = false
+                [[positional]]
+                c_var = "input"
+                c_type = "char*"
+                help_name = "FILE"
+                required = true
+                [[positional]]
+                c_var = "count"
+                c_type = "int"
+                help_name = "N"
+                [[non_positional]]
+                c_var = "block_size"
+                c_type = "int"
+                long = "block-size"
+                short = "b"
+                default = "12"
+                [[non_positional]]
+                c_var = "name"
+                c_type = "char*"
+                long = "name"
+                [[non_positional]]
+                c_var = "quiet"
+                c_type = "int"
+                long = "quiet"
+                flag = true
+                "#,
+            )
+            .unwrap(),
+        );
+        assert!(cpp.contains(
+            "class Args {\npublic:\n\
+             \tstd::string input{};\n\
+             \tstd::optional<int64_t> count;\n\
+             \tint64_t block_size = 12;\n\
+             \tstd::optional<std::string> name;\n\
+             \tbool quiet = false;\n"
+        ));
+        assert!(cpp.contains(
+            "\t{\"--block-size\", 2, true},\n\
+             \t{\"--help\", 0, false},\n\
+             \t{\"--name\", 3, true},\n\
+             \t{\"--quiet\", 4, false},\n\
+             \t{\"-b\", 2, true},\n\
+             \t{\"-h\", 0, false},\n"
+        ));
+        assert!(cpp.contains(
+            "\t\tcase 2: // --block-size\n\
+             \t\t\targs.block_size = parse_int(progname, name, value);\n\
+             \t\t\tbreak;\n"
+        ));
+        assert!(cpp.contains("\t\targs.count = parse_int(progname, \"N\", positional[next++]);\n"));
+        assert!(cpp.contains("throw ArgsError(progname, \"missing argument FILE\");"));
+    }
+}
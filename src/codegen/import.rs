@@ -0,0 +1,382 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{NonPositionalItem, Spec, RESERVED_C_VARS};
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Whether an option takes an argument, as has_arg in struct option or colons in an optstring
+/// say.
+#[derive(Clone, Copy, PartialEq)]
+enum HasArg {
+    No,
+    Required,
+    Optional,
+}
+
+impl HasArg {
+    fn parse(field: &str) -> Option<HasArg> {
+        match field {
+            "no_argument" | "0" => Some(HasArg::No),
+            "required_argument" | "1" => Some(HasArg::Required),
+            "optional_argument" | "2" => Some(HasArg::Optional),
+            _ => None,
+        }
+    }
+}
+
+/// An entry of a struct option table. has_arg is None where it is not a value we know, such
+/// as a macro.
+struct LongOpt {
+    name: String,
+    has_arg: Option<HasArg>,
+    /// The variable getopt_long stores val in, if it does not return it.
+    flag: Option<String>,
+    val: String,
+}
+
+impl LongOpt {
+    /// The short option that val says this is, if it is a character, as a literal or a number.
+    fn short(&self) -> Option<char> {
+        let c = match self
+            .val
+            .strip_prefix('\'')
+            .and_then(|v| v.strip_suffix('\''))
+        {
+            Some(literal) if literal.chars().count() == 1 => literal.chars().next()?,
+            Some(_) => return None,
+            None => char::from(self.val.parse::<u8>().ok()?),
+        };
+        Some(c).filter(|&c| c.is_ascii_graphic() && c != '\\')
+    }
+}
+
+/// The source with its comments blanked out, leaving string and character literals alone.
+fn strip_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' | '\'' => {
+                stripped.push(ch);
+                while let Some(c) = chars.next() {
+                    stripped.push(c);
+                    if c == '\\' {
+                        stripped.extend(chars.next());
+                    } else if c == ch || c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+                stripped.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                stripped.push(' ');
+            }
+            _ => stripped.push(ch),
+        }
+    }
+    stripped
+}
+
+/// The entries of the first struct option table in source, if it has one.
+fn parse_table(source: &str) -> Vec<LongOpt> {
+    let table_re = Regex::new(r"struct\s+option\s+\w+\s*\[[^\]]*\]\s*=\s*\{").unwrap();
+    let start = match table_re.find(source) {
+        Some(m) => m.end(),
+        None => return Vec::new(),
+    };
+    let mut depth = 1;
+    let end = source[start..]
+        .char_indices()
+        .find(|&(_, c)| {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            depth == 0
+        })
+        .map_or(source.len(), |(i, _)| start + i);
+    let entry_re = Regex::new(
+        r#"\{\s*"((?:[^"\\]|\\.)*)"\s*,\s*([^,{}]+?)\s*,\s*([^,{}]+?)\s*,\s*([^,{}]+?)\s*\}"#,
+    )
+    .unwrap();
+    entry_re
+        .captures_iter(&source[start..end])
+        .map(|entry| {
+            let flag = entry[3].trim_start_matches('&').trim();
+            LongOpt {
+                name: entry[1].to_owned(),
+                has_arg: HasArg::parse(&entry[2]),
+                flag: match flag {
+                    "0" | "NULL" | "nullptr" => None,
+                    _ => Some(flag.to_owned()),
+                },
+                val: entry[4].to_owned(),
+            }
+        })
+        .collect()
+}
+
+/// The optstring of the first getopt, getopt_long or getopt_long_only call in source, if it
+/// is a literal.
+fn parse_optstring(source: &str) -> Option<String> {
+    let call_re =
+        Regex::new(r#"getopt(?:_long(?:_only)?)?\s*\([^,]+,[^,]+,\s*"((?:[^"\\]|\\.)*)""#).unwrap();
+    call_re.captures(source).map(|call| call[1].to_owned())
+}
+
+/// The short options in an optstring, and whether it asks to stop at the first non-option.
+fn parse_shorts(optstring: &str) -> (Vec<(char, HasArg)>, bool) {
+    let strict = optstring.starts_with('+');
+    let chars: Vec<char> = optstring
+        .trim_start_matches(['+', '-', ':'])
+        .chars()
+        .collect();
+    let mut shorts = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let colons = chars[i + 1..]
+            .iter()
+            .take(2)
+            .take_while(|&&c| c == ':')
+            .count();
+        let has_arg = match colons {
+            0 => HasArg::No,
+            1 => HasArg::Required,
+            _ => HasArg::Optional,
+        };
+        if chars[i].is_ascii_graphic() && chars[i] != ';' {
+            shorts.push((chars[i], has_arg));
+        }
+        i += 1 + colons;
+    }
+    (shorts, strict)
+}
+
+/// A c_var made from name that is not reserved or in taken, which it is then added to.
+fn unique_c_var(name: &str, taken: &mut HashSet<String>) -> String {
+    let mut c_var: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !c_var.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        c_var.insert_str(0, "opt_");
+    }
+    let mut unique = c_var.clone();
+    let mut n = 1;
+    while RESERVED_C_VARS.contains(&unique.as_str()) || taken.contains(&unique) {
+        n += 1;
+        unique = format!("{}_{}", c_var, n);
+    }
+    taken.insert(unique.clone());
+    unique
+}
+
+/// Makes opt take an argument as has_arg says, noting what it cannot express.
+fn with_arg(opt: NonPositionalItem, has_arg: HasArg, notes: &mut Vec<String>) -> NonPositionalItem {
+    match has_arg {
+        HasArg::No => opt.flag(),
+        HasArg::Required => opt,
+        HasArg::Optional => {
+            notes.push(format!(
+                "--{} takes an optional value, which argen has no way to say, so now it needs one",
+                opt.long
+            ));
+            opt
+        }
+    }
+}
+
+impl Spec {
+    /// Makes a best guess at a spec for the hand-written parser in C source, from its struct
+    /// option table and getopt optstring. Also returns notes on what did not carry over, which
+    /// always includes the help text and positional arguments, as the table does not say them.
+    pub fn import_getopt(source: &str) -> Result<(Spec, Vec<String>), String> {
+        let source = strip_comments(source);
+        let table = parse_table(&source);
+        let optstring = parse_optstring(&source);
+        if table.is_empty() && optstring.is_none() {
+            return Err(String::from(
+                "found no struct option table or getopt optstring to import",
+            ));
+        }
+        let (mut shorts, strict) = parse_shorts(optstring.as_deref().unwrap_or(""));
+        let mut notes = Vec::new();
+        let mut spec = Spec::default();
+        if strict {
+            spec.strict_order = Some(true);
+        }
+        let mut c_vars: HashSet<String> = HashSet::new();
+        let mut longs: HashSet<&str> = HashSet::new();
+        // entries that getopt_long returns the same val for are one option by several names
+        let mut options: Vec<Vec<&LongOpt>> = Vec::new();
+        for entry in &table {
+            if !longs.insert(&entry.name) {
+                continue;
+            }
+            if entry.name == "help" || entry.name == "version" {
+                notes.push(format!(
+                    "--{} is one argen makes itself, so it was left out",
+                    entry.name
+                ));
+                shorts.retain(|&(c, _)| Some(c) != entry.short());
+                continue;
+            }
+            let same_option = options.iter_mut().find(|names| {
+                entry.flag.is_none() && names[0].flag.is_none() && names[0].val == entry.val
+            });
+            match same_option {
+                Some(names) => names.push(entry),
+                None => options.push(vec![entry]),
+            }
+        }
+        for names in options {
+            let entry = names[0];
+            let mut opt = NonPositionalItem::new(&entry.name);
+            for alias in &names[1..] {
+                opt = opt.alias(&alias.name);
+            }
+            let short = entry.short().filter(|_| entry.flag.is_none());
+            if let Some(at) = short.and_then(|c| shorts.iter().position(|&(s, _)| s == c)) {
+                let (c, _) = shorts.remove(at);
+                if c == 'h' {
+                    notes.push(format!("-h is argen's --help, so --{} lost it", entry.name));
+                } else {
+                    opt = opt.short(c);
+                }
+            }
+            let has_arg = entry.has_arg.unwrap_or_else(|| {
+                notes.push(format!(
+                    "--{} has an argument setting that is not a literal, so it was taken to need one",
+                    entry.name
+                ));
+                HasArg::Required
+            });
+            opt = with_arg(opt, has_arg, &mut notes);
+            let c_var = match &entry.flag {
+                Some(flag) => {
+                    let c_var = unique_c_var(flag, &mut c_vars);
+                    if entry.val != "1" || c_var != *flag || has_arg != HasArg::No {
+                        notes.push(format!(
+                            "--{} stored {} in {}, and is now an option of its own in {}",
+                            entry.name, entry.val, flag, c_var
+                        ));
+                    }
+                    c_var
+                }
+                None => unique_c_var(&entry.name, &mut c_vars),
+            };
+            spec.non_positional.push(opt.c_var(&c_var));
+        }
+        for (c, has_arg) in shorts {
+            if c == 'h' {
+                notes.push(String::from("-h is argen's --help, so it was left out"));
+                continue;
+            }
+            let long = c.to_string();
+            notes.push(format!(
+                "-{} has no long name, so it was given --{}",
+                c, long
+            ));
+            let opt = NonPositionalItem::new(&long).short(c);
+            let opt = with_arg(opt, has_arg, &mut notes);
+            let c_var = unique_c_var(&format!("opt_{}", c), &mut c_vars);
+            spec.non_positional.push(opt.c_var(&c_var));
+        }
+        notes.push(String::from(
+            "help text and positional arguments are not in the option table, so add them",
+        ));
+        for (pointer, e) in spec.check() {
+            notes.push(format!("{}: {}", pointer, e));
+        }
+        Ok((spec, notes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Spec;
+
+    const SOURCE: &str = r#"
+        static int verbose_flag;
+        /* { "commented", no_argument, 0, 'x' }, */
+        static struct option long_options[] = {
+            {"verbose", no_argument,       &verbose_flag, 1},
+            {"add",     no_argument,       0, 'a'},
+            {"append",  no_argument,       0, 'a'},
+            {"create",  required_argument, 0, 'c'},
+            {"file",    required_argument, 0, 'f'},
+            {"color",   optional_argument, 0, 256},
+            {"help",    no_argument,       0, 'h'},
+            {0, 0, 0, 0}
+        };
+        while ((c = getopt_long(argc, argv, "+ac:f:hq", long_options, &option_index)) != -1) {
+    "#;
+
+    #[test]
+    fn imports_option_table() {
+        let (spec, notes) = Spec::import_getopt(SOURCE).unwrap();
+        let json = serde_json::to_value(&spec).unwrap();
+        let options: Vec<(String, String, String, bool)> = json["non_positional"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|o| {
+                (
+                    o["long"].as_str().unwrap().to_owned(),
+                    o["c_var"].as_str().unwrap().to_owned(),
+                    o["short"].as_str().unwrap_or("").to_owned(),
+                    o["flag"].as_bool().unwrap_or(false),
+                )
+            })
+            .collect();
+        let expected = [
+            ("verbose", "verbose_flag", "", true),
+            ("add", "add", "a", true),
+            ("create", "create", "c", false),
+            ("file", "file", "f", false),
+            ("color", "color", "", false),
+            ("q", "opt_q", "q", true),
+        ];
+        let expected: Vec<(String, String, String, bool)> = expected
+            .iter()
+            .map(|&(l, c, s, f)| (l.to_owned(), c.to_owned(), s.to_owned(), f))
+            .collect();
+        assert_eq!(options, expected);
+        assert_eq!(json["non_positional"][1]["aliases"][0], "append");
+        assert_eq!(json["strict_order"], true);
+        assert!(notes
+            .iter()
+            .any(|n| n.starts_with("--color takes an optional value")));
+        assert!(notes.iter().any(|n| n.starts_with("-q has no long name")));
+        assert!(spec.check().is_empty());
+        assert!(Spec::import_getopt("int main(void) { return 0; }").is_err());
+    }
+}
@@ -0,0 +1,185 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{NonPositionalItem, PositionalItem, Spec};
+use std::io;
+use std::io::{BufRead, Write};
+
+/// Asks questions on out and reads the answers from input.
+struct Prompter<'a, R, W> {
+    input: &'a mut R,
+    out: &'a mut W,
+}
+
+impl<'a, R: BufRead, W: Write> Prompter<'a, R, W> {
+    /// The answer to question, trimmed, or None if it was left blank or input ended.
+    fn ask(&mut self, question: &str) -> io::Result<Option<String>> {
+        write!(self.out, "{}: ", question)?;
+        self.out.flush()?;
+        let mut line = String::new();
+        self.input.read_line(&mut line)?;
+        let answer = line.trim();
+        Ok(Some(answer.to_owned()).filter(|a| !a.is_empty()))
+    }
+    /// Asks until the answer is one of choices, the first of which a blank answer picks.
+    fn choose(&mut self, question: &str, choices: &[&str]) -> io::Result<usize> {
+        let question = format!("{} ({}) [{}]", question, choices.join(", "), choices[0]);
+        loop {
+            let answer = match self.ask(&question)? {
+                Some(answer) => answer,
+                None => return Ok(0),
+            };
+            match choices.iter().position(|c| answer.eq_ignore_ascii_case(c)) {
+                Some(i) => return Ok(i),
+                None => writeln!(self.out, "Answer one of {}.", choices.join(", "))?,
+            }
+        }
+    }
+    /// A yes or no answer, no if left blank.
+    fn confirm(&mut self, question: &str) -> io::Result<bool> {
+        Ok(self.choose(question, &["n", "y"])? == 1)
+    }
+}
+
+impl Spec {
+    /// Builds a spec from answers to questions about the program and each of its positional
+    /// arguments and options, asked on out and read from input. Each argument is checked as
+    /// it is given, and asked for again if the spec would not be valid with it, so the spec
+    /// returned always is.
+    pub fn init_wizard<R, W>(input: &mut R, out: &mut W) -> io::Result<Spec>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        let mut p = Prompter { input, out };
+        let mut spec = Spec {
+            program_name: p.ask("Program name (blank for argv[0])")?,
+            about: p.ask("Description (blank for none)")?,
+            ..Spec::default()
+        };
+        writeln!(p.out, "Positional arguments, in order:")?;
+        while let Some(name) = p.ask("Name (blank when done)")? {
+            let mut pi = PositionalItem::new(&name);
+            if p.choose("  Type", &["string", "int"])? == 1 {
+                pi = pi.int32();
+            }
+            pi.help_descr = p.ask("  Help")?;
+            let multi = p.confirm("  Takes all the values left")?;
+            if multi {
+                pi.multi = Some(true);
+            }
+            if p.confirm("  Required")? {
+                pi.required = Some(true);
+            } else if !multi {
+                pi.default = p.ask("  Default (blank for none)")?;
+            }
+            spec.positional.push(pi);
+            if !check_last(&spec, &mut p.out)? {
+                spec.positional.pop();
+            } else if multi {
+                break;
+            }
+        }
+        writeln!(p.out, "Options:")?;
+        while let Some(long) = p.ask("Long name (blank when done)")? {
+            let mut npi = NonPositionalItem::new(&long);
+            npi.short = p.ask("  Short name (blank for none)")?;
+            if npi.short.as_ref().is_some_and(|s| s.chars().count() != 1) {
+                writeln!(p.out, "A short name is a single character. Try again.")?;
+                continue;
+            }
+            npi = match p.choose("  Type", &["flag", "string", "int"])? {
+                0 => npi.flag(),
+                1 => npi,
+                _ => npi.int32(),
+            };
+            npi.help_descr = p.ask("  Help")?;
+            if npi.flag.is_none() {
+                if p.confirm("  Required")? {
+                    npi.required = Some(true);
+                } else {
+                    npi.default = p.ask("  Default (blank for none)")?;
+                }
+            }
+            spec.non_positional.push(npi);
+            if !check_last(&spec, &mut p.out)? {
+                spec.non_positional.pop();
+            }
+        }
+        Ok(spec)
+    }
+}
+
+/// Whether the spec is valid, printing what is wrong with it to out if not. Only the argument
+/// given last can be at fault, since the spec was valid before it.
+fn check_last<W: Write>(spec: &Spec, out: &mut W) -> io::Result<bool> {
+    let problems = spec.check();
+    for (pointer, e) in &problems {
+        writeln!(out, "{}: {}. Try again.", pointer, e)?;
+    }
+    Ok(problems.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Spec;
+
+    #[test]
+    fn asks_again_for_invalid_arguments() {
+        let answers = [
+            "demo",
+            "",
+            // positional: FILE, required
+            "file",
+            "",
+            "file to read",
+            "",
+            "y",
+            "",
+            // options: a reserved name is asked for again
+            "argc",
+            "",
+            "flag",
+            "",
+            "jobs",
+            "j",
+            "int",
+            "how many at once",
+            "",
+            "4",
+            "verbose",
+            "v",
+            "",
+            "say more",
+            "",
+        ];
+        let input = answers.join("\n") + "\n";
+        let mut out = Vec::new();
+        let spec = Spec::init_wizard(&mut input.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("/non_positional/0/c_var"), "{}", out);
+        assert!(spec.check().is_empty());
+        let json = serde_json::to_value(&spec).unwrap();
+        assert_eq!(json["program_name"], "demo");
+        assert_eq!(json["positional"][0]["help_name"], "FILE");
+        assert_eq!(json["positional"][0]["required"], true);
+        let longs: Vec<_> = spec.non_positional.iter().map(|o| &o.long[..]).collect();
+        assert_eq!(longs, ["jobs", "verbose"]);
+        assert_eq!(json["non_positional"][0]["c_type"], "int");
+        assert_eq!(json["non_positional"][0]["default"], "4");
+        assert_eq!(json["non_positional"][1]["flag"], true);
+    }
+}
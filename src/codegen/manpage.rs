@@ -0,0 +1,204 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{NonPositionalItem, PositionalItem, Spec};
+
+/// roff_quote escapes text for use in a roff document. Dashes become \- so they render as the
+/// minus signs people type, and lines that would start with a control character are guarded.
+fn roff_quote(i: &str) -> String {
+    let escaped = i.replace('\\', "\\e").replace('-', "\\-");
+    escaped
+        .split('\n')
+        .map(|line| {
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{}", line)
+            } else {
+                line.to_owned()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl PositionalItem {
+    /// Paragraph for the ARGUMENTS section of the man page.
+    fn manpage(&self) -> String {
+        let mut entry = format!(".TP\n.I {}\n", roff_quote(&self.help_name));
+        if let Some(descr) = &self.help_descr {
+            entry.push_str(&roff_quote(descr));
+            entry.push('\n');
+        }
+        if self.is_required() {
+            entry.push_str("Required.\n");
+        }
+        if let Some(default) = &self.default {
+            entry.push_str(&format!("Defaults to {}.\n", roff_quote(default)));
+        }
+        entry
+    }
+}
+
+impl NonPositionalItem {
+    /// Paragraph for the OPTIONS section of the man page.
+    fn manpage(&self) -> String {
+        let mut tag = String::new();
+        if let Some(short) = &self.short {
+            tag.push_str(&format!("\\fB\\-{}\\fR, ", roff_quote(short)));
+        }
+        tag.push_str(&format!("\\fB\\-\\-{}\\fR", roff_quote(&self.long)));
+        if !self.is_flag() {
+            tag.push_str(&format!(" \\fI{}\\fR", roff_quote(self.metavar())));
+        }
+        let mut entry = format!(".TP\n{}\n", tag);
+        if let Some(descr) = &self.help_descr {
+            entry.push_str(&roff_quote(descr));
+            entry.push('\n');
+        }
+        if let Some(aliases) = &self.aliases {
+            let aliases: Vec<String> = aliases
+                .iter()
+                .map(|alias| {
+                    let name = format!("\\fB\\-\\-{}\\fR", roff_quote(alias.name()));
+                    if alias.is_deprecated() {
+                        name + " (deprecated)"
+                    } else {
+                        name
+                    }
+                })
+                .collect();
+            entry.push_str(&format!("Also spelled {}.\n", aliases.join(", ")));
+        }
+        if self.is_required() {
+            entry.push_str("Required.\n");
+        }
+        if let Some(default) = &self.default {
+            entry.push_str(&format!("Defaults to {}.\n", roff_quote(default)));
+        }
+        if let Some(deprecated) = &self.deprecated {
+            entry.push_str(&format!("Deprecated: {}\n", roff_quote(deprecated)));
+        }
+        entry
+    }
+}
+
+impl Spec {
+    /// Generates a section 1 man page in roff. The page documents program_name, or default_name
+    /// if the spec does not set one.
+    pub fn gen_manpage(&self, default_name: &str) -> String {
+        let raw_name = self.program_name.as_deref().unwrap_or(default_name);
+        let name = roff_quote(raw_name);
+        let footer = match &self.version {
+            Some(version) => format!("{} {}", name, roff_quote(version)),
+            None => name.to_owned(),
+        };
+        let mut page = format!(
+            ".TH \"{}\" 1 \"\" \"{}\" \"User Commands\"\n.SH NAME\n{}",
+            roff_quote(&raw_name.to_uppercase()),
+            footer,
+            name
+        );
+        let summary = self.about.as_deref().and_then(|a| a.lines().next());
+        if let Some(summary) = summary {
+            page.push_str(&format!(" \\- {}", roff_quote(summary)));
+        }
+
+        page.push_str(&format!("\n.SH SYNOPSIS\n.B {}\n[\\fIoptions\\fR]", name));
+        let mut noptional = 0;
+        for pi in &self.positional {
+            let (words, opened) = pi.synopsis(&format!("\\fI{}\\fR", roff_quote(&pi.help_name)));
+            page.push_str(&words);
+            noptional += opened;
+        }
+        page.push_str(&"]".repeat(noptional));
+        page.push('\n');
+
+        if let Some(about) = &self.about {
+            page.push_str(&format!(".SH DESCRIPTION\n{}\n", roff_quote(about)));
+        }
+        if !self.positional.is_empty() {
+            page.push_str(".SH ARGUMENTS\n");
+            for pi in &self.positional {
+                page.push_str(&pi.manpage());
+            }
+        }
+        page.push_str(
+            ".SH OPTIONS\n\
+             .TP\n\\fB\\-h\\fR, \\fB\\-\\-help\\fR\nPrint usage and exit.\n",
+        );
+        if self.version.is_some() {
+            page.push_str(".TP\n\\fB\\-V\\fR, \\fB\\-\\-version\\fR\nPrint version and exit.\n");
+        }
+        let visible = || self.non_positional.iter().filter(|npi| !npi.is_hidden());
+        for npi in visible().filter(|npi| npi.group.is_none()) {
+            page.push_str(&npi.manpage());
+        }
+        for title in self.help_groups() {
+            page.push_str(&format!(".SS {}\n", roff_quote(title)));
+            for npi in visible().filter(|npi| npi.group.as_deref() == Some(title)) {
+                page.push_str(&npi.manpage());
+            }
+        }
+        page
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{roff_quote, Spec};
+    use std::str::FromStr;
+
+    #[test]
+    fn roff_escapes() {
+        assert_eq!(roff_quote(r"a-b\c"), r"a\-b\ec");
+        assert_eq!(roff_quote(".hidden\n'quoted"), "\\&.hidden\n\\&'quoted");
+    }
+
+    #[test]
+    fn manpage_sections() {
+        let page = Spec::from_str(
+            r#"
+            program_name = "demo"
+            version = "1.0"
+            about = "Demonstrates things."
+            [[positional]]
+            c_var = "input"
+            c_type = "char*"
+            help_name = "FILE"
+            required = true
+            [[non_positional]]
+            c_var = "block_size"
+            c_type = "int"
+            long = "block-size"
+            short = "b"
+            aliases = ["bs"]
+            help_name = "num"
+            help_descr = "set the block size"
+            default = "12"
+            "#,
+        )
+        .unwrap()
+        .gen_manpage("unused");
+        assert!(page.starts_with(".TH \"DEMO\" 1 \"\" \"demo 1.0\" \"User Commands\"\n"));
+        assert!(page.contains(".SH NAME\ndemo \\- Demonstrates things.\n"));
+        assert!(page.contains(".SH SYNOPSIS\n.B demo\n[\\fIoptions\\fR] \\fIFILE\\fR\n"));
+        assert!(page.contains(
+            ".TP\n\\fB\\-b\\fR, \\fB\\-\\-block\\-size\\fR \\fInum\\fR\n\
+             set the block size\n\
+             Also spelled \\fB\\-\\-bs\\fR.\n\
+             Defaults to 12.\n"
+        ));
+    }
+}
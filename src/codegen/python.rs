@@ -0,0 +1,539 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::regions::user_region;
+use super::{CType, CodeGenerator, NonPositionalItem, PositionalItem, Spec};
+
+/// Generates Python that parses with argparse.
+pub struct Python;
+
+/// py_quote makes a single-quoted Python string literal.
+fn py_quote(i: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in i.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// py_int turns a C integer literal into a Python one, which spells octal 0o17 rather than 017.
+fn py_int(lit: &str) -> String {
+    let (sign, digits) = lit.split_at(lit.len() - lit.trim_start_matches(&['-', '+'][..]).len());
+    if digits.len() > 1 && digits.starts_with('0') && !digits[1..].starts_with(&['x', 'X'][..]) {
+        format!("{}0o{}", sign, &digits[1..])
+    } else {
+        lit.to_owned()
+    }
+}
+
+/// A default as a Python literal of the given type.
+fn py_value(c_type: CType, lit: &str) -> String {
+    match c_type {
+        CType::Chars | CType::Custom => py_quote(lit),
+        CType::Int => py_int(lit),
+    }
+}
+
+/// A help string, looked up in the message catalog when translate is set.
+fn py_text(text: &str, translate: bool) -> String {
+    if translate {
+        format!("_({})", py_quote(text))
+    } else {
+        py_quote(text)
+    }
+}
+
+/// The help argument to add_argument, if any. argparse treats % as a format specifier.
+fn py_help(descr: &Option<String>, translate: bool) -> String {
+    match descr {
+        Some(descr) => format!(", help={}", py_text(&descr.replace('%', "%%"), translate)),
+        None => String::new(),
+    }
+}
+
+impl PositionalItem {
+    /// The add_argument call for the item.
+    fn pygen_add_argument(&self, translate: bool) -> String {
+        let mut call = format!(
+            "\tparser.add_argument({}, metavar={}",
+            py_quote(&self.c_var),
+            py_quote(&self.help_name)
+        );
+        if let CType::Int = self.c_type {
+            call.push_str(", type=int");
+        }
+        if self.is_multi() {
+            call.push_str(if self.is_required() {
+                ", nargs='+'"
+            } else {
+                ", nargs='*'"
+            });
+            if let Some(default) = &self.default {
+                call.push_str(&format!(", default=[{}]", py_value(self.c_type, default)));
+            }
+        } else if !self.is_required() {
+            call.push_str(", nargs='?'");
+            if let Some(default) = &self.default {
+                call.push_str(&format!(", default={}", py_value(self.c_type, default)));
+            }
+        }
+        call.push_str(&py_help(&self.help_descr, translate));
+        call.push_str(")\n");
+        call
+    }
+    /// Checks after parsing that the item got as many values as it takes, which nargs cannot
+    /// say beyond one or more.
+    fn pygen_check_arity(&self) -> String {
+        let mut checks = String::new();
+        let min = self.min_values();
+        if self.is_multi() && min > 1 {
+            checks.push_str(&format!(
+                "\tif len(args.{}) < {}:\n\t\tparser.error({})\n",
+                self.c_var,
+                min,
+                py_quote(&format!(
+                    "the following arguments need at least {} values: {}",
+                    min, self.help_name
+                ))
+            ));
+        }
+        if let (true, Some(max)) = (self.is_multi(), self.max_values) {
+            checks.push_str(&format!(
+                "\tif len(args.{0}) > {1}:\n\
+                 \t\tparser.error('unrecognized arguments: ' + ' '.join(args.{0}[{1}:]))\n",
+                self.c_var, max
+            ));
+        }
+        checks
+    }
+}
+
+impl NonPositionalItem {
+    /// The add_argument call for the item. Options that are not given are left out of the
+    /// namespace, which is how the post loop tells whether they were.
+    fn pygen_add_argument(&self, translate: bool) -> String {
+        let mut names: Vec<String> = self.short.iter().map(|s| format!("-{}", s)).collect();
+        names.extend(self.long_names().map(|long| format!("--{}", long)));
+        let mut call = format!(
+            "\tparser.add_argument({}, dest={}, default=argparse.SUPPRESS",
+            names
+                .iter()
+                .map(|name| py_quote(name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            py_quote(&self.c_var)
+        );
+        if self.is_count() {
+            call.push_str(", action='count'");
+        } else if self.is_flag() {
+            call.push_str(", action='store_true'");
+        } else {
+            if self.is_map() {
+                call.push_str(", action='append', type=key_value");
+            } else if let CType::Int = self.c_type {
+                call.push_str(", type=int");
+            }
+            call.push_str(&format!(", metavar={}", py_quote(self.metavar())));
+        }
+        if self.is_required() {
+            call.push_str(", required=True");
+        }
+        if self.is_hidden() {
+            call.push_str(", help=argparse.SUPPRESS");
+        } else {
+            call.push_str(&py_help(&self.descr(), translate));
+        }
+        call.push(')');
+        // the action argparse made warns when it is given by a deprecated alias
+        let warnings: Vec<String> = self
+            .deprecated_aliases()
+            .map(|alias| {
+                format!(
+                    "{}: {}",
+                    py_quote(&format!("--{}", alias)),
+                    py_quote(&self.alias_warning(alias))
+                )
+            })
+            .collect();
+        if !warnings.is_empty() {
+            call = call.replacen(
+                "parser.add_argument(",
+                "warn_when_given(parser.add_argument(",
+                1,
+            );
+            call.push_str(&format!(", {{{}}})", warnings.join(", ")));
+        }
+        call.push('\n');
+        call
+    }
+    /// Warns on stderr if the item is deprecated and was given.
+    fn pygen_warn(&self) -> String {
+        match self.deprecation_warning() {
+            Some(warning) => format!(
+                "\tif {} in given:\n\t\tprint(parser.prog + {}, file=sys.stderr)\n",
+                py_quote(&self.c_var),
+                py_quote(&format!(": {}", warning))
+            ),
+            None => String::new(),
+        }
+    }
+    /// Assigns the item if it was not given. Map pairs become a dict, later names winning.
+    fn pygen_post_loop(&self) -> String {
+        if self.is_map() {
+            return format!(
+                "\targs.{} = dict(getattr(args, {}, ()))\n",
+                self.c_var,
+                py_quote(&self.c_var)
+            );
+        }
+        let value = if self.is_required() {
+            return String::new();
+        } else if self.is_count() {
+            String::from("0")
+        } else if self.is_flag() {
+            String::from("False")
+        } else {
+            match &self.default {
+                Some(default) => py_value(self.c_type, default),
+                None => String::from("None"),
+            }
+        };
+        format!(
+            "\tif {} not in given:\n\t\targs.{} = {}\n",
+            py_quote(&self.c_var),
+            self.c_var,
+            value
+        )
+    }
+}
+
+impl Spec {
+    /// Checks groups, requires and conflicts_with against the set of dests that were given.
+    fn pygen_constraints(&self) -> String {
+        let mut checks = String::new();
+        for group in self.groups.iter().flatten() {
+            let pairs: Vec<String> = group
+                .options
+                .iter()
+                .filter_map(|long| self.find_non_positional(long))
+                .map(|npi| {
+                    format!(
+                        "({}, {})",
+                        py_quote(&format!("--{}", npi.long)),
+                        py_quote(&npi.c_var)
+                    )
+                })
+                .collect();
+            checks.push_str(&format!(
+                "\tgroup = [name for name, dest in [{}] if dest in given]\n",
+                pairs.join(", ")
+            ));
+            if group.is_exclusive() {
+                checks.push_str(
+                    "\tif len(group) > 1:\n\
+                     \t\tparser.error('mutually exclusive options given: ' + ' '.join(group))\n",
+                );
+            }
+            if group.is_required() {
+                let names = group
+                    .options
+                    .iter()
+                    .map(|long| format!("--{}", long))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                checks.push_str(&format!(
+                    "\tif not group:\n\t\tparser.error({})\n",
+                    py_quote(&format!("one of these options is required: {}", names))
+                ));
+            }
+        }
+        for npi in &self.non_positional {
+            let requires = npi.requires.iter().flatten();
+            for other in requires.filter_map(|long| self.find_non_positional(long)) {
+                checks.push_str(&format!(
+                    "\tif {} in given and {} not in given:\n\t\tparser.error({})\n",
+                    py_quote(&npi.c_var),
+                    py_quote(&other.c_var),
+                    py_quote(&format!("option --{} requires --{}", npi.long, other.long))
+                ));
+            }
+            let conflicts = npi.conflicts_with.iter().flatten();
+            for other in conflicts.filter_map(|long| self.find_non_positional(long)) {
+                checks.push_str(&format!(
+                    "\tif {} in given and {} in given:\n\t\tparser.error({})\n",
+                    py_quote(&npi.c_var),
+                    py_quote(&other.c_var),
+                    py_quote(&format!(
+                        "option --{} conflicts with --{}",
+                        npi.long, other.long
+                    ))
+                ));
+            }
+        }
+        checks
+    }
+}
+
+impl CodeGenerator for Python {
+    fn prelude(&self, spec: &Spec) -> String {
+        match &spec.text_domain {
+            Some(domain) => format!(
+                "#!/usr/bin/env python3\n\
+                 # Generated by argen.\n\
+                 import argparse\n\
+                 import gettext\n\
+                 import sys\n\n\
+                 _ = gettext.translation({}, {}, fallback=True).gettext\n\n\n",
+                py_quote(domain),
+                spec.locale_dir
+                    .as_ref()
+                    .map_or_else(|| String::from("None"), |dir| py_quote(dir))
+            ),
+            None => String::from(
+                "#!/usr/bin/env python3\n\
+                 # Generated by argen.\n\
+                 import argparse\n\
+                 import sys\n\n\n",
+            ),
+        }
+    }
+    /// argparse prints help itself.
+    fn usage(&self, _spec: &Spec) -> String {
+        String::new()
+    }
+    fn helpers(&self, spec: &Spec) -> String {
+        let mut helpers = String::new();
+        if spec.has_response_files() {
+            helpers.push_str(
+                "class ArgumentParser(argparse.ArgumentParser):\n\
+                 \t\"\"\"Reads @file arguments as whitespace-separated words.\"\"\"\n\n\
+                 \tdef convert_arg_line_to_args(self, arg_line):\n\
+                 \t\treturn arg_line.split()\n\n\n",
+            );
+        }
+        if spec.has_map() {
+            helpers.push_str(
+                "def key_value(arg):\n\
+                 \t\"\"\"Splits the NAME=VALUE argument of a map option.\"\"\"\n\
+                 \tname, eq, value = arg.partition('=')\n\
+                 \tif not eq:\n\
+                 \t\traise argparse.ArgumentTypeError('expected NAME=VALUE, got %r' % arg)\n\
+                 \treturn name, value\n\n\n",
+            );
+        }
+        if spec
+            .non_positional
+            .iter()
+            .any(|npi| npi.deprecated_aliases().next().is_some())
+        {
+            // argparse gives actions the option string it matched, abbreviations spelled out
+            helpers.push_str(
+                "def warn_when_given(action, warnings):\n\
+                 \t\"\"\"Makes action print the warning for an option string when given by it.\"\"\"\n\
+                 \tclass WarningAction(type(action)):\n\
+                 \t\tdef __call__(self, parser, namespace, values, option_string=None):\n\
+                 \t\t\tif option_string in warnings:\n\
+                 \t\t\t\tprint(parser.prog + ': ' + warnings[option_string], file=sys.stderr)\n\
+                 \t\t\tsuper().__call__(parser, namespace, values, option_string)\n\n\
+                 \taction.__class__ = WarningAction\n\
+                 \treturn action\n\n\n",
+            );
+        }
+        helpers
+    }
+    fn decls(&self, spec: &Spec) -> String {
+        let mut kwargs = Vec::new();
+        if let Some(name) = &spec.program_name {
+            kwargs.push(format!("prog={}", py_quote(name)));
+        }
+        if let Some(about) = &spec.about {
+            kwargs.push(format!(
+                "description={}",
+                py_text(about, spec.text_domain.is_some())
+            ));
+        }
+        if let Some(width) = spec.help_width {
+            kwargs.push(format!(
+                "formatter_class=lambda prog: argparse.HelpFormatter(prog, width={})",
+                width
+            ));
+        }
+        // argparse takes prefixes unless told not to
+        if !spec.allows_abbrev() {
+            kwargs.push(String::from("allow_abbrev=False"));
+        }
+        let class = if spec.has_response_files() {
+            kwargs.push(String::from("fromfile_prefix_chars='@'"));
+            "ArgumentParser"
+        } else {
+            "argparse.ArgumentParser"
+        };
+        format!(
+            "def parse_args(argv=None):\n\tparser = {}({})\n",
+            class,
+            kwargs.join(", ")
+        )
+    }
+    fn parse_item(&self, spec: &Spec, i: usize) -> String {
+        let npi = &spec.non_positional[i];
+        let call = npi.pygen_add_argument(spec.text_domain.is_some());
+        let titles = spec.help_groups();
+        match titles
+            .iter()
+            .position(|&title| npi.group.as_deref() == Some(title))
+        {
+            Some(group) => call.replacen("parser.", &format!("groups[{}].", group), 1),
+            None => call,
+        }
+    }
+    fn parse_loop(&self, spec: &Spec, items: &str) -> String {
+        let mut body = String::new();
+        for pi in &spec.positional {
+            body.push_str(&pi.pygen_add_argument(spec.text_domain.is_some()));
+        }
+        if let Some(version) = &spec.version {
+            body.push_str(&format!(
+                "\tparser.add_argument('-V', '--version', action='version', version={})\n",
+                py_quote(&format!("%(prog)s {}", version.replace('%', "%%")))
+            ));
+        }
+        let titles = spec.help_groups();
+        if !titles.is_empty() {
+            let groups: Vec<String> = titles
+                .iter()
+                .map(|title| {
+                    format!(
+                        "parser.add_argument_group({})",
+                        py_text(title, spec.text_domain.is_some())
+                    )
+                })
+                .collect();
+            body.push_str(&format!("\tgroups = [{}]\n", groups.join(", ")));
+        }
+        body.push_str(items);
+        // intermixed parsing takes options after positionals, as the C target does
+        if spec.is_strict_order() {
+            body.push_str("\targs = parser.parse_args(argv)\n");
+        } else {
+            body.push_str("\targs = parser.parse_intermixed_args(argv)\n");
+        }
+        body
+    }
+    fn post_loop(&self, spec: &Spec) -> String {
+        let mut body = String::from("\tgiven = set(vars(args))\n");
+        for pi in &spec.positional {
+            body.push_str(&pi.pygen_check_arity());
+        }
+        for npi in &spec.non_positional {
+            body.push_str(&npi.pygen_warn());
+        }
+        body.push_str(&spec.pygen_constraints());
+        for npi in &spec.non_positional {
+            body.push_str(&npi.pygen_post_loop());
+        }
+        body.push_str("\treturn args\n\n\n");
+        body
+    }
+    fn main(&self, _spec: &Spec) -> String {
+        let mut main = user_region("#", "", "user-helpers", "");
+        main.push_str("\n\ndef main():\n\targs = parse_args()\n\n");
+        main.push_str(&user_region(
+            "#",
+            "\t",
+            "user-code",
+            "\t# call your code here\n\treturn 0\n",
+        ));
+        main.push_str("\n\nif __name__ == '__main__':\n\tsys.exit(main())\n");
+        main
+    }
+    /// Pieces are indented with tabs like the C target's, which become four spaces here.
+    fn finish_lines(&self, _spec: &Spec) -> Box<dyn FnMut(&str) -> String> {
+        Box::new(|lines| lines.replace('\t', "    "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{py_int, py_quote, CodeGenerator, Python, Spec};
+    use std::str::FromStr;
+
+    #[test]
+    fn python_literals() {
+        assert_eq!(py_quote("it's a\\b\n"), r"'it\'s a\\b\n'");
+        assert_eq!(py_int("017"), "0o17");
+        assert_eq!(py_int("-017"), "-0o17");
+        assert_eq!(py_int("0x1F"), "0x1F");
+        assert_eq!(py_int("0"), "0");
+    }
+
+    #[test]
+    fn argparse_calls() {
+        let py = Python.gen(
+            &Spec::from_str(
+                r#"
+                program_name = "demo"
+                version = "1.0"
+                [[positional]]
+                c_var = "input"
+                c_type = "char*"
+                help_name = "FILE"
+                required = true
+                [[positional]]
+                c_var = "words"
+                c_type = "char*"
+                help_name = "WORD"
+                multi = true
+                [[non_positional]]
+                c_var = "block_size"
+                c_type = "int"
+                long = "block-size"
+                short = "b"
+                aliases = ["bs"]
+                help_descr = "100% of blocks"
+                default = "012"
+                [[non_positional]]
+                c_var = "verbose"
+                c_type = "int"
+                long = "verbose"
+                short = "v"
+                count = true
+                "#,
+            )
+            .unwrap(),
+        );
+        assert!(
+            py.contains("    parser = argparse.ArgumentParser(prog='demo', allow_abbrev=False)\n")
+        );
+        assert!(py.contains(
+            "    parser.add_argument('-b', '--block-size', '--bs', dest='block_size', \
+             default=argparse.SUPPRESS, type=int, metavar='NUM', help='100%% of blocks')\n"
+        ));
+        assert!(py.contains("    parser.add_argument('words', metavar='WORD', nargs='*')\n"));
+        assert!(py.contains("version='%(prog)s 1.0'"));
+        assert!(py.contains("    if 'block_size' not in given:\n        args.block_size = 0o12\n"));
+        assert!(py.contains("        args.verbose = 0\n"));
+        assert!(!py.contains('\t'));
+    }
+}
@@ -0,0 +1,192 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+const BEGIN_MARKER: &str = "ARGEN:BEGIN ";
+const END_MARKER: &str = "ARGEN:END";
+
+/// Error type for user code regions that cannot be carried over safely
+#[derive(Debug, PartialEq)]
+pub enum RegionError {
+    Unterminated(String),
+    UnexpectedEnd(usize),
+    Duplicate(String),
+    Dropped(String),
+}
+impl fmt::Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegionError::Unterminated(name) => write!(f, "region {} has no ARGEN:END", name),
+            RegionError::UnexpectedEnd(line) => {
+                write!(f, "line {}: ARGEN:END outside of a region", line)
+            }
+            RegionError::Duplicate(name) => write!(f, "region {} appears more than once", name),
+            RegionError::Dropped(name) => write!(
+                f,
+                "region {} is not in the new output, move its code elsewhere first",
+                name
+            ),
+        }
+    }
+}
+impl Error for RegionError {}
+
+/// The name of the region a line begins, if it is a begin marker.
+fn begin_name(line: &str) -> Option<&str> {
+    let at = line.find(BEGIN_MARKER)?;
+    line[at + BEGIN_MARKER.len()..].split_whitespace().next()
+}
+
+fn is_end(line: &str) -> bool {
+    line.contains(END_MARKER)
+}
+
+/// A user code region and the text between it and the previous one. Marker lines belong to the
+/// text around bodies.
+struct Region {
+    before: String,
+    name: String,
+    body: String,
+}
+
+/// Splits text into its regions and whatever follows the last one.
+fn split_regions(text: &str) -> Result<(Vec<Region>, String), RegionError> {
+    let mut regions = Vec::new();
+    let mut outside = String::new();
+    let mut inside: Option<(String, String)> = None;
+    for (n, line) in text.split_inclusive('\n').enumerate() {
+        match inside.take() {
+            Some((name, body)) if is_end(line) => {
+                regions.push(Region {
+                    before: outside,
+                    name,
+                    body,
+                });
+                outside = line.to_owned();
+            }
+            Some((name, mut body)) => {
+                if begin_name(line).is_some() {
+                    return Err(RegionError::Unterminated(name));
+                }
+                body.push_str(line);
+                inside = Some((name, body));
+            }
+            None => {
+                outside.push_str(line);
+                if let Some(name) = begin_name(line) {
+                    if regions.iter().any(|r: &Region| r.name == name) {
+                        return Err(RegionError::Duplicate(name.to_owned()));
+                    }
+                    inside = Some((name.to_owned(), String::new()));
+                } else if is_end(line) {
+                    return Err(RegionError::UnexpectedEnd(n + 1));
+                }
+            }
+        }
+    }
+    match inside {
+        Some((name, _)) => Err(RegionError::Unterminated(name)),
+        None => Ok((regions, outside)),
+    }
+}
+
+/// Carries the user code regions of a previously generated file over into freshly generated
+/// code. Regions are delimited by comments containing `ARGEN:BEGIN <name>` and `ARGEN:END`; the
+/// body of each region in existing replaces the body of the same region in generated. Fails
+/// rather than lose code if existing has a region generated does not.
+pub fn preserve_regions(generated: &str, existing: &str) -> Result<String, RegionError> {
+    let (old, _) = split_regions(existing)?;
+    let mut kept: HashMap<String, String> = old
+        .into_iter()
+        .map(|region| (region.name, region.body))
+        .collect();
+    let (new, tail) = split_regions(generated)?;
+    let mut merged = String::new();
+    for region in new {
+        merged.push_str(&region.before);
+        merged.push_str(&kept.remove(&region.name).unwrap_or(region.body));
+    }
+    merged.push_str(&tail);
+    match kept.into_keys().min() {
+        Some(name) => Err(RegionError::Dropped(name)),
+        None => Ok(merged),
+    }
+}
+
+/// A region for user code, initially holding body. Markers are comments opened by comment and
+/// indented like body.
+pub(crate) fn user_region(comment: &str, indent: &str, name: &str, body: &str) -> String {
+    let close = if comment == "/*" { " */" } else { "" };
+    format!(
+        "{indent}{c} {begin}{name}{close}\n{body}{indent}{c} {end}{close}\n",
+        indent = indent,
+        c = comment,
+        begin = BEGIN_MARKER,
+        name = name,
+        close = close,
+        body = body,
+        end = END_MARKER
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{preserve_regions, user_region, RegionError};
+
+    fn generated() -> String {
+        format!(
+            "int main(void) {{\n{}}}\n",
+            user_region("/*", "\t", "user-code", "\t/* call your code here */\n")
+        )
+    }
+
+    #[test]
+    fn keeps_user_code() {
+        let existing = "int main(void) {\n\
+                        \t/* ARGEN:BEGIN user-code */\n\
+                        \tputs(\"hi\");\n\
+                        \t/* ARGEN:END */\n\
+                        }\n";
+        let changed = generated().replace("void", "int argc, char **argv");
+        assert_eq!(
+            preserve_regions(&changed, existing).unwrap(),
+            existing.replace("void", "int argc, char **argv")
+        );
+        assert_eq!(preserve_regions(&generated(), "").unwrap(), generated());
+    }
+
+    #[test]
+    fn refuses_to_lose_code() {
+        let unterminated = "/* ARGEN:BEGIN user-code */\nputs(\"hi\");\n";
+        assert_eq!(
+            preserve_regions(&generated(), unterminated),
+            Err(RegionError::Unterminated(String::from("user-code")))
+        );
+        let stray = "x\n/* ARGEN:END */\n";
+        assert_eq!(
+            preserve_regions(&generated(), stray),
+            Err(RegionError::UnexpectedEnd(2))
+        );
+        let other = "# ARGEN:BEGIN user-helpers\ndef f():\n\tpass\n# ARGEN:END\n";
+        assert_eq!(
+            preserve_regions(&generated(), other),
+            Err(RegionError::Dropped(String::from("user-helpers")))
+        );
+    }
+}
@@ -0,0 +1,442 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::regions::user_region;
+use super::{CType, CodeGenerator, NonPositionalItem, PositionalItem, Spec};
+
+/// Keywords that need the r# prefix to be used as field names.
+const RUST_KEYWORDS: [&str; 47] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual",
+    "where", "while", "yield",
+];
+
+/// Generates a Rust struct that derives clap's Parser.
+pub struct Rust;
+
+/// rs_quote makes a Rust string literal.
+fn rs_quote(i: &str) -> String {
+    format!("{:?}", i)
+}
+
+/// rs_ident makes c_var usable as a Rust field name.
+fn rs_ident(c_var: &str) -> String {
+    if RUST_KEYWORDS.contains(&c_var) {
+        format!("r#{}", c_var)
+    } else {
+        c_var.to_owned()
+    }
+}
+
+/// rs_int turns a C integer literal into a Rust one, which spells octal 0o17 rather than 017.
+fn rs_int(lit: &str) -> String {
+    let (sign, digits) = lit.split_at(lit.len() - lit.trim_start_matches(&['-', '+'][..]).len());
+    let sign = sign.trim_start_matches('+');
+    if let Some(hex) = digits.strip_prefix("0X") {
+        format!("{}0x{}", sign, hex)
+    } else if digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0x") {
+        format!("{}0o{}", sign, &digits[1..])
+    } else {
+        format!("{}{}", sign, digits)
+    }
+}
+
+/// The Rust type a value of c_type is stored as. Custom values are left as text, since their
+/// converters are C.
+fn rs_type(c_type: CType) -> &'static str {
+    match c_type {
+        CType::Chars | CType::Custom => "String",
+        CType::Int => "i64",
+    }
+}
+
+/// The help argument of an #[arg], if any.
+fn rs_help(descr: &Option<String>) -> Option<String> {
+    descr
+        .as_ref()
+        .map(|descr| format!("help = {}", rs_quote(descr)))
+}
+
+/// A field of the Args struct with its #[arg] attribute.
+fn rs_field(attrs: &[String], c_var: &str, ty: &str) -> String {
+    let attr = if attrs.is_empty() {
+        String::new()
+    } else {
+        format!("\t#[arg({})]\n", attrs.join(", "))
+    };
+    format!("{}\tpub {}: {},\n", attr, rs_ident(c_var), ty)
+}
+
+impl PositionalItem {
+    /// The item as a field of Args.
+    fn rsgen_field(&self) -> String {
+        let mut attrs = vec![format!("value_name = {}", rs_quote(&self.help_name))];
+        attrs.extend(rs_help(&self.help_descr));
+        let base = rs_type(self.c_type);
+        let ty = if self.is_multi() {
+            if self.is_required() {
+                attrs.push(String::from("required = true"));
+            }
+            if self.has_arity() {
+                let max = self.max_values().map(|max| format!("={}", max));
+                attrs.push(format!(
+                    "num_args = {}..{}",
+                    self.min_values(),
+                    max.unwrap_or_default()
+                ));
+            }
+            format!("Vec<{}>", base)
+        } else if self.is_required() || self.has_default() {
+            base.to_owned()
+        } else {
+            format!("Option<{}>", base)
+        };
+        match (&self.default, self.c_type) {
+            (Some(default), CType::Int) => {
+                attrs.push(format!("default_value_t = {}", rs_int(default)))
+            }
+            (Some(default), CType::Chars | CType::Custom) => {
+                attrs.push(format!("default_value = {}", rs_quote(default)))
+            }
+            (None, _) => (),
+        }
+        rs_field(&attrs, &self.c_var, &ty)
+    }
+}
+
+impl NonPositionalItem {
+    /// The item as a field of Args.
+    fn rsgen_field(&self, spec: &Spec) -> String {
+        let mut attrs = Vec::new();
+        if let Some(short) = &self.short {
+            attrs.push(format!("short = {:?}", short.chars().next().unwrap()));
+        }
+        attrs.push(format!("long = {}", rs_quote(&self.long)));
+        // clap cannot note which aliases are deprecated in help, so it leaves them out
+        let visible: Vec<String> = self
+            .current_long_names()
+            .skip(1)
+            .map(|a| rs_quote(a))
+            .collect();
+        if !visible.is_empty() {
+            attrs.push(format!("visible_aliases = [{}]", visible.join(", ")));
+        }
+        let deprecated: Vec<String> = self.deprecated_aliases().map(|a| rs_quote(a)).collect();
+        if !deprecated.is_empty() {
+            attrs.push(format!("aliases = [{}]", deprecated.join(", ")));
+        }
+        if !self.is_flag() {
+            attrs.push(format!("value_name = {}", rs_quote(self.metavar())));
+        }
+        attrs.extend(rs_help(&self.descr()));
+        if self.is_hidden() {
+            attrs.push(String::from("hide = true"));
+        }
+        if let Some(group) = &self.group {
+            attrs.push(format!("help_heading = {}", rs_quote(group)));
+        }
+        let base = rs_type(self.c_type);
+        let ty = if self.is_map() {
+            attrs.push(String::from("value_parser = parse_key_value"));
+            String::from("Vec<(String, String)>")
+        } else if self.is_count() {
+            attrs.push(String::from("action = ArgAction::Count"));
+            String::from("u8")
+        } else if self.is_flag() {
+            String::from("bool")
+        } else if let Some(default) = &self.default {
+            match self.c_type {
+                CType::Chars | CType::Custom => {
+                    attrs.push(format!("default_value = {}", rs_quote(default)))
+                }
+                CType::Int => attrs.push(format!("default_value_t = {}", rs_int(default))),
+            }
+            base.to_owned()
+        } else if self.is_required() {
+            base.to_owned()
+        } else {
+            format!("Option<{}>", base)
+        };
+        let ids = |longs: &Option<Vec<String>>| -> Vec<String> {
+            longs
+                .iter()
+                .flatten()
+                .filter_map(|long| spec.find_non_positional(long))
+                .map(|npi| rs_quote(&npi.c_var))
+                .collect()
+        };
+        let requires = ids(&self.requires);
+        if !requires.is_empty() {
+            attrs.push(format!("requires_all = [{}]", requires.join(", ")));
+        }
+        let conflicts = ids(&self.conflicts_with);
+        if !conflicts.is_empty() {
+            attrs.push(format!("conflicts_with_all = [{}]", conflicts.join(", ")));
+        }
+        rs_field(&attrs, &self.c_var, &ty)
+    }
+}
+
+impl Spec {
+    fn has_deprecated(&self) -> bool {
+        self.non_positional
+            .iter()
+            .any(|npi| npi.deprecated.is_some() || npi.deprecated_aliases().next().is_some())
+    }
+    /// Parses through ArgMatches rather than Args::parse, to see which deprecated options were
+    /// actually given and not just defaulted.
+    fn rsgen_parse_args(&self) -> String {
+        let mut parse = String::from(
+            "/// Parses the command line, warning about deprecated options that were given.\n\
+             fn parse_args() -> Args {\n\
+             \tlet mut command = Args::command();\n\
+             \tlet matches = command.get_matches_mut();\n",
+        );
+        for npi in &self.non_positional {
+            if let Some(warning) = npi.deprecation_warning() {
+                parse.push_str(&format!(
+                    "\tif matches.value_source({}) == Some(ValueSource::CommandLine) {{\n\
+                     \t\teprintln!({}, command.get_name());\n\
+                     \t}}\n",
+                    rs_quote(&npi.c_var),
+                    rs_quote(&format!(
+                        "{{}}: {}",
+                        warning.replace('{', "{{").replace('}', "}}")
+                    ))
+                ));
+            }
+        }
+        // clap does not say which name an option was given by, so deprecated aliases are looked
+        // for among the arguments it took options from
+        let aliased: Vec<&NonPositionalItem> = self
+            .non_positional
+            .iter()
+            .filter(|npi| npi.deprecated_aliases().next().is_some())
+            .collect();
+        if !aliased.is_empty() {
+            parse.push_str(
+                "\tlet given: Vec<String> = std::env::args().skip(1).take_while(|arg| arg != \"--\").collect();\n",
+            );
+        }
+        for npi in aliased {
+            for alias in npi.deprecated_aliases() {
+                parse.push_str(&format!(
+                    "\tif given.iter().any(|arg| arg.split('=').next() == Some({})) {{\n\
+                     \t\teprintln!({}, command.get_name());\n\
+                     \t}}\n",
+                    rs_quote(&format!("--{}", alias)),
+                    rs_quote(&format!(
+                        "{{}}: {}",
+                        npi.alias_warning(alias)
+                            .replace('{', "{{")
+                            .replace('}', "}}")
+                    ))
+                ));
+            }
+        }
+        parse.push_str("\tArgs::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())\n}\n\n");
+        parse
+    }
+}
+
+impl CodeGenerator for Rust {
+    fn prelude(&self, spec: &Spec) -> String {
+        let mut uses = vec!["Parser"];
+        if spec.non_positional.iter().any(NonPositionalItem::is_count) {
+            uses.insert(0, "ArgAction");
+        }
+        if spec.groups.iter().flatten().next().is_some() {
+            uses.insert(uses.len() - 1, "ArgGroup");
+        }
+        if spec.has_deprecated() {
+            uses.insert(uses.len() - 1, "CommandFactory");
+            uses.insert(uses.len() - 1, "FromArgMatches");
+            uses.push("parser::ValueSource");
+        }
+        let uses = match uses.as_slice() {
+            [single] => single.to_string(),
+            _ => format!("{{{}}}", uses.join(", ")),
+        };
+        format!(
+            "// Generated by argen. Needs clap 4 with the derive feature.\n\
+             use clap::{};\n\n",
+            uses
+        )
+    }
+    /// clap prints help itself.
+    fn usage(&self, _spec: &Spec) -> String {
+        String::new()
+    }
+    fn helpers(&self, spec: &Spec) -> String {
+        let mut helpers = String::new();
+        if spec.has_deprecated() {
+            helpers.push_str(&spec.rsgen_parse_args());
+        }
+        if spec.has_map() {
+            helpers.push_str(
+                "/// Splits the NAME=VALUE argument of a map option.\n\
+                 fn parse_key_value(arg: &str) -> Result<(String, String), String> {\n\
+                 \tmatch arg.split_once('=') {\n\
+                 \t\tSome((name, value)) => Ok((name.to_owned(), value.to_owned())),\n\
+                 \t\tNone => Err(format!(\"expected NAME=VALUE, got '{}'\", arg)),\n\
+                 \t}\n\
+                 }\n\n",
+            );
+        }
+        helpers
+    }
+    fn decls(&self, spec: &Spec) -> String {
+        let mut attrs = Vec::new();
+        if let Some(name) = &spec.program_name {
+            attrs.push(format!("name = {}", rs_quote(name)));
+        }
+        if let Some(version) = &spec.version {
+            attrs.push(format!("version = {}", rs_quote(version)));
+        }
+        if let Some(about) = &spec.about {
+            attrs.push(format!("about = {}", rs_quote(about)));
+        }
+        if let Some(width) = spec.help_width {
+            attrs.push(format!("term_width = {}", width));
+        }
+        if spec.allows_abbrev() {
+            attrs.push(String::from("infer_long_args = true"));
+        }
+        let takes_values =
+            !spec.positional.is_empty() || spec.non_positional.iter().any(|npi| !npi.is_flag());
+        if spec.takes_negative_numbers() && takes_values {
+            attrs.push(String::from("allow_negative_numbers = true"));
+        }
+        let mut decls = String::from("#[derive(Parser, Debug)]\n");
+        if !attrs.is_empty() {
+            decls.push_str(&format!("#[command({})]\n", attrs.join(", ")));
+        }
+        for group in spec.groups.iter().flatten() {
+            let ids: Vec<String> = group
+                .options
+                .iter()
+                .filter_map(|long| spec.find_non_positional(long))
+                .map(|npi| rs_quote(&npi.c_var))
+                .collect();
+            decls.push_str(&format!(
+                "#[command(group(ArgGroup::new({}).args([{}]).multiple({}).required({})))]\n",
+                rs_quote(&group.name()),
+                ids.join(", "),
+                !group.is_exclusive(),
+                group.is_required()
+            ));
+        }
+        decls.push_str("pub struct Args {\n");
+        decls
+    }
+    fn parse_item(&self, spec: &Spec, i: usize) -> String {
+        spec.non_positional[i].rsgen_field(spec)
+    }
+    fn parse_loop(&self, spec: &Spec, items: &str) -> String {
+        let mut fields: String = spec.positional.iter().map(|pi| pi.rsgen_field()).collect();
+        fields.push_str(items);
+        fields.push_str("}\n\n");
+        fields
+    }
+    /// clap checks everything while parsing.
+    fn post_loop(&self, _spec: &Spec) -> String {
+        String::new()
+    }
+    fn main(&self, spec: &Spec) -> String {
+        let parse = if spec.has_deprecated() {
+            "parse_args()"
+        } else {
+            "Args::parse()"
+        };
+        let mut main = user_region("//", "", "user-helpers", "");
+        main.push_str(&format!("\nfn main() {{\n\tlet args = {};\n\n", parse));
+        main.push_str(&user_region(
+            "//",
+            "\t",
+            "user-code",
+            "\t// call your code here\n\tlet _ = args;\n",
+        ));
+        main.push_str("}\n");
+        main
+    }
+    /// Pieces are indented with tabs like the C target's, which become four spaces as rustfmt
+    /// would have them.
+    fn finish_lines(&self, _spec: &Spec) -> Box<dyn FnMut(&str) -> String> {
+        Box::new(|lines| lines.replace('\t', "    "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rs_ident, rs_int, CodeGenerator, Rust, Spec};
+    use std::str::FromStr;
+
+    #[test]
+    fn rust_literals() {
+        assert_eq!(rs_int("017"), "0o17");
+        assert_eq!(rs_int("+0XfF"), "0xfF");
+        assert_eq!(rs_int("-12"), "-12");
+        assert_eq!(rs_ident("type"), "r#type");
+        assert_eq!(rs_ident("block_size"), "block_size");
+    }
+
+    #[test]
+    fn derive_fields() {
+        let rs = Rust.gen(
+            &Spec::from_str(
+                r#"
+                program_name = "demo"
+                [[positional]]
+                c_var = "input"
+                c_type = "char*"
+                help_name = "FILE"
+                required = true
+                [[non_positional]]
+                c_var = "block_size"
+                c_type = "int"
+                long = "block-size"
+                short = "b"
+                aliases = ["bs"]
+                default = "0x10"
+                [[non_positional]]
+                c_var = "verbose"
+                c_type = "int"
+                long = "verbose"
+                count = true
+                conflicts_with = ["block-size"]
+                "#,
+            )
+            .unwrap(),
+        );
+        assert!(rs.starts_with(
+            "// Generated by argen. Needs clap 4 with the derive feature.\n\
+                                use clap::{ArgAction, Parser};\n"
+        ));
+        assert!(rs.contains(
+            "#[command(name = \"demo\", allow_negative_numbers = true)]\npub struct Args {\n"
+        ));
+        assert!(rs.contains("    #[arg(value_name = \"FILE\")]\n    pub input: String,\n"));
+        assert!(rs.contains(
+            "    #[arg(short = 'b', long = \"block-size\", visible_aliases = [\"bs\"], \
+             value_name = \"NUM\", default_value_t = 0x10)]\n    pub block_size: i64,\n"
+        ));
+        assert!(rs.contains(
+            "action = ArgAction::Count, conflicts_with_all = [\"block_size\"])]\n    pub verbose: u8,\n"
+        ));
+    }
+}
@@ -0,0 +1,375 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::Spec;
+use serde_json::{json, Value};
+
+/// A property holding a boolean, false unless given.
+fn flag(description: &str) -> Value {
+    json!({"type": "boolean", "default": false, "description": description})
+}
+
+fn string(description: &str) -> Value {
+    json!({"type": "string", "description": description})
+}
+
+fn strings(description: &str) -> Value {
+    json!({"type": "array", "items": {"type": "string"}, "description": description})
+}
+
+fn c_var() -> Value {
+    json!({
+        "type": "string",
+        "pattern": "^[_a-zA-Z][_a-zA-Z0-9]*$",
+        "description": "Name of the C variable the argument is stored in."
+    })
+}
+
+fn c_type() -> Value {
+    json!({
+        "enum": ["char*", "int", "custom"],
+        "description": "Type of the C variable. custom values are read by the converter \
+                        function into a c_decl_type."
+    })
+}
+
+/// Fields that positional arguments and options have in common.
+fn common_properties() -> serde_json::Map<String, Value> {
+    let help_name = "Placeholder for the value in help output.";
+    let properties = json!({
+        "c_var": c_var(),
+        "c_type": c_type(),
+        "help_name": string(help_name),
+        "metavar": string(help_name),
+        "help_descr": string("Description shown in help output."),
+        "required": flag("Whether the argument must be given."),
+        "default": string("Value used when the argument is not given, written as in C."),
+        "validator": string(
+            "Name of a C function, left to you to write, that checks each value given."
+        ),
+        "converter": string(
+            "With c_type custom, name of a C function, left to you to write, that reads \
+             values into a c_decl_type."
+        ),
+        "c_decl_type": string("With c_type custom, the C type the variable is declared with."),
+    });
+    match properties {
+        Value::Object(properties) => properties,
+        _ => unreachable!(),
+    }
+}
+
+fn exit_code(description: &str) -> Value {
+    json!({
+        "type": "integer",
+        "minimum": 1,
+        "maximum": 255,
+        "default": 1,
+        "description": description
+    })
+}
+
+fn positional() -> Value {
+    let mut properties = common_properties();
+    properties.insert(
+        String::from("multi"),
+        flag("Whether the argument takes all the values left. Only the last one can."),
+    );
+    let count =
+        |description: &str| json!({"type": "integer", "minimum": 0, "description": description});
+    properties.insert(
+        String::from("min_values"),
+        count("With multi, the fewest values taken, 1 if required and 0 otherwise if unset."),
+    );
+    properties.insert(
+        String::from("max_values"),
+        count("With multi, the most values taken, any number if unset."),
+    );
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": ["c_var", "c_type"],
+        "anyOf": [{"required": ["help_name"]}, {"required": ["metavar"]}],
+        "additionalProperties": false
+    })
+}
+
+fn non_positional() -> Value {
+    let mut properties = common_properties();
+    let more = json!({
+        "long": string("Name of the option, as in --long."),
+        "short": {
+            "type": "string",
+            "minLength": 1,
+            "maxLength": 1,
+            "description": "Single-character name of the option, as in -s."
+        },
+        "aliases": {
+            "type": "array",
+            "items": {
+                "anyOf": [
+                    {"type": "string"},
+                    {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string"},
+                            "deprecated": flag(
+                                "Whether giving the alias warns to use the long name instead."
+                            )
+                        },
+                        "required": ["name"],
+                        "additionalProperties": false
+                    }
+                ]
+            },
+            "description": "Other long names for the option, each a name or a table with one."
+        },
+        "flag": flag("Whether the option takes no value, setting its int to 1 when given."),
+        "count": flag("Whether the option is a flag counting how many times it was given."),
+        "map": flag("Whether the option is a repeatable NAME=VALUE option."),
+        "requires": strings("Long names of options that must be given along with this one."),
+        "conflicts_with": strings("Long names of options that cannot be given with this one."),
+        "hidden": flag("Whether the option is left out of help, completions and man pages."),
+        "deprecated": string("Warning printed when the option is given."),
+        "group": string("Title of the help section the option is listed in."),
+        "case_insensitive": {
+            "type": "boolean",
+            "description": "Whether the long names match whatever their case. Defaults to the \
+                            spec's case_insensitive."
+        },
+    });
+    if let Value::Object(more) = more {
+        properties.extend(more);
+    }
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": ["c_var", "c_type", "long"],
+        "additionalProperties": false
+    })
+}
+
+fn group() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "options": {
+                "type": "array",
+                "items": {"type": "string"},
+                "minItems": 2,
+                "description": "Long names of the options in the group."
+            },
+            "exclusive": flag("Whether at most one of the options may be given."),
+            "required": flag("Whether at least one of the options must be given.")
+        },
+        "required": ["options"],
+        "anyOf": [
+            {"properties": {"exclusive": {"const": true}}, "required": ["exclusive"]},
+            {"properties": {"required": {"const": true}}, "required": ["required"]}
+        ],
+        "additionalProperties": false
+    })
+}
+
+fn config_file() -> Value {
+    json!({
+        "type": "object",
+        "description": "File of KEY = VALUE lines giving values for options not on the command \
+                        line, keyed by long name. Only generated C reads it.",
+        "properties": {
+            "path": string("File read if it exists."),
+            "option": string(
+                "Long name of a char* option naming a file to read instead, which must exist."
+            )
+        },
+        "anyOf": [{"required": ["path"]}, {"required": ["option"]}],
+        "additionalProperties": false
+    })
+}
+
+fn style() -> Value {
+    json!({
+        "type": "object",
+        "description": "How generated C is laid out. Other languages ignore it.",
+        "properties": {
+            "indent": {"enum": ["tabs", "spaces"], "default": "tabs"},
+            "indent_width": {
+                "type": "integer",
+                "minimum": 0,
+                "default": 4,
+                "description": "Spaces per level when indenting with spaces."
+            },
+            "braces": {
+                "enum": ["attach", "linux", "allman"],
+                "default": "attach",
+                "description": "Where opening braces go."
+            },
+            "std": {
+                "enum": ["c89", "c99", "c11"],
+                "default": "c99",
+                "description": "C standard to write for."
+            },
+            "target": {
+                "enum": ["posix", "windows"],
+                "default": "posix",
+                "description": "Platform to write for. windows brings its own getopt_long."
+            },
+            "wmain": flag(
+                "With target windows, whether arguments are taken as UTF-16 in wmain and \
+                 passed on as UTF-8."
+            )
+        },
+        "additionalProperties": false
+    })
+}
+
+impl Spec {
+    /// A JSON Schema (draft 2020-12) for spec files, for editors to complete and check them
+    /// with. It is stricter than argen in one way: it rejects fields argen does not know,
+    /// which argen ignores.
+    pub fn json_schema() -> String {
+        let schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "argen spec",
+            "type": "object",
+            "properties": {
+                "include": strings(
+                    "Spec files to merge into this one, relative to it. Options in this \
+                     spec replace included options with the same long name."
+                ),
+                "program_name": string("Name shown in help and version output, argv[0] if unset."),
+                "version": string("Enables -V/--version, which prints this version."),
+                "about": string("Description shown below the usage line."),
+                "strict_order": flag(
+                    "Whether option parsing stops at the first positional argument."
+                ),
+                "help_width": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "default": 80,
+                    "description": "Column help output is wrapped at."
+                },
+                "response_files": flag(
+                    "Whether @FILE arguments are replaced with the words in FILE."
+                ),
+                "allow_abbrev": flag(
+                    "Whether unambiguous prefixes of long option names are accepted."
+                ),
+                "case_insensitive": flag(
+                    "Whether long option names match whatever their case. C and C++ only."
+                ),
+                "negative_numbers": {
+                    "type": "boolean",
+                    "default": true,
+                    "description": "Whether arguments such as -5 and -1.5 are taken as \
+                                    positional arguments or option values rather than options, \
+                                    unless a short option is a digit."
+                },
+                "text_domain": string(
+                    "gettext domain to translate help text in. C and Python only."
+                ),
+                "locale_dir": string(
+                    "Directory the text domain's catalogs are in, the system's if unset."
+                ),
+                "docs": {
+                    "type": "boolean",
+                    "default": true,
+                    "description": "Whether generated C and C++ have Doxygen comments."
+                },
+                "usage_exit_code": exit_code(
+                    "Exit status when the command line is wrong. C and C++ only."
+                ),
+                "error_exit_code": exit_code(
+                    "Exit status for other errors, such as an unreadable response file. C only."
+                ),
+                "usage_to_stderr": {
+                    "type": "boolean",
+                    "default": true,
+                    "description": "Whether the usage shown after a command-line error goes to \
+                                    stderr rather than stdout. C and C++ only."
+                },
+                "copy_strings": flag(
+                    "Whether string values are copied out of argv, with free_args to free them. \
+                     C only."
+                ),
+                "selftest": flag(
+                    "Whether --argen-selftest, given alone, runs checks of the parser and exits 0 \
+                     if they pass. C only, for the posix target."
+                ),
+                "config_file": config_file(),
+                "positional": {"type": "array", "items": positional()},
+                "non_positional": {"type": "array", "items": non_positional()},
+                "groups": {"type": "array", "items": group()},
+                "style": style(),
+                "includes": strings("Extra headers for generated C.")
+            },
+            "additionalProperties": false
+        });
+        serde_json::to_string_pretty(&schema).unwrap() + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Spec;
+    use serde_json::Value;
+    use std::fs;
+    use std::path::Path;
+
+    /// Fields of value that schema has no property for, by path.
+    fn unknown_fields(value: &Value, schema: &Value, path: &str, unknown: &mut Vec<String>) {
+        match value {
+            // of alternatives, tables can only be the one with properties
+            Value::Object(_) if schema.get("properties").is_none() => {
+                let tables = schema["anyOf"].as_array().into_iter().flatten();
+                match tables.into_iter().find(|s| s.get("properties").is_some()) {
+                    Some(table) => unknown_fields(value, table, path, unknown),
+                    None => unknown.push(path.to_owned()),
+                }
+            }
+            Value::Object(fields) => {
+                for (name, field) in fields {
+                    let path = format!("{}/{}", path, name);
+                    match schema["properties"].get(name) {
+                        Some(property) => unknown_fields(field, property, &path, unknown),
+                        None => unknown.push(path),
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    unknown_fields(item, &schema["items"], &format!("{}/{}", path, i), unknown);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn describes_every_field() {
+        let schema: Value = serde_json::from_str(&Spec::json_schema()).unwrap();
+        let mut unknown = Vec::new();
+        for entry in fs::read_dir("tests/specs").unwrap() {
+            let path = entry.unwrap().path();
+            let spec = serde_json::to_value(Spec::from_path(&path).unwrap()).unwrap();
+            unknown_fields(&spec, &schema, &path.display().to_string(), &mut unknown);
+        }
+        let example = Spec::from_path(Path::new("examples/example_spec.toml")).unwrap();
+        let example = serde_json::to_value(example).unwrap();
+        unknown_fields(&example, &schema, "example", &mut unknown);
+        assert!(unknown.is_empty(), "not in the schema: {:?}", unknown);
+    }
+}
@@ -0,0 +1,401 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{
+    c_quote, CTarget, CType, NonPositionalItem, PositionalItem, Problems, Spec, ValidationError,
+};
+use std::ptr;
+
+/// A command line the self-test parses, and what must come of it: parse_args exiting with
+/// status, or if status is 0, returning with none of wrong true.
+struct Case {
+    what: String,
+    args: Vec<String>,
+    status: i32,
+    /// C expressions, each true if a variable came out other than it should have.
+    wrong: Vec<String>,
+}
+
+/// The value the self-test gives arguments of c_type.
+fn sample(c_type: CType) -> &'static str {
+    match c_type {
+        CType::Int => "42",
+        CType::Chars | CType::Custom => "value",
+    }
+}
+
+/// C true if c_var does not hold value, written as a spec's defaults are, or is not zeroed if
+/// there is none. None for custom types, which only their converter knows how to compare.
+fn c_differs(c_type: CType, c_var: &str, value: Option<&str>) -> Option<String> {
+    match (c_type, value) {
+        (CType::Custom, _) => None,
+        (CType::Int, Some(value)) => Some(format!("{} != (int){}", c_var, value)),
+        (CType::Int, None) => Some(format!("{} != 0", c_var)),
+        (CType::Chars, Some(value)) => Some(format!(
+            "{0} == NULL || strcmp({0}, \"{1}\") != 0",
+            c_var,
+            c_quote(value)
+        )),
+        (CType::Chars, None) => Some(format!("{} != NULL", c_var)),
+    }
+}
+
+impl NonPositionalItem {
+    /// Words giving the option on a self-test command line, with the sample value if it takes
+    /// one.
+    fn selftest_words(&self) -> Vec<String> {
+        let mut words = vec![format!("--{}", self.long)];
+        if self.is_map() {
+            words.push(String::from("name=value"));
+        } else if !self.is_flag() {
+            words.push(sample(self.c_type).to_owned());
+        }
+        words
+    }
+    /// What is true if the option came out other than selftest_words give it, or if not given,
+    /// other than its default.
+    fn selftest_wrong(&self, given: bool) -> Vec<String> {
+        let c_var = &self.c_var;
+        let wrong = if self.is_map() && given {
+            Some(format!(
+                "{0}__size != 1 || strcmp({0}__keys[0], \"name\") != 0 || strcmp({0}__values[0], \"value\") != 0",
+                c_var
+            ))
+        } else if self.is_map() {
+            Some(format!("{}__size != 0", c_var))
+        } else if self.is_flag() {
+            Some(format!("{} != {}", c_var, usize::from(given)))
+        } else if given {
+            c_differs(self.c_type, c_var, Some(sample(self.c_type)))
+        } else {
+            c_differs(self.c_type, c_var, self.default.as_deref())
+        };
+        wrong.into_iter().collect()
+    }
+    /// Whether the self-test can give the option a value it knows will be taken as it is.
+    fn selftest_can_give(&self) -> bool {
+        self.validator.is_none() && !matches!(self.c_type, CType::Custom)
+    }
+}
+
+impl PositionalItem {
+    /// What is true if the argument came out other than given sample values make it, or if
+    /// given none, other than its default.
+    fn selftest_wrong(&self, given: usize) -> Vec<String> {
+        let c_var = &self.c_var;
+        let wrong = if self.is_multi() && given > 0 {
+            Some(format!(
+                "{0}__size != {1} || strcmp({0}[0], \"value\") != 0",
+                c_var, given
+            ))
+        } else if self.is_multi() && self.has_default() {
+            None
+        } else if self.is_multi() {
+            Some(format!("{}__size != 0", c_var))
+        } else if given > 0 {
+            c_differs(self.c_type, c_var, Some(sample(self.c_type)))
+        } else {
+            c_differs(self.c_type, c_var, self.default.as_deref())
+        };
+        wrong.into_iter().collect()
+    }
+    fn selftest_can_give(&self) -> bool {
+        self.validator.is_none() && !matches!(self.c_type, CType::Custom)
+    }
+}
+
+impl Spec {
+    /// Options every self-test command line gives: the required ones, and the first of each
+    /// required group that has none of them.
+    fn selftest_given(&self) -> Vec<&NonPositionalItem> {
+        let mut given: Vec<&NonPositionalItem> = self
+            .non_positional
+            .iter()
+            .filter(|npi| npi.is_required())
+            .collect();
+        for group in self.groups.iter().flatten().filter(|g| g.is_required()) {
+            if !given.iter().any(|npi| group.options.contains(&npi.long)) {
+                given.extend(self.find_non_positional(&group.options[0]));
+            }
+        }
+        given
+    }
+    /// How many values every self-test command line gives each positional: as few as it
+    /// takes.
+    fn selftest_counts(&self) -> Vec<usize> {
+        self.positional
+            .iter()
+            .map(PositionalItem::min_values)
+            .collect()
+    }
+    /// A self-test command line giving options, then counts[i] sample values for positional i.
+    fn selftest_args(&self, options: &[&NonPositionalItem], counts: &[usize]) -> Vec<String> {
+        let mut args = vec![self
+            .program_name
+            .clone()
+            .unwrap_or_else(|| String::from("selftest"))];
+        for npi in options {
+            args.extend(npi.selftest_words());
+        }
+        for (pi, &count) in self.positional.iter().zip(counts) {
+            args.extend(std::iter::repeat_n(sample(pi.c_type).to_owned(), count));
+        }
+        args
+    }
+    /// Arguments the self-test would have to make up a value for that it cannot know will be
+    /// taken, because a validator or converter decides.
+    pub(super) fn selftest_problems(&self) -> Problems {
+        let mut problems = Vec::new();
+        if self.c_target() == CTarget::Windows {
+            problems.push((
+                String::from("/selftest"),
+                ValidationError::SelftestNeedsPosix,
+            ));
+        }
+        let given = self.selftest_given();
+        for (i, npi) in self.non_positional.iter().enumerate() {
+            if given.iter().any(|g| ptr::eq(*g, npi)) && !npi.selftest_can_give() {
+                problems.push((
+                    format!("/non_positional/{}", i),
+                    ValidationError::SelftestNeedsValue(npi.long.to_owned()),
+                ));
+            }
+        }
+        for (i, pi) in self.positional.iter().enumerate() {
+            if pi.is_required() && !pi.selftest_can_give() {
+                problems.push((
+                    format!("/positional/{}", i),
+                    ValidationError::SelftestNeedsValue(pi.help_name.to_owned()),
+                ));
+            }
+        }
+        problems
+    }
+    /// The self-test's table: defaults are applied, what is required is, and options not tied
+    /// up in constraints read their values.
+    fn selftest_cases(&self) -> Vec<Case> {
+        let given = self.selftest_given();
+        let counts = self.selftest_counts();
+        let is_given = |npi: &NonPositionalItem| given.iter().any(|g| ptr::eq(*g, npi));
+        let usage = self.usage_exit_code();
+        let mut wrong = Vec::new();
+        for npi in &self.non_positional {
+            // a config file may give what the command line does not
+            if is_given(npi) || self.config_file.is_none() || !npi.is_configurable() {
+                wrong.extend(npi.selftest_wrong(is_given(npi)));
+            }
+        }
+        for (pi, &count) in self.positional.iter().zip(&counts) {
+            wrong.extend(pi.selftest_wrong(count));
+        }
+        let mut cases = vec![Case {
+            what: String::from("defaults are applied"),
+            args: self.selftest_args(&given, &counts),
+            status: 0,
+            wrong,
+        }];
+        let leaving_out = |left_out: &dyn Fn(&NonPositionalItem) -> bool| {
+            let options: Vec<&NonPositionalItem> =
+                given.iter().copied().filter(|npi| !left_out(npi)).collect();
+            self.selftest_args(&options, &counts)
+        };
+        if self.config_file.is_none() {
+            for npi in given.iter().filter(|npi| npi.is_required()) {
+                cases.push(Case {
+                    what: format!("--{} is required", npi.long),
+                    args: leaving_out(&|other| ptr::eq(other, *npi)),
+                    status: usage,
+                    wrong: Vec::new(),
+                });
+            }
+        }
+        for group in self.groups.iter().flatten().filter(|g| g.is_required()) {
+            let longs: Vec<String> = group.options.iter().map(|l| format!("--{}", l)).collect();
+            cases.push(Case {
+                what: format!("one of {} is required", longs.join(", ")),
+                args: leaving_out(&|npi| group.options.contains(&npi.long)),
+                status: usage,
+                wrong: Vec::new(),
+            });
+        }
+        for (i, pi) in self.positional.iter().enumerate() {
+            if !pi.is_required() {
+                continue;
+            }
+            let mut fewer = counts.clone();
+            fewer[i] -= 1;
+            cases.push(Case {
+                what: match counts[i] {
+                    1 => format!("{} is required", pi.help_name),
+                    n => format!("{} takes at least {} values", pi.help_name, n),
+                },
+                args: self.selftest_args(&given, &fewer),
+                status: usage,
+                wrong: Vec::new(),
+            });
+        }
+        let config_option = self.config_file.as_ref().and_then(|c| c.option.as_deref());
+        let constrained = self.constrained_options();
+        for npi in &self.non_positional {
+            // options naming a config file would have it read
+            if is_given(npi)
+                || !npi.selftest_can_give()
+                || constrained.contains(npi.long.as_str())
+                || config_option == Some(&npi.long)
+            {
+                continue;
+            }
+            let mut options = given.clone();
+            options.push(npi);
+            cases.push(Case {
+                what: format!("--{} is read", npi.long),
+                args: self.selftest_args(&options, &counts),
+                status: 0,
+                wrong: npi.selftest_wrong(true),
+            });
+            if matches!(npi.c_type, CType::Int) && !npi.is_flag() {
+                let mut args = self.selftest_args(&options, &counts);
+                let value = 1 + args
+                    .iter()
+                    .position(|a| *a == format!("--{}", npi.long))
+                    .unwrap();
+                args[value] = String::from("x");
+                cases.push(Case {
+                    what: format!("--{} rejects a value that is not a number", npi.long),
+                    args,
+                    status: usage,
+                    wrong: Vec::new(),
+                });
+            }
+        }
+        cases
+    }
+    /// Whether parse_args takes --argen-selftest. It runs each case in a child process, so
+    /// only the posix target can.
+    pub(crate) fn has_selftest(&self) -> bool {
+        self.selftest.unwrap_or(false) && self.c_target() == CTarget::Posix
+    }
+    /// Creates the self-test --argen-selftest runs: a function for each case that parses its
+    /// command line and returns whether anything came out wrong, and argen_selftest, which runs
+    /// them in child processes so that the ones parse_args must reject can exit, printing
+    /// whether each passed in TAP and returning 0 only if all did.
+    pub(crate) fn cgen_selftest(&self) -> String {
+        if !self.has_selftest() {
+            return String::new();
+        }
+        let std = self.c_std();
+        let mut body = format!(
+            "static char **selftest_argv(const char *const *args, int argc) {{\n\
+             \tchar **argv = malloc((argc + 1) * sizeof(char *));\n\
+             {}\
+             \tif (!argv) {{\n\
+             \t\texit(1);\n\
+             \t}}\n\
+             \tfor ({}; i < argc; i++) {{\n\
+             \t\targv[i] = malloc(strlen(args[i]) + 1);\n\
+             \t\tif (!argv[i]) {{\n\
+             \t\t\texit(1);\n\
+             \t\t}}\n\
+             \t\tstrcpy(argv[i], args[i]);\n\
+             \t}}\n\
+             \targv[argc] = NULL;\n\
+             \treturn argv;\n\
+             }}\n\n",
+            std.counter_decl("int", "i"),
+            std.for_init("int", "i", "0")
+        );
+        let cases = self.selftest_cases();
+        let mut table = String::new();
+        for (i, case) in cases.iter().enumerate() {
+            let args: Vec<String> = case
+                .args
+                .iter()
+                .map(|arg| format!("\"{}\"", c_quote(arg)))
+                .collect();
+            body.push_str(&format!(
+                "static const char *const argen_selftest_{}_args[] = {{{}}};\n\n\
+                 static int argen_selftest_{0}(void) {{\n",
+                i,
+                args.join(", ")
+            ));
+            for npi in &self.non_positional {
+                body.push_str(&npi.cgen_main_decl());
+            }
+            for pi in &self.positional {
+                body.push_str(&pi.cgen_main_decls());
+            }
+            body.push_str(&format!(
+                "\n\tparse_args({0}, selftest_argv(argen_selftest_{1}_args, {0})",
+                args.len(),
+                i
+            ));
+            for npi in &self.non_positional {
+                body.push_str(&npi.cgen_call_arg());
+            }
+            for pi in &self.positional {
+                body.push_str(&pi.cgen_call_arg());
+            }
+            body.push_str(");\n");
+            match case.wrong.as_slice() {
+                [] => body.push_str("\treturn 0;\n}\n\n"),
+                wrong => body.push_str(&format!("\treturn {};\n}}\n\n", wrong.join("\n\t\t|| "))),
+            }
+            table.push_str(&format!(
+                "\t\t{{\"{}\", argen_selftest_{}, {}}},\n",
+                c_quote(&case.what),
+                i,
+                case.status
+            ));
+        }
+        body.push_str(&format!(
+            "static int argen_selftest(void) {{\n\
+             \tstatic const struct {{\n\
+             \t\tconst char *what;\n\
+             \t\tint (*run)(void);\n\
+             \t\tint status;\n\
+             \t}} cases[] = {{\n\
+             {}\
+             \t}};\n\
+             \tint failed = 0;\n\
+             {}\
+             \tprintf(\"1..%d\\n\", (int)(sizeof(cases) / sizeof(cases[0])));\n\
+             \tfor ({}; i < sizeof(cases) / sizeof(cases[0]); i++) {{\n\
+             \t\tint status;\n\
+             \t\tpid_t pid;\n\
+             \t\tfflush(stdout);\n\
+             \t\tpid = fork();\n\
+             \t\tif (pid == 0) {{\n\
+             \t\t\tif (!freopen(\"/dev/null\", \"w\", stdout) || !freopen(\"/dev/null\", \"w\", stderr)) {{\n\
+             \t\t\t\texit(1);\n\
+             \t\t\t}}\n\
+             \t\t\texit(cases[i].run() ? 1 : 0);\n\
+             \t\t}}\n\
+             \t\tif (pid < 0 || waitpid(pid, &status, 0) < 0 || !WIFEXITED(status) || WEXITSTATUS(status) != cases[i].status) {{\n\
+             \t\t\tprintf(\"not ok %d - %s\\n\", (int)i + 1, cases[i].what);\n\
+             \t\t\tfailed++;\n\
+             \t\t}} else {{\n\
+             \t\t\tprintf(\"ok %d - %s\\n\", (int)i + 1, cases[i].what);\n\
+             \t\t}}\n\
+             \t}}\n\
+             \treturn failed > 0;\n\
+             }}\n\n",
+            table,
+            std.counter_decl("size_t", "i"),
+            std.for_init("size_t", "i", "0")
+        ));
+        body
+    }
+}
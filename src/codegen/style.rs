@@ -0,0 +1,330 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// C standards the C target can be written for.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CStd {
+    C89,
+    C99,
+    C11,
+}
+impl FromStr for CStd {
+    type Err = String;
+    fn from_str(s: &str) -> Result<CStd, String> {
+        match s {
+            "c89" | "c90" => Ok(CStd::C89),
+            "c99" => Ok(CStd::C99),
+            "c11" => Ok(CStd::C11),
+            _ => Err(format!(
+                "unsupported C standard \"{}\" (try c89, c99, or c11)",
+                s
+            )),
+        }
+    }
+}
+impl CStd {
+    /// Type of the __isset variables, bool where the standard has it.
+    pub(crate) fn bool_type(self) -> &'static str {
+        match self {
+            CStd::C89 => "int",
+            _ => "bool",
+        }
+    }
+    pub(crate) fn true_lit(self) -> &'static str {
+        match self {
+            CStd::C89 => "1",
+            _ => "true",
+        }
+    }
+    pub(crate) fn false_lit(self) -> &'static str {
+        match self {
+            CStd::C89 => "0",
+            _ => "false",
+        }
+    }
+    /// Declares the loop counters vars ahead of the loops that use them, which C89 needs.
+    pub(crate) fn counter_decl(self, c_type: &str, vars: &str) -> String {
+        match self {
+            CStd::C89 => format!("\t{} {};\n", c_type, vars),
+            _ => String::new(),
+        }
+    }
+    /// The init clause of a for loop over counter var, declaring it there after C89.
+    pub(crate) fn for_init(self, c_type: &str, var: &str, start: &str) -> String {
+        match self {
+            CStd::C89 => format!("{} = {}", var, start),
+            _ => format!("{} {} = {}", c_type, var, start),
+        }
+    }
+}
+
+/// Platforms the C target can be written for.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CTarget {
+    /// Systems with getopt_long(3) in getopt.h, such as glibc, musl and the BSDs.
+    Posix,
+    /// Windows, where MSVC has no getopt.h, so the parser brings its own getopt_long.
+    Windows,
+}
+impl FromStr for CTarget {
+    type Err = String;
+    fn from_str(s: &str) -> Result<CTarget, String> {
+        match s {
+            "posix" => Ok(CTarget::Posix),
+            "windows" => Ok(CTarget::Windows),
+            _ => Err(format!(
+                "unsupported target \"{}\" (try posix or windows)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentKind {
+    Tabs,
+    Spaces,
+}
+
+/// Where opening braces go.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Braces {
+    /// At the end of the line that opens the block.
+    Attach,
+    /// On their own line for function bodies, attached otherwise.
+    Linux,
+    /// On their own line everywhere.
+    Allman,
+}
+
+/// How generated C is laid out, and which standard and platform it is written for.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Style {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) indent: Option<IndentKind>,
+    /// Spaces per level when indenting with spaces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) indent_width: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) braces: Option<Braces>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) std: Option<CStd>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) target: Option<CTarget>,
+    /// With the windows target, take arguments in wmain as UTF-16 and pass them on as UTF-8.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) wmain: Option<bool>,
+}
+
+/// Whether a line of code, literals stripped, opens a block with a brace at its end. Braces of
+/// initializers do not count.
+fn opens_block(code: &str) -> bool {
+    let code = code.trim_end();
+    code.ends_with('{') && !code.ends_with("= {") && code.trim() != "{"
+}
+
+/// The line with the contents of string and character literals removed, and comments too.
+/// in_comment says whether the line starts inside a block comment, and is left saying whether
+/// it ends inside one.
+fn strip_literals(line: &str, in_comment: &mut bool) -> String {
+    let mut code = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if *in_comment {
+            if ch == '*' && chars.peek() == Some(&'/') {
+                chars.next();
+                *in_comment = false;
+            }
+            continue;
+        }
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if ch == '\\' {
+                    escaped = true;
+                } else if ch == q {
+                    quote = None;
+                    code.push(ch);
+                }
+            }
+            None if ch == '/' && chars.peek() == Some(&'*') => {
+                chars.next();
+                *in_comment = true;
+            }
+            None if ch == '/' && chars.peek() == Some(&'/') => break,
+            None => {
+                if ch == '"' || ch == '\'' {
+                    quote = Some(ch);
+                }
+                code.push(ch);
+            }
+        }
+    }
+    code
+}
+
+impl Style {
+    pub(crate) fn std(&self) -> CStd {
+        self.std.unwrap_or(CStd::C99)
+    }
+    pub(crate) fn target(&self) -> CTarget {
+        self.target.unwrap_or(CTarget::Posix)
+    }
+    /// Whether main is reached through a wmain converting the arguments to UTF-8.
+    pub(crate) fn has_wmain(&self) -> bool {
+        self.target() == CTarget::Windows && self.wmain.unwrap_or(false)
+    }
+    fn indent_width(&self) -> usize {
+        self.indent_width.unwrap_or(4)
+    }
+    /// A Styler laying out C generated with tabs and attached braces the way this style asks
+    /// for.
+    pub(crate) fn styler(&self) -> Styler {
+        Styler {
+            braces: self.braces.unwrap_or(Braces::Attach),
+            unit: match self.indent.unwrap_or(IndentKind::Tabs) {
+                IndentKind::Tabs => String::from("\t"),
+                IndentKind::Spaces => " ".repeat(self.indent_width()),
+            },
+            depth: 0,
+            in_comment: false,
+        }
+    }
+}
+
+/// Lays out C the way a Style asks for, given its lines in order in as many pieces as suit.
+pub(crate) struct Styler {
+    braces: Braces,
+    unit: String,
+    /// Braces open so far, so function bodies are found even after multi-line signatures.
+    depth: usize,
+    /// Whether the lines so far end inside a block comment, whose braces do not count.
+    in_comment: bool,
+}
+
+impl Styler {
+    /// The whole lines in code, styled.
+    pub(crate) fn lines(&mut self, code: &str) -> String {
+        let (braces, unit) = (self.braces, &self.unit);
+        let depth = &mut self.depth;
+        let in_comment = &mut self.in_comment;
+        let mut styled = String::new();
+        for line in code.split_inclusive('\n') {
+            let body = line.trim_start_matches('\t');
+            let indent = unit.repeat(line.len() - body.len());
+            let code = strip_literals(body, in_comment);
+            let own_line = match braces {
+                Braces::Attach => false,
+                Braces::Linux => *depth == 0 && opens_block(&code),
+                Braces::Allman => opens_block(&code),
+            };
+            *depth += code.matches('{').count();
+            // a stray brace in code argen did not write must not wrap the count
+            *depth = depth.saturating_sub(code.matches('}').count());
+            if !own_line {
+                styled.push_str(&indent);
+                styled.push_str(body);
+                continue;
+            }
+            let head = body.trim_end().trim_end_matches('{').trim_end();
+            // "} else {" closes one block before opening the next
+            if let Some(rest) = head.strip_prefix("} ") {
+                styled.push_str(&format!("{}}}\n", indent));
+                styled.push_str(&format!("{}{}\n", indent, rest));
+            } else {
+                styled.push_str(&format!("{}{}\n", indent, head));
+            }
+            // the brace lines up with the block it opens, not with a continued signature
+            styled.push_str(&format!("{}{{\n", unit.repeat(*depth - 1)));
+        }
+        styled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Braces, CStd, IndentKind, Style};
+
+    const CODE: &str = "int main(int argc,\n\
+                        \t     char **argv) {\n\
+                        \tif (x) {\n\
+                        \t\tputs(\"{\");\n\
+                        \t} else {\n\
+                        \t\tstatic int a[] = {\n\
+                        \t\t\t1,\n\
+                        \t\t};\n\
+                        \t}\n\
+                        }\n";
+
+    #[test]
+    fn restyles() {
+        let style = Style {
+            indent: Some(IndentKind::Spaces),
+            indent_width: Some(2),
+            braces: Some(Braces::Linux),
+            ..Style::default()
+        };
+        assert_eq!(
+            style.styler().lines(CODE),
+            "int main(int argc,\n       char **argv)\n{\n  if (x) {\n    puts(\"{\");\n  } else {\n    static int a[] = {\n      1,\n    };\n  }\n}\n"
+        );
+        let style = Style {
+            braces: Some(Braces::Allman),
+            ..Style::default()
+        };
+        assert_eq!(
+            style.styler().lines(CODE),
+            "int main(int argc,\n\t     char **argv)\n{\n\tif (x)\n\t{\n\t\tputs(\"{\");\n\t}\n\telse\n\t{\n\t\tstatic int a[] = {\n\t\t\t1,\n\t\t};\n\t}\n}\n"
+        );
+        let mut styler = style.styler();
+        let by_line: String = CODE
+            .split_inclusive('\n')
+            .map(|l| styler.lines(l))
+            .collect();
+        assert_eq!(by_line, style.styler().lines(CODE));
+        assert_eq!(Style::default().styler().lines(CODE), CODE);
+    }
+
+    #[test]
+    fn skips_comments() {
+        let style = Style {
+            braces: Some(Braces::Linux),
+            ..Style::default()
+        };
+        assert_eq!(
+            style
+                .styler()
+                .lines("/* } */\n/*\n * {\n */\nint f(void) {\n}\n"),
+            "/* } */\n/*\n * {\n */\nint f(void)\n{\n}\n"
+        );
+    }
+
+    #[test]
+    fn parses_std() {
+        assert_eq!("c90".parse::<CStd>(), Ok(CStd::C89));
+        assert!("gnu99".parse::<CStd>().is_err());
+    }
+}
@@ -0,0 +1,157 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+use regex::Regex;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// What a template can place: the pieces of a CodeGenerator, then fields of the spec, which
+/// are empty when unset.
+const FIELDS: [&str; 10] = [
+    "prelude",
+    "usage",
+    "helpers",
+    "decls",
+    "parse_loop",
+    "post_loop",
+    "main",
+    "program_name",
+    "version",
+    "about",
+];
+
+/// The layout every target is generated in unless given another.
+const DEFAULT_TEMPLATE: &str = include_str!("../../templates/parser.tmpl");
+
+/// Error type for templates naming fields there are none of
+#[derive(Debug, PartialEq)]
+pub enum TemplateError {
+    UnknownField(usize, String),
+}
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::UnknownField(line, name) => write!(
+                f,
+                "line {}: unknown field {{{{{}}}}} (try {})",
+                line,
+                name,
+                FIELDS.join(", ")
+            ),
+        }
+    }
+}
+impl Error for TemplateError {}
+
+enum Piece {
+    Text(String),
+    Field(&'static str),
+}
+
+/// How generated code is laid out: text with {{field}} placeholders for the generated pieces
+/// and spec fields. A placeholder alone on its line stands for the whole line, since the
+/// pieces end their own lines. Braces around anything but a lower-case name are left as they
+/// are, so C initializers such as {{0}} need no escaping.
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, TemplateError> {
+        let field_re = Regex::new(r"\{\{\s*([a-z_]+)\s*\}\}").unwrap();
+        let known = |line: usize, name: &str| {
+            FIELDS
+                .iter()
+                .find(|&&field| field == name)
+                .copied()
+                .ok_or_else(|| TemplateError::UnknownField(line, name.to_owned()))
+        };
+        let mut pieces = Vec::new();
+        for (n, line) in text.split_inclusive('\n').enumerate() {
+            if let Some(m) = field_re.captures(line.trim()) {
+                if m[0].len() == line.trim().len() {
+                    pieces.push(Piece::Field(known(n + 1, &m[1])?));
+                    continue;
+                }
+            }
+            let mut at = 0;
+            for m in field_re.captures_iter(line) {
+                let whole = m.get(0).unwrap();
+                pieces.push(Piece::Text(line[at..whole.start()].to_owned()));
+                pieces.push(Piece::Field(known(n + 1, &m[1])?));
+                at = whole.end();
+            }
+            pieces.push(Piece::Text(line[at..].to_owned()));
+        }
+        Ok(Template { pieces })
+    }
+    /// Hands the template to write in order, with each field replaced by what value gives for
+    /// it, asked for only once the template reaches it. Values are not themselves expanded, so
+    /// generated code containing braces comes out as it went in.
+    pub(crate) fn render<V, W>(&self, mut value: V, mut write: W) -> io::Result<()>
+    where
+        V: FnMut(&str) -> String,
+        W: FnMut(&str) -> io::Result<()>,
+    {
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => write(text)?,
+                Piece::Field(name) => write(&value(name))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Template {
+    fn default() -> Template {
+        Template::parse(DEFAULT_TEMPLATE).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Template, TemplateError};
+
+    #[test]
+    fn renders_fields() {
+        let template =
+            Template::parse("/* {{ program_name }} */\n  {{prelude}}\nint x[] = {{0}};\n").unwrap();
+        let mut out = String::new();
+        let value = |field: &str| match field {
+            "program_name" => String::from("demo"),
+            _ => String::from("#include <stdio.h>\n{{main}}\n"),
+        };
+        let write = |text: &str| {
+            out.push_str(text);
+            Ok(())
+        };
+        template.render(value, write).unwrap();
+        assert_eq!(
+            out,
+            "/* demo */\n#include <stdio.h>\n{{main}}\nint x[] = {{0}};\n"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let err = Template::parse("{{prelude}}\n// {{licence}}\n")
+            .err()
+            .unwrap();
+        assert_eq!(err, TemplateError::UnknownField(2, String::from("licence")));
+    }
+}
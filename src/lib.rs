@@ -0,0 +1,26 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates argument parsers from a spec of positional arguments and options. Specs are usually
+//! read from TOML, JSON or YAML files with `Spec::from_path`, or built in code with `SpecBuilder`.
+
+pub mod codegen;
+
+pub use codegen::{
+    preserve_regions, Braces, CStd, CTarget, CType, CodeGenerator, Group, IndentKind, Language,
+    NonPositionalItem, Opt, Positional, PositionalItem, RegionError, Shell, Spec, SpecBuilder,
+    SpecFormat, Style, Template, TemplateError, ValidationError,
+};
@@ -14,56 +14,232 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
-mod codegen;
-
-use codegen::Spec;
+use argen::{preserve_regions, CStd, CTarget, Language, Shell, Spec, Template};
 use getopts::Options;
 use std::env;
+use std::fs;
 use std::fs::File;
 use std::io;
-use std::io::{Read, Write};
+use std::io::Write;
 use std::path::Path;
 use std::process;
 
 const VERSION: &str = "1.0.0";
 
 fn print_usage(program: &str, opts: Options) {
-    let brief = format!("Usage: {} [options] SPEC.toml", program);
+    let brief = format!(
+        "Usage: {0} [options] SPEC\n       {0} import [-o SPEC] FILE.c\n       {0} init [-o SPEC]",
+        program
+    );
     print!("{}", opts.usage(&brief));
 }
 
-fn codegen(filename: String, output: Option<String>) {
+/// What to generate from the spec.
+enum Target {
+    Code(Language),
+    Completion(Shell),
+    Manpage,
+}
+
+/// The template in template_dir for lang, or the default if there is none there.
+fn load_template(template_dir: Option<&str>, lang: Language) -> Template {
+    let path = match template_dir {
+        Some(dir) => Path::new(dir).join(lang.template_file()),
+        None => return Template::default(),
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Template::default(),
+        Err(e) => {
+            writeln!(
+                &mut io::stderr(),
+                "Template Error: {}: {}",
+                path.display(),
+                e
+            )
+            .unwrap();
+            process::exit(1);
+        }
+    };
+    match Template::parse(&text) {
+        Ok(template) => template,
+        Err(e) => {
+            writeln!(
+                &mut io::stderr(),
+                "Template Error: {}: {}",
+                path.display(),
+                e
+            )
+            .unwrap();
+            process::exit(1);
+        }
+    }
+}
+
+fn codegen(
+    filename: String,
+    output: Option<String>,
+    target: Target,
+    std: Option<CStd>,
+    c_target: Option<CTarget>,
+    template_dir: Option<String>,
+) {
     let path = Path::new(&filename);
-    let mut f = File::open(&path).expect("open input toml");
-    let mut contents = String::new();
-    f.read_to_string(&mut contents).expect("read input toml");
-    let s = Spec::from_str(&contents);
+    let s = Spec::from_path(path);
     if let Err(e) = s {
         writeln!(&mut io::stderr(), "Spec Parse Error: {}", e).unwrap();
         process::exit(1);
     }
-    let s = s.unwrap();
+    let mut s = s.unwrap();
+    if let Some(std) = std {
+        s.set_c_std(std);
+    }
+    if let Some(c_target) = c_target {
+        s.set_c_target(c_target);
+    }
+    let default_name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("a.out");
+    let doc = match target {
+        Target::Code(_) => None,
+        Target::Completion(shell) => Some(s.gen_completion(shell, default_name)),
+        Target::Manpage => Some(s.gen_manpage(default_name)),
+    };
+    if let Some(doc) = doc {
+        match output {
+            Some(f) => File::create(Path::new(&f))
+                .and_then(|mut f| f.write_all(doc.as_bytes()))
+                .expect("write output file"),
+            None => print!("{}", doc),
+        }
+        return;
+    }
+    let lang = match target {
+        Target::Code(lang) => lang,
+        _ => unreachable!(),
+    };
+    let template = load_template(template_dir.as_deref(), lang);
     match output {
         Some(f) => {
             let p = Path::new(&f);
-            let mut f = File::create(&p).expect("open output file");
-            s.writeout(&mut f)
+            let mut code = s.gen_with(lang, &template);
+            // regenerating over earlier output keeps the code written into its user regions
+            if let Ok(existing) = fs::read_to_string(p) {
+                code = match preserve_regions(&code, &existing) {
+                    Ok(code) => code,
+                    Err(e) => {
+                        writeln!(&mut io::stderr(), "Region Error: {}: {}", f, e).unwrap();
+                        process::exit(1);
+                    }
+                };
+            }
+            File::create(p)
+                .and_then(|mut f| f.write_all(code.as_bytes()))
+                .expect("write output file")
         }
-        None => s.writeout(&mut io::stdout()),
+        None => s
+            .writeout_with(&mut io::stdout().lock(), lang, &template)
+            .expect("write generated code"),
     };
 }
 
+/// Writes a JSON spec for the getopt_long parser in filename, noting what did not carry over.
+fn import(filename: &str, output: Option<String>) {
+    let source = fs::read_to_string(filename).expect("open input C file");
+    let (spec, notes) = match Spec::import_getopt(&source) {
+        Ok(imported) => imported,
+        Err(e) => {
+            writeln!(&mut io::stderr(), "{}: {}", filename, e).unwrap();
+            process::exit(1);
+        }
+    };
+    for note in notes {
+        writeln!(&mut io::stderr(), "{}: note: {}", filename, note).unwrap();
+    }
+    let json = serde_json::to_string_pretty(&spec).unwrap() + "\n";
+    match output {
+        Some(f) => fs::write(&f, json).expect("write output file"),
+        None => print!("{}", json),
+    }
+}
+
+/// Writes a JSON spec built from answers to questions asked on stderr.
+fn init(output: Option<String>) {
+    let stdin = io::stdin();
+    let spec = Spec::init_wizard(&mut stdin.lock(), &mut io::stderr()).expect("ask for spec");
+    let json = serde_json::to_string_pretty(&spec).unwrap() + "\n";
+    match output {
+        Some(f) => fs::write(&f, json).expect("write output file"),
+        None => print!("{}", json),
+    }
+}
+
+/// Prints everything wrong with the spec at filename, exiting with 1 if there is anything.
+fn check(filename: &str) {
+    let problems = Spec::check_path(Path::new(filename));
+    for (pointer, e) in &problems {
+        if pointer.is_empty() {
+            writeln!(&mut io::stderr(), "{}: {}", filename, e).unwrap();
+        } else {
+            writeln!(&mut io::stderr(), "{}: {}: {}", filename, pointer, e).unwrap();
+        }
+    }
+    if !problems.is_empty() {
+        process::exit(1);
+    }
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     let program = args[0].clone();
 
     let mut opts = Options::new();
     opts.optopt("o", "", "set output file name", "NAME");
+    opts.optopt(
+        "",
+        "completion",
+        "generate a completion script for SHELL (bash, zsh, fish) instead of C",
+        "SHELL",
+    );
+    opts.optflag("", "manpage", "generate a man page instead of C");
+    opts.optopt(
+        "",
+        "lang",
+        "generate the parser in LANG (c, c++, python, rust), c by default",
+        "LANG",
+    );
+    opts.optopt(
+        "",
+        "std",
+        "write C for STD (c89, c99, c11), overriding the spec's style",
+        "STD",
+    );
+    opts.optopt(
+        "",
+        "target",
+        "write C for TARGET (posix, windows), overriding the spec's style",
+        "TARGET",
+    );
+    opts.optopt(
+        "",
+        "template-dir",
+        "lay out generated code with the templates in DIR, named c.tmpl, cpp.tmpl, python.tmpl \
+         and rust.tmpl, where there is one for the language",
+        "DIR",
+    );
+    opts.optflag(
+        "",
+        "check",
+        "only check SPEC, printing every problem with it, instead of generating code",
+    );
+    opts.optflag(
+        "",
+        "schema",
+        "print a JSON Schema for spec files, for editors to check them with",
+    );
     opts.optflag("h", "help", "print this help menu");
     opts.optflag("v", "version", "show version");
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => m,
-        Err(f) => panic!(f.to_string()),
+        Err(f) => panic!("{}", f),
     };
     if matches.opt_present("h") {
         print_usage(&program, opts);
@@ -73,23 +249,106 @@ fn main() {
         println!("argen {}", VERSION);
         return;
     }
+    if matches.opt_present("schema") {
+        print!("{}", Spec::json_schema());
+        return;
+    }
     let output = matches.opt_str("o");
+    let target = match matches.opt_str("completion").map(|s| s.parse::<Shell>()) {
+        Some(Ok(shell)) => Target::Completion(shell),
+        Some(Err(e)) => {
+            writeln!(&mut io::stderr(), "{}", e).unwrap();
+            process::exit(1);
+        }
+        None if matches.opt_present("manpage") => Target::Manpage,
+        None => match matches.opt_str("lang").map(|s| s.parse::<Language>()) {
+            Some(Ok(lang)) => Target::Code(lang),
+            Some(Err(e)) => {
+                writeln!(&mut io::stderr(), "{}", e).unwrap();
+                process::exit(1);
+            }
+            None => Target::Code(Language::C),
+        },
+    };
+    let std = match matches.opt_str("std").map(|s| s.parse::<CStd>()) {
+        Some(Ok(std)) => Some(std),
+        Some(Err(e)) => {
+            writeln!(&mut io::stderr(), "{}", e).unwrap();
+            process::exit(1);
+        }
+        None => None,
+    };
+    let c_target = match matches.opt_str("target").map(|s| s.parse::<CTarget>()) {
+        Some(Ok(c_target)) => Some(c_target),
+        Some(Err(e)) => {
+            writeln!(&mut io::stderr(), "{}", e).unwrap();
+            process::exit(1);
+        }
+        None => None,
+    };
     let input = if !matches.free.is_empty() {
         matches.free[0].clone()
     } else {
         print_usage(&program, opts);
         return;
     };
+    if input == "init" && matches.free.len() == 1 {
+        return init(output);
+    }
+    if input == "import" && matches.free.len() == 2 {
+        return import(&matches.free[1], output);
+    }
+    if matches.opt_present("check") {
+        return check(&input);
+    }
 
-    codegen(input, output)
+    codegen(
+        input,
+        output,
+        target,
+        std,
+        c_target,
+        matches.opt_str("template-dir"),
+    )
 }
 
 #[cfg(test)]
 mod tests {
-    use super::codegen;
+    use super::{codegen, Language, Target};
 
     #[test]
     fn it_works() {
-        codegen(String::from("examples/example_spec.toml"), None)
+        codegen(
+            String::from("examples/example_spec.toml"),
+            None,
+            Target::Code(Language::C),
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn it_works_json() {
+        codegen(
+            String::from("examples/example_spec.json"),
+            None,
+            Target::Code(Language::C),
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn it_works_yaml() {
+        codegen(
+            String::from("examples/example_spec.yaml"),
+            None,
+            Target::Code(Language::C),
+            None,
+            None,
+            None,
+        )
     }
 }
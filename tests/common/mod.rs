@@ -0,0 +1,64 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Helpers shared by the integration tests, which are not all used by each of them.
+#![allow(dead_code)]
+
+use argen::Spec;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const COMPILERS: [&str; 2] = ["gcc", "clang"];
+
+pub fn installed(cc: &str) -> bool {
+    Command::new(cc).arg("--version").output().is_ok()
+}
+
+/// The installed compilers out of gcc and clang.
+pub fn compilers() -> Vec<&'static str> {
+    COMPILERS
+        .iter()
+        .cloned()
+        .filter(|cc| installed(cc))
+        .collect()
+}
+
+/// Every spec fixture in tests/specs, by path.
+pub fn specs() -> Vec<PathBuf> {
+    let mut specs: Vec<PathBuf> = fs::read_dir("tests/specs")
+        .expect("read tests/specs")
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    specs.sort();
+    specs
+}
+
+pub fn stem(path: &Path) -> &str {
+    path.file_stem().unwrap().to_str().unwrap()
+}
+
+pub fn read_spec(path: &Path) -> Spec {
+    Spec::from_path(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// A fresh directory for build output, named after the test using it.
+pub fn scratch_dir(test: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("argen-{}-{}", test, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
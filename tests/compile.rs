@@ -0,0 +1,149 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compiles the C and C++ generated for every spec in tests/specs, warnings as errors, with each
+//! of gcc and clang (g++ and clang++) that is installed. Nothing is linked, since validators are
+//! left to the user.
+
+mod common;
+
+use argen::{CStd, Language, NonPositionalItem, SpecBuilder};
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Flags for each standard. C89 adds -pedantic, which is what enforces declarations first.
+fn std_flags(std: CStd) -> &'static [&'static str] {
+    match std {
+        CStd::C89 => &["-std=c89", "-pedantic"],
+        CStd::C99 => &["-std=c99"],
+        CStd::C11 => &["-std=c11"],
+    }
+}
+
+/// Compiles source with cc as std, warnings as errors, returning what cc said if it failed.
+fn compile(cc: &str, std: CStd, source: &Path, dir: &Path) -> Result<(), String> {
+    let out = Command::new(cc)
+        .args(std_flags(std))
+        .args(["-Wall", "-Wextra", "-Werror", "-c", "-o"])
+        .arg(dir.join("a.o"))
+        .arg(source)
+        .output()
+        .unwrap();
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&out.stderr).into_owned())
+    }
+}
+
+#[test]
+fn generated_c_compiles_without_warnings() {
+    let compilers = common::compilers();
+    if compilers.is_empty() {
+        eprintln!("neither gcc nor clang is installed, skipping");
+        return;
+    }
+    let dir = common::scratch_dir("compile");
+    let mut specs = common::specs();
+    specs.push(PathBuf::from("examples/example_spec.toml"));
+    let mut failures = Vec::new();
+    for path in specs {
+        for &std in &[CStd::C89, CStd::C99, CStd::C11] {
+            let mut spec = common::read_spec(&path);
+            spec.set_c_std(std);
+            let source = dir.join(format!("{}-{:?}.c", common::stem(&path), std));
+            fs::write(&source, spec.gen(Language::C)).unwrap();
+            for cc in &compilers {
+                if let Err(err) = compile(cc, std, &source, &dir) {
+                    failures.push(format!(
+                        "{} {:?} with {}:\n{}",
+                        path.display(),
+                        std,
+                        cc,
+                        err
+                    ));
+                }
+            }
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn generated_cpp_compiles_without_warnings() {
+    let compilers: Vec<&str> = ["g++", "clang++"]
+        .iter()
+        .cloned()
+        .filter(|cxx| common::installed(cxx))
+        .collect();
+    if compilers.is_empty() {
+        eprintln!("neither g++ nor clang++ is installed, skipping");
+        return;
+    }
+    let dir = common::scratch_dir("compile-cpp");
+    let mut specs = common::specs();
+    specs.push(PathBuf::from("examples/example_spec.toml"));
+    let mut failures = Vec::new();
+    for path in specs {
+        let source = dir.join(format!("{}.cpp", common::stem(&path)));
+        fs::write(&source, common::read_spec(&path).gen(Language::Cpp)).unwrap();
+        for cxx in &compilers {
+            let out = Command::new(cxx)
+                .args(["-std=c++17", "-Wall", "-Wextra", "-Werror", "-c", "-o"])
+                .arg(dir.join("a.o"))
+                .arg(&source)
+                .output()
+                .unwrap();
+            if !out.status.success() {
+                failures.push(format!(
+                    "{} with {}:\n{}",
+                    path.display(),
+                    cxx,
+                    String::from_utf8_lossy(&out.stderr)
+                ));
+            }
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// More long-only options than there are bytes, so their getopt_long values go past 255.
+#[test]
+fn many_options_compile() {
+    let compilers = common::compilers();
+    if compilers.is_empty() {
+        eprintln!("neither gcc nor clang is installed, skipping");
+        return;
+    }
+    let mut builder = SpecBuilder::new().version("1.0");
+    for i in 0..300 {
+        builder = builder.option(NonPositionalItem::new(&format!("option-{}", i)));
+    }
+    let spec = builder.build().unwrap();
+    let dir = common::scratch_dir("many-options");
+    let source = dir.join("many.c");
+    fs::write(&source, spec.gen(Language::C)).unwrap();
+    let failures: Vec<String> = compilers
+        .iter()
+        .filter_map(|cc| compile(cc, CStd::C99, &source, &dir).err())
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,100 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compares the C generated for each spec in tests/specs with tests/golden, and for the example
+//! specs with examples/example.c, and the C laid out by tests/templates with tests/golden/templated.
+//! Run with ARGEN_BLESS=1 to rewrite the golden files after an
+//! intended change to the output, then review the diff.
+
+mod common;
+
+use argen::{Language, Template};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Checks that spec generates the contents of golden as template lays it out, or writes them
+/// there when blessing.
+fn check(spec: &Path, golden: &Path, template: &Template) -> Option<String> {
+    let generated = common::read_spec(spec).gen_with(Language::C, template);
+    if env::var_os("ARGEN_BLESS").is_some() {
+        fs::write(golden, &generated).unwrap();
+        return None;
+    }
+    let expected = fs::read_to_string(golden).unwrap_or_default();
+    if generated == expected {
+        return None;
+    }
+    let line = generated
+        .lines()
+        .zip(expected.lines())
+        .position(|(g, e)| g != e)
+        .unwrap_or_else(|| generated.lines().count().min(expected.lines().count()));
+    Some(format!(
+        "{} differs from {} at line {}",
+        spec.display(),
+        golden.display(),
+        line + 1
+    ))
+}
+
+#[test]
+fn generated_c_matches_golden_files() {
+    let mut pairs: Vec<(PathBuf, PathBuf)> = common::specs()
+        .into_iter()
+        .map(|spec| {
+            let golden = Path::new("tests/golden").join(format!("{}.c", common::stem(&spec)));
+            (spec, golden)
+        })
+        .collect();
+    for ext in &["toml", "json", "yaml"] {
+        pairs.push((
+            PathBuf::from(format!("examples/example_spec.{}", ext)),
+            PathBuf::from("examples/example.c"),
+        ));
+    }
+    let failures: Vec<String> = pairs
+        .iter()
+        .filter_map(|(spec, golden)| check(spec, golden, &Template::default()))
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "{}\n(rerun with ARGEN_BLESS=1 if the change is intended)",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn templated_c_matches_golden_files() {
+    // a brace in a comment, as in one the template puts text in, must not throw off brace style
+    let pairs = [
+        ("c.tmpl", "maps_and_counts"),
+        ("comment_braces.tmpl", "linux_braces"),
+    ];
+    let mut failures = Vec::new();
+    for (template, spec) in &pairs {
+        let text = fs::read_to_string(Path::new("tests/templates").join(template)).unwrap();
+        let template = Template::parse(&text).unwrap();
+        let golden = Path::new("tests/golden/templated").join(format!("{}.c", spec));
+        let spec = Path::new("tests/specs").join(format!("{}.toml", spec));
+        failures.extend(check(&spec, &golden, &template));
+    }
+    assert!(
+        failures.is_empty(),
+        "{}\n(rerun with ARGEN_BLESS=1 if the change is intended)",
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,236 @@
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<getopt.h>
+#include<stdbool.h>
+
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options]\n%s", progname,
+	             "  -h, --help        print this usage and exit\n"
+	             "      --verbose\n"
+	             "      --verbatim\n"
+	             "      --name <STR>  (aliased: --nom)\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[9];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"verbose",
+		"verbatim",
+		"name",
+		"nom",
+		"help",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	size_t matches = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		if (strncmp(names[i], typed, typed_len) == 0) {
+			matches++;
+		}
+	}
+	if (matches > 1) {
+		fprintf(stderr, "%s: ambiguous option: --%.*s could be", progname, (int)typed_len, typed);
+		for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+			if (strncmp(names[i], typed, typed_len) == 0) {
+				fprintf(stderr, " --%s", names[i]);
+			}
+		}
+		fprintf(stderr, "\n");
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static int ascii_tolower(int c) {
+	return c >= 'A' && c <= 'Z' ? c - 'A' + 'a' : c;
+}
+
+static void fold_option_case(char **argv, int at, int argc) {
+	static const char *names[] = {
+		"verbose",
+		"name",
+		"nom",
+	};
+	char *typed;
+	size_t typed_len;
+	if (at >= argc || strncmp(argv[at], "--", 2) != 0) {
+		return;
+	}
+	typed = argv[at] + 2;
+	typed_len = strcspn(typed, "=");
+	for (int pass = 0; pass < 2; pass++) {
+		for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+			size_t known_len = strlen(names[i]);
+			size_t j = 0;
+			if (!(pass == 0 ? known_len == typed_len : known_len > typed_len)) {
+				continue;
+			}
+			while (j < typed_len && ascii_tolower((unsigned char)typed[j]) == ascii_tolower((unsigned char)names[i][j])) {
+				j++;
+			}
+			if (j == typed_len) {
+				memcpy(typed, names[i], typed_len);
+				return;
+			}
+		}
+	}
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] verbose --verbose. Set when given.
+ * @param[out] verbatim --verbatim. Set when given.
+ * @param[out] name --name.
+ */
+void parse_args(int argc, char **argv, int *verbose, int *verbatim, char* *name) {
+	const char *progname = argv[0];
+	bool verbose__isset = false;
+	bool verbatim__isset = false;
+	static struct option longopts[] = {
+		{"verbose", no_argument, 0, 256},
+		{"verbatim", no_argument, 0, 257},
+		{"name", required_argument, 0, 258},
+		{"nom", required_argument, 0, 258},
+		{"help", 0, 0, 'h'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	*name = NULL;
+	opterr = 0;
+	for (;;) {
+		fold_option_case(argv, optind, argc);
+		ch = getopt_long(argc, argv, "-:h", longopts, NULL);
+		if (ch == -1) {
+			break;
+		}
+		switch (ch) {
+		case 256:
+			*verbose = 1;
+			verbose__isset = true;
+			break;
+		case 257:
+			*verbatim = 1;
+			verbatim__isset = true;
+			break;
+		case 258:
+			*name = optarg;
+			break;
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(1);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(1);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(1);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+	if (!verbose__isset) {
+		*verbose = 0;
+	}
+	if (!verbatim__isset) {
+		*verbatim = 0;
+	}
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	int verbose;
+	int verbatim;
+	char* name;
+
+	parse_args(argc, argv, &verbose, &verbatim, &name);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	return 0;
+	/* ARGEN:END */
+}
@@ -0,0 +1,382 @@
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<getopt.h>
+#include<ctype.h>
+#include<errno.h>
+#include<limits.h>
+#include<stdbool.h>
+
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options]\n%s", progname,
+	             "  -h, --help           print this usage and exit\n"
+	             "      --config <FILE>  read defaults from FILE instead of config_file.conf\n"
+	             "      --host <STR>\n"
+	             "      --port <NUM>\n"
+	             "  -v, --verbose\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[8];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"config",
+		"host",
+		"port",
+		"verbose",
+		"help",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+struct config_entry {
+	char *key;
+	char *value;
+	int line;
+	int used;
+};
+
+struct config {
+	const char *path;
+	struct config_entry *entries;
+	size_t size;
+};
+
+static char *config_trim(char *s) {
+	char *end = s + strlen(s);
+	while (isspace((unsigned char)*s)) {
+		s++;
+	}
+	while (end > s && isspace((unsigned char)end[-1])) {
+		end--;
+	}
+	*end = '\0';
+	return s;
+}
+
+static int config_add(const char *progname, struct config *config, char *line, int lineno) {
+	char *key = config_trim(line);
+	char *eq;
+	struct config_entry *grown;
+	if (*key == '\0' || *key == '#' || *key == ';' || *key == '[') {
+		return 0;
+	}
+	eq = strchr(key, '=');
+	if (!eq) {
+		fprintf(stderr, "%s: %s:%d: expected KEY = VALUE\n", progname, config->path, lineno);
+		exit(1);
+	}
+	*eq = '\0';
+	grown = realloc(config->entries, (config->size + 1) * sizeof(struct config_entry));
+	if (!grown) {
+		perror("realloc");
+		exit(1);
+	}
+	config->entries = grown;
+	grown[config->size].key = config_trim(key);
+	grown[config->size].value = config_trim(eq + 1);
+	grown[config->size].line = lineno;
+	grown[config->size].used = 0;
+	config->size++;
+	return 1;
+}
+
+static void read_config(const char *progname, const char *path, int must_exist,
+	                        struct config *config) {
+	FILE *f;
+	char *line = NULL;
+	size_t len = 0, size = 0;
+	int c, lineno = 1;
+	config->path = path;
+	config->entries = NULL;
+	config->size = 0;
+	if (!path) {
+		return;
+	}
+	f = fopen(path, "r");
+	if (!f) {
+		if (!must_exist && errno == ENOENT) {
+			return;
+		}
+		fprintf(stderr, "%s: cannot read %s: %s\n", progname, path, strerror(errno));
+		exit(1);
+	}
+	do {
+		c = fgetc(f);
+		if (len + 1 >= size) {
+			char *grown;
+			size = size ? size * 2 : 64;
+			grown = realloc(line, size);
+			if (!grown) {
+				perror("realloc");
+				exit(1);
+			}
+			line = grown;
+		}
+		if (c != EOF && c != '\n') {
+			line[len++] = (char)c;
+			continue;
+		}
+		line[len] = '\0';
+		if (config_add(progname, config, line, lineno)) {
+			line = NULL;
+			size = 0;
+		}
+		len = 0;
+		lineno++;
+	} while (c != EOF);
+	free(line);
+	if (ferror(f)) {
+		fprintf(stderr, "%s: cannot read %s\n", progname, path);
+		exit(1);
+	}
+	fclose(f);
+}
+
+static char *config_get(struct config *config, const char *key) {
+	char *value = NULL;
+	for (size_t i = 0; i < config->size; i++) {
+		if (strcmp(config->entries[i].key, key) == 0) {
+			config->entries[i].used = 1;
+			value = config->entries[i].value;
+		}
+	}
+	return value;
+}
+
+static int config_flag(const char *progname, const struct config *config, const char *key,
+	                       const char *value) {
+	if (!strcmp(value, "1") || !strcmp(value, "true") || !strcmp(value, "yes") || !strcmp(value, "on")) {
+		return 1;
+	}
+	if (!strcmp(value, "0") || !strcmp(value, "false") || !strcmp(value, "no") || !strcmp(value, "off")) {
+		return 0;
+	}
+	fprintf(stderr, "%s: %s: expected true or false for %s, got '%s'\n", progname, config->path, key, value);
+	exit(1);
+}
+
+static void config_check_used(const char *progname, const struct config *config) {
+	for (size_t i = 0; i < config->size; i++) {
+		if (!config->entries[i].used) {
+			fprintf(stderr, "%s: %s:%d: unknown key '%s'\n", progname, config->path,
+			        config->entries[i].line, config->entries[i].key);
+			exit(1);
+		}
+	}
+}
+
+static int parse_int(const char *progname, const char *name, const char *arg) {
+	char *end;
+	long value;
+	errno = 0;
+	value = strtol(arg, &end, 0);
+	if (end == arg || *end != '\0') {
+		fprintf(stderr, "%s: invalid value for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+		fprintf(stderr, "%s: value out of range for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	return (int)value;
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] config --config: read defaults from FILE instead of
+ *   config_file.conf.
+ * @param[out] host --host. Required.
+ * @param[out] port --port. Defaults to 80.
+ * @param[out] verbose -v, --verbose. Set when given.
+ */
+void parse_args(int argc, char **argv, char* *config, char* *host, int *port, int *verbose) {
+	const char *progname = "serve";
+	bool config__isset = false;
+	bool host__isset = false;
+	bool port__isset = false;
+	bool verbose__isset = false;
+	static int port__default = 80;
+	static struct option longopts[] = {
+		{"config", required_argument, 0, 256},
+		{"host", required_argument, 0, 257},
+		{"port", required_argument, 0, 258},
+		{"verbose", no_argument, 0, 118},
+		{"help", 0, 0, 'h'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	int at;
+	int longindex;
+	struct config file_config;
+	*config = NULL;
+	opterr = 0;
+	for (;;) {
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:vh", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(1);
+		}
+		switch (ch) {
+		case 256:
+			*config = optarg;
+			config__isset = true;
+			break;
+		case 257:
+			*host = optarg;
+			host__isset = true;
+			break;
+		case 258:
+			*port = parse_int(progname, "--port", optarg);
+			port__isset = true;
+			break;
+		case 118:
+			*verbose = 1;
+			verbose__isset = true;
+			break;
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(1);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(1);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(1);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+	read_config(progname, config__isset ? *config : "config_file.conf", config__isset, &file_config);
+	if ((optarg = config_get(&file_config, "host")) != NULL && !host__isset) {
+		*host = optarg;
+		host__isset = true;
+	}
+	if ((optarg = config_get(&file_config, "port")) != NULL && !port__isset) {
+		*port = parse_int(progname, "--port", optarg);
+		port__isset = true;
+	}
+	if ((optarg = config_get(&file_config, "verbose")) != NULL && !verbose__isset && config_flag(progname, &file_config, "verbose", optarg)) {
+		*verbose = 1;
+		verbose__isset = true;
+	}
+	config_check_used(progname, &file_config);
+	if (!host__isset) {
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (!port__isset) {
+		*port = port__default;
+	}
+	if (!verbose__isset) {
+		*verbose = 0;
+	}
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	char* config;
+	char* host;
+	int port;
+	int verbose;
+
+	parse_args(argc, argv, &config, &host, &port, &verbose);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	return 0;
+	/* ARGEN:END */
+}
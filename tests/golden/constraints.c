@@ -0,0 +1,327 @@
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<getopt.h>
+#include<errno.h>
+#include<limits.h>
+#include<stdbool.h>
+
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options] TARGET [RETRIES]\n%s", progname,
+	             "Checks requires, conflicts, and groups.\n"
+	             "\n"
+	             "  TARGET\n"
+	             "  RETRIES\n"
+	             "  -h, --help        print this usage and exit\n"
+	             "  -V, --version     print version and exit\n"
+	             "  -u, --user <STR>  user to connect as, which is a\n"
+	             "                    rather long description that\n"
+	             "                    wraps (aliased: --login)\n"
+	             "      --json\n"
+	             "      --xml\n"
+	             "      --port <NUM>\n"
+	             "      --host <STR>\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[8];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"user",
+		"login",
+		"json",
+		"xml",
+		"port",
+		"host",
+		"help",
+		"version",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static int is_digit(char c) {
+	return c >= '0' && c <= '9';
+}
+
+static int is_negative_number(const char *arg) {
+	if (arg[0] != '-' || !is_digit(arg[1])) {
+		return 0;
+	}
+	arg++;
+	while (is_digit(*arg)) {
+		arg++;
+	}
+	if (arg[0] == '.' && is_digit(arg[1])) {
+		arg++;
+		while (is_digit(*arg)) {
+			arg++;
+		}
+	}
+	return *arg == '\0';
+}
+
+static int parse_int(const char *progname, const char *name, const char *arg) {
+	char *end;
+	long value;
+	errno = 0;
+	value = strtol(arg, &end, 0);
+	if (end == arg || *end != '\0') {
+		fprintf(stderr, "%s: invalid value for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+		fprintf(stderr, "%s: value out of range for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	return (int)value;
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] user -u, --user: user to connect as, which is a rather long
+ *   description that wraps. Required.
+ * @param[out] json --json. Set when given.
+ * @param[out] xml --xml. Set when given.
+ * @param[out] port --port. Defaults to 0x50.
+ * @param[out] host --host.
+ * @param[out] target TARGET. Required.
+ * @param[out] retries RETRIES. Defaults to -3.
+ */
+void parse_args(int argc, char **argv, char* *user, int *json, int *xml, int *port, char* *host, char* *target, int *retries) {
+	const char *progname = "say \"hi\"";
+	bool user__isset = false;
+	bool json__isset = false;
+	bool xml__isset = false;
+	bool port__isset = false;
+	bool host__isset = false;
+	bool retries__isset = false;
+	static int port__default = 0x50;
+	static int retries__default = -3;
+	static struct option longopts[] = {
+		{"user", required_argument, 0, 117},
+		{"login", required_argument, 0, 117},
+		{"json", no_argument, 0, 256},
+		{"xml", no_argument, 0, 257},
+		{"port", required_argument, 0, 258},
+		{"host", required_argument, 0, 259},
+		{"help", 0, 0, 'h'},
+		{"version", 0, 0, 'V'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	int at;
+	int longindex;
+	*host = NULL;
+	opterr = 0;
+	for (;;) {
+		if (optind < argc && is_negative_number(argv[optind])) {
+			argv[++npos] = argv[optind++];
+			continue;
+		}
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:u:hV", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(1);
+		}
+		switch (ch) {
+		case 117:
+			*user = optarg;
+			user__isset = true;
+			break;
+		case 256:
+			*json = 1;
+			json__isset = true;
+			break;
+		case 257:
+			*xml = 1;
+			xml__isset = true;
+			break;
+		case 258:
+			*port = parse_int(progname, "--port", optarg);
+			port__isset = true;
+			break;
+		case 259:
+			*host = optarg;
+			host__isset = true;
+			break;
+		case 'V':
+			printf("%s 2.0 (100%%)\n", progname);
+			exit(0);
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(1);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(1);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(1);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+	if (!user__isset) {
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (!json__isset) {
+		*json = 0;
+	}
+	if (!xml__isset) {
+		*xml = 0;
+	}
+	if (!port__isset) {
+		*port = port__default;
+	}
+	if (json__isset + xml__isset + host__isset < 1) {
+		fprintf(stderr, "%s: one of these options is required: --json, --xml, --host\n", progname);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (json__isset && xml__isset) {
+		fprintf(stderr, "%s: option --json conflicts with --xml\n", progname);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (port__isset && !host__isset) {
+		fprintf(stderr, "%s: option --port requires --host\n", progname);
+		usage(stderr, progname);
+		exit(1);
+	}
+
+	if (argc-optind < 1) {
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (argc-optind > 2) {
+		fprintf(stderr, "%s: unexpected argument: %s\n", progname, argv[optind+2]);
+		usage(stderr, progname);
+		exit(1);
+	}
+	argv += optind;
+	argc -= optind;
+
+	*target = argv[0];
+	argv++;
+	argc--;
+
+	if (argc > 0) {
+		*retries = parse_int(progname, "RETRIES", argv[0]);
+		retries__isset = true;
+		argv++; argc--;
+	}
+	if (!retries__isset) {
+		*retries = retries__default;
+	}
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	char* user;
+	int json;
+	int xml;
+	int port;
+	char* host;
+	char* target;
+	int retries;
+
+	parse_args(argc, argv, &user, &json, &xml, &port, &host, &target, &retries);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	return 0;
+	/* ARGEN:END */
+}
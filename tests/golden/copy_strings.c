@@ -0,0 +1,331 @@
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<getopt.h>
+#include<stdbool.h>
+
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options] INPUT [REST...]\n%s", progname,
+	             "  INPUT\n"
+	             "  REST\n"
+	             "  -h, --help        print this usage and exit\n"
+	             "      --name <STR>\n"
+	             "  -D, --define <NAME=VALUE>\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[7];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"name",
+		"define",
+		"help",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static int is_digit(char c) {
+	return c >= '0' && c <= '9';
+}
+
+static int is_negative_number(const char *arg) {
+	if (arg[0] != '-' || !is_digit(arg[1])) {
+		return 0;
+	}
+	arg++;
+	while (is_digit(*arg)) {
+		arg++;
+	}
+	if (arg[0] == '.' && is_digit(arg[1])) {
+		arg++;
+		while (is_digit(*arg)) {
+			arg++;
+		}
+	}
+	return *arg == '\0';
+}
+
+static char *copy_string(const char *value) {
+	char *copy;
+	if (!value) {
+		return NULL;
+	}
+	copy = malloc(strlen(value) + 1);
+	if (!copy) {
+		perror("malloc");
+		exit(1);
+	}
+	return strcpy(copy, value);
+}
+
+static char **copy_strings(char **values, size_t size) {
+	char **copies;
+	if (size == 0) {
+		return NULL;
+	}
+	copies = malloc(size * sizeof(char *));
+	if (!copies) {
+		perror("malloc");
+		exit(1);
+	}
+	for (size_t i = 0; i < size; i++) {
+		copies[i] = copy_string(values[i]);
+	}
+	return copies;
+}
+
+static void map_push(const char *progname, const char *opt, char *arg,
+	             char ***keys, char ***values, size_t *size, size_t *cap) {
+	char *eq = strchr(arg, '=');
+	if (!eq) {
+		fprintf(stderr, "%s: expected NAME=VALUE for --%s, got '%s'\n", progname, opt, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (*cap == 0) {
+		*keys = NULL;
+		*values = NULL;
+		*size = 0;
+	}
+	if (*size == *cap) {
+		size_t new_cap = *cap ? *cap * 2 : 8;
+		char **grown_keys = realloc(*keys, new_cap * sizeof(char *));
+		char **grown_values;
+		if (!grown_keys) {
+			perror("realloc");
+			exit(1);
+		}
+		*keys = grown_keys;
+		grown_values = realloc(*values, new_cap * sizeof(char *));
+		if (!grown_values) {
+			perror("realloc");
+			exit(1);
+		}
+		*values = grown_values;
+		*cap = new_cap;
+	}
+	arg = copy_string(arg);
+	eq = strchr(arg, '=');
+	*eq = '\0';
+	(*keys)[*size] = arg;
+	(*values)[*size] = eq + 1;
+	(*size)++;
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] name --name. Defaults to "anon".
+ * @param[out] defs__keys -D, --define.
+ * @param[out] defs__values Values paired with defs__keys.
+ * @param[out] defs__size Number of pairs.
+ * @param[out] input INPUT. Required.
+ * @param[out] rest REST...
+ * @param[out] rest__size Number of values in rest.
+ */
+void parse_args(int argc, char **argv, char* *name, char* **defs__keys, char* **defs__values, size_t *defs__size, char* *input, char* **rest, size_t *rest__size) {
+	const char *progname = "copy_strings";
+	bool name__isset = false;
+	bool defs__isset = false;
+	size_t defs__cap = 0;
+	static char* name__default = "anon";
+	static struct option longopts[] = {
+		{"name", required_argument, 0, 256},
+		{"define", required_argument, 0, 68},
+		{"help", 0, 0, 'h'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	int at;
+	int longindex;
+	*rest = NULL;
+	*rest__size = 0;
+	opterr = 0;
+	for (;;) {
+		if (optind < argc && is_negative_number(argv[optind])) {
+			argv[++npos] = argv[optind++];
+			continue;
+		}
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:D:h", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(1);
+		}
+		switch (ch) {
+		case 256:
+			*name = optarg;
+			name__isset = true;
+			break;
+		case 68:
+			map_push(progname, "define", optarg, defs__keys, defs__values, defs__size, &defs__cap);
+			defs__isset = true;
+			break;
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(1);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(1);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(1);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+	if (!name__isset) {
+		*name = name__default;
+	}
+	if (!defs__isset) {
+		*defs__keys = NULL;
+		*defs__values = NULL;
+		*defs__size = 0;
+	}
+
+	if (argc-optind < 1) {
+		usage(stderr, progname);
+		exit(1);
+	}
+	argv += optind;
+	argc -= optind;
+
+	*input = argv[0];
+	argv++;
+	argc--;
+
+	if (argc > 0) {
+		*rest = argv;
+		*rest__size = argc;
+	}
+
+	*name = copy_string(*name);
+	*input = copy_string(*input);
+	*rest = copy_strings(*rest, *rest__size);
+}
+
+/**
+ * Frees the copies of string values parse_args made, given the variables it
+ * filled in.
+ */
+void free_args(char *name, char **defs__keys, char **defs__values, size_t defs__size, char *input, char **rest, size_t rest__size) {
+	free(name);
+	for (size_t i = 0; i < defs__size; i++) {
+		free(defs__keys[i]);
+	}
+	free(defs__keys);
+	free(defs__values);
+	free(input);
+	for (size_t i = 0; i < rest__size; i++) {
+		free(rest[i]);
+	}
+	free(rest);
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	char* name;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(argc, argv, &name, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	free_args(name, defs__keys, defs__values, defs__size, input, rest, rest__size);
+	return 0;
+	/* ARGEN:END */
+}
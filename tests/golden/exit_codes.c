@@ -0,0 +1,269 @@
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<getopt.h>
+#include<ctype.h>
+#include<errno.h>
+#include<limits.h>
+#include<stdbool.h>
+
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options]\n%s", progname,
+	             "  -h, --help         print this usage and exit\n"
+	             "      --level <NUM>  how loud to be\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[6];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"level",
+		"help",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static void argv_push(int *argc, char ***argv, size_t *cap, char *arg) {
+	if ((size_t)*argc + 1 >= *cap) {
+		size_t new_cap = *cap ? *cap * 2 : 16;
+		char **grown = realloc(*argv, new_cap * sizeof(char *));
+		if (!grown) {
+			perror("realloc");
+			exit(66);
+		}
+		*argv = grown;
+		*cap = new_cap;
+	}
+	(*argv)[(*argc)++] = arg;
+	(*argv)[*argc] = NULL;
+}
+
+static void expand_response_file(const char *progname, const char *path, int depth,
+	                                 int *argc, char ***argv, size_t *cap) {
+	FILE *f;
+	char *word = NULL;
+	size_t len = 0, size = 0;
+	int c;
+	if (depth > 16) {
+		fprintf(stderr, "%s: response files nested too deeply at @%s\n", progname, path);
+		exit(66);
+	}
+	f = fopen(path, "r");
+	if (!f) {
+		fprintf(stderr, "%s: cannot read @%s: %s\n", progname, path, strerror(errno));
+		exit(66);
+	}
+	do {
+		c = fgetc(f);
+		if (c == EOF || isspace(c)) {
+			if (len == 0) {
+				continue;
+			}
+			word[len] = '\0';
+			if (word[0] == '@' && word[1] != '\0') {
+				expand_response_file(progname, word + 1, depth + 1, argc, argv, cap);
+				free(word);
+			} else {
+				argv_push(argc, argv, cap, word);
+			}
+			word = NULL;
+			len = size = 0;
+			continue;
+		}
+		if (len + 1 >= size) {
+			char *grown;
+			size = size ? size * 2 : 32;
+			grown = realloc(word, size);
+			if (!grown) {
+				perror("realloc");
+				exit(66);
+			}
+			word = grown;
+		}
+		word[len++] = (char)c;
+	} while (c != EOF);
+	if (ferror(f)) {
+		fprintf(stderr, "%s: cannot read @%s\n", progname, path);
+		exit(66);
+	}
+	fclose(f);
+}
+
+static void expand_response_files(const char *progname, int *argc, char ***argv) {
+	char **expanded = NULL;
+	int nexpanded = 0;
+	size_t cap = 0;
+	for (int i = 0; i < *argc; i++) {
+		char *arg = (*argv)[i];
+		if (i > 0 && arg[0] == '@' && arg[1] != '\0') {
+			expand_response_file(progname, arg + 1, 1, &nexpanded, &expanded, &cap);
+		} else {
+			argv_push(&nexpanded, &expanded, &cap, arg);
+		}
+	}
+	*argc = nexpanded;
+	*argv = expanded;
+}
+
+static int parse_int(const char *progname, const char *name, const char *arg) {
+	char *end;
+	long value;
+	errno = 0;
+	value = strtol(arg, &end, 0);
+	if (end == arg || *end != '\0') {
+		fprintf(stderr, "%s: invalid value for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(64);
+	}
+	if (errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+		fprintf(stderr, "%s: value out of range for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(64);
+	}
+	return (int)value;
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] level --level: how loud to be.
+ */
+void parse_args(int argc, char **argv, int *level) {
+	const char *progname = "exit_codes";
+	static struct option longopts[] = {
+		{"level", required_argument, 0, 256},
+		{"help", 0, 0, 'h'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	int at;
+	int longindex;
+	*level = 0;
+	expand_response_files(progname, &argc, &argv);
+	opterr = 0;
+	for (;;) {
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:h", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(64);
+		}
+		switch (ch) {
+		case 256:
+			*level = parse_int(progname, "--level", optarg);
+			break;
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(64);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(64);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(64);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	int level;
+
+	parse_args(argc, argv, &level);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	return 0;
+	/* ARGEN:END */
+}
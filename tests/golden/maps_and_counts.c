@@ -0,0 +1,425 @@
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<getopt.h>
+#include<ctype.h>
+#include<errno.h>
+#include<limits.h>
+#include<stdbool.h>
+
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options] N\n%s", progname,
+	             "Demo 100% things\n"
+	             "\n"
+	             "  N\n"
+	             "  -h, --help     print this usage and exit\n"
+	             "  -V, --version  print version and exit\n"
+	             "  -D, --define <NAME=VALUE>\n"
+	             "      --aa\n"
+	             "      --bb\n"
+	             "  -v, --verbose\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[8];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"define",
+		"aa",
+		"bb",
+		"verbose",
+		"help",
+		"version",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static void argv_push(int *argc, char ***argv, size_t *cap, char *arg) {
+	if ((size_t)*argc + 1 >= *cap) {
+		size_t new_cap = *cap ? *cap * 2 : 16;
+		char **grown = realloc(*argv, new_cap * sizeof(char *));
+		if (!grown) {
+			perror("realloc");
+			exit(1);
+		}
+		*argv = grown;
+		*cap = new_cap;
+	}
+	(*argv)[(*argc)++] = arg;
+	(*argv)[*argc] = NULL;
+}
+
+static void expand_response_file(const char *progname, const char *path, int depth,
+	                                 int *argc, char ***argv, size_t *cap) {
+	FILE *f;
+	char *word = NULL;
+	size_t len = 0, size = 0;
+	int c;
+	if (depth > 16) {
+		fprintf(stderr, "%s: response files nested too deeply at @%s\n", progname, path);
+		exit(1);
+	}
+	f = fopen(path, "r");
+	if (!f) {
+		fprintf(stderr, "%s: cannot read @%s: %s\n", progname, path, strerror(errno));
+		exit(1);
+	}
+	do {
+		c = fgetc(f);
+		if (c == EOF || isspace(c)) {
+			if (len == 0) {
+				continue;
+			}
+			word[len] = '\0';
+			if (word[0] == '@' && word[1] != '\0') {
+				expand_response_file(progname, word + 1, depth + 1, argc, argv, cap);
+				free(word);
+			} else {
+				argv_push(argc, argv, cap, word);
+			}
+			word = NULL;
+			len = size = 0;
+			continue;
+		}
+		if (len + 1 >= size) {
+			char *grown;
+			size = size ? size * 2 : 32;
+			grown = realloc(word, size);
+			if (!grown) {
+				perror("realloc");
+				exit(1);
+			}
+			word = grown;
+		}
+		word[len++] = (char)c;
+	} while (c != EOF);
+	if (ferror(f)) {
+		fprintf(stderr, "%s: cannot read @%s\n", progname, path);
+		exit(1);
+	}
+	fclose(f);
+}
+
+static void expand_response_files(const char *progname, int *argc, char ***argv) {
+	char **expanded = NULL;
+	int nexpanded = 0;
+	size_t cap = 0;
+	for (int i = 0; i < *argc; i++) {
+		char *arg = (*argv)[i];
+		if (i > 0 && arg[0] == '@' && arg[1] != '\0') {
+			expand_response_file(progname, arg + 1, 1, &nexpanded, &expanded, &cap);
+		} else {
+			argv_push(&nexpanded, &expanded, &cap, arg);
+		}
+	}
+	*argc = nexpanded;
+	*argv = expanded;
+}
+
+static int is_digit(char c) {
+	return c >= '0' && c <= '9';
+}
+
+static int is_negative_number(const char *arg) {
+	if (arg[0] != '-' || !is_digit(arg[1])) {
+		return 0;
+	}
+	arg++;
+	while (is_digit(*arg)) {
+		arg++;
+	}
+	if (arg[0] == '.' && is_digit(arg[1])) {
+		arg++;
+		while (is_digit(*arg)) {
+			arg++;
+		}
+	}
+	return *arg == '\0';
+}
+
+static int parse_int(const char *progname, const char *name, const char *arg) {
+	char *end;
+	long value;
+	errno = 0;
+	value = strtol(arg, &end, 0);
+	if (end == arg || *end != '\0') {
+		fprintf(stderr, "%s: invalid value for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+		fprintf(stderr, "%s: value out of range for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	return (int)value;
+}
+
+static void map_push(const char *progname, const char *opt, char *arg,
+	             char ***keys, char ***values, size_t *size, size_t *cap) {
+	char *eq = strchr(arg, '=');
+	if (!eq) {
+		fprintf(stderr, "%s: expected NAME=VALUE for --%s, got '%s'\n", progname, opt, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (*cap == 0) {
+		*keys = NULL;
+		*values = NULL;
+		*size = 0;
+	}
+	if (*size == *cap) {
+		size_t new_cap = *cap ? *cap * 2 : 8;
+		char **grown_keys = realloc(*keys, new_cap * sizeof(char *));
+		char **grown_values;
+		if (!grown_keys) {
+			perror("realloc");
+			exit(1);
+		}
+		*keys = grown_keys;
+		grown_values = realloc(*values, new_cap * sizeof(char *));
+		if (!grown_values) {
+			perror("realloc");
+			exit(1);
+		}
+		*values = grown_values;
+		*cap = new_cap;
+	}
+	*eq = '\0';
+	(*keys)[*size] = arg;
+	(*values)[*size] = eq + 1;
+	(*size)++;
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] defs__keys -D, --define.
+ * @param[out] defs__values Values paired with defs__keys.
+ * @param[out] defs__size Number of pairs.
+ * @param[out] a --aa. Set when given.
+ * @param[out] b --bb. Set when given.
+ * @param[out] v -v, --verbose. Counts the times it is given.
+ * @param[out] input N. Required.
+ */
+void parse_args(int argc, char **argv, char* **defs__keys, char* **defs__values, size_t *defs__size, int *a, int *b, int *v, int *input) {
+	const char *progname = "demo";
+	bool defs__isset = false;
+	size_t defs__cap = 0;
+	bool a__isset = false;
+	bool b__isset = false;
+	bool v__isset = false;
+	static struct option longopts[] = {
+		{"define", required_argument, 0, 68},
+		{"aa", no_argument, 0, 256},
+		{"bb", no_argument, 0, 257},
+		{"verbose", no_argument, 0, 118},
+		{"help", 0, 0, 'h'},
+		{"version", 0, 0, 'V'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	int at;
+	int longindex;
+	expand_response_files(progname, &argc, &argv);
+	opterr = 0;
+	for (;;) {
+		if (optind < argc && is_negative_number(argv[optind])) {
+			argv[++npos] = argv[optind++];
+			continue;
+		}
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:D:vhV", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(1);
+		}
+		switch (ch) {
+		case 68:
+			map_push(progname, "define", optarg, defs__keys, defs__values, defs__size, &defs__cap);
+			defs__isset = true;
+			break;
+		case 256:
+			*a = 1;
+			a__isset = true;
+			break;
+		case 257:
+			*b = 1;
+			b__isset = true;
+			break;
+		case 118:
+			*v = v__isset ? *v + 1 : 1;
+			v__isset = true;
+			break;
+		case 'V':
+			printf("%s 1.0\n", progname);
+			exit(0);
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(1);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(1);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(1);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+	if (!defs__isset) {
+		*defs__keys = NULL;
+		*defs__values = NULL;
+		*defs__size = 0;
+	}
+	if (!a__isset) {
+		*a = 0;
+	}
+	if (!b__isset) {
+		*b = 0;
+	}
+	if (!v__isset) {
+		*v = 0;
+	}
+	if (a__isset + v__isset > 1) {
+		fprintf(stderr, "%s: mutually exclusive options given:", progname);
+		if (a__isset) {
+			fprintf(stderr, " --aa");
+		}
+		if (v__isset) {
+			fprintf(stderr, " --verbose");
+		}
+		fprintf(stderr, "\n");
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (a__isset && !b__isset) {
+		fprintf(stderr, "%s: option --aa requires --bb\n", progname);
+		usage(stderr, progname);
+		exit(1);
+	}
+
+	if (argc-optind < 1) {
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (argc-optind > 1) {
+		fprintf(stderr, "%s: unexpected argument: %s\n", progname, argv[optind+1]);
+		usage(stderr, progname);
+		exit(1);
+	}
+	argv += optind;
+	argc -= optind;
+
+	*input = parse_int(progname, "N", argv[0]);
+	argv++;
+	argc--;
+
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	int a;
+	int b;
+	int v;
+	int input;
+
+	parse_args(argc, argv, &defs__keys, &defs__values, &defs__size, &a, &b, &v, &input);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	return 0;
+	/* ARGEN:END */
+}
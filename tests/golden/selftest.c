@@ -0,0 +1,591 @@
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<getopt.h>
+#include<errno.h>
+#include<limits.h>
+#include<stdbool.h>
+#include<unistd.h>
+#include<sys/wait.h>
+
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options] FILE [REST...]\n%s", progname,
+	             "  FILE\n"
+	             "  REST\n"
+	             "  -h, --help         print this usage and exit\n"
+	             "      --level <NUM>\n"
+	             "  -j, --jobs <NUM>\n"
+	             "      --name <STR>\n"
+	             "      --force\n"
+	             "  -v, --verbose\n"
+	             "  -D, --define <NAME=VALUE>\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[8];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"level",
+		"jobs",
+		"name",
+		"force",
+		"verbose",
+		"define",
+		"help",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static int is_digit(char c) {
+	return c >= '0' && c <= '9';
+}
+
+static int is_negative_number(const char *arg) {
+	if (arg[0] != '-' || !is_digit(arg[1])) {
+		return 0;
+	}
+	arg++;
+	while (is_digit(*arg)) {
+		arg++;
+	}
+	if (arg[0] == '.' && is_digit(arg[1])) {
+		arg++;
+		while (is_digit(*arg)) {
+			arg++;
+		}
+	}
+	return *arg == '\0';
+}
+
+static int parse_int(const char *progname, const char *name, const char *arg) {
+	char *end;
+	long value;
+	errno = 0;
+	value = strtol(arg, &end, 0);
+	if (end == arg || *end != '\0') {
+		fprintf(stderr, "%s: invalid value for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(2);
+	}
+	if (errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+		fprintf(stderr, "%s: value out of range for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(2);
+	}
+	return (int)value;
+}
+
+static void map_push(const char *progname, const char *opt, char *arg,
+	             char ***keys, char ***values, size_t *size, size_t *cap) {
+	char *eq = strchr(arg, '=');
+	if (!eq) {
+		fprintf(stderr, "%s: expected NAME=VALUE for --%s, got '%s'\n", progname, opt, arg);
+		usage(stderr, progname);
+		exit(2);
+	}
+	if (*cap == 0) {
+		*keys = NULL;
+		*values = NULL;
+		*size = 0;
+	}
+	if (*size == *cap) {
+		size_t new_cap = *cap ? *cap * 2 : 8;
+		char **grown_keys = realloc(*keys, new_cap * sizeof(char *));
+		char **grown_values;
+		if (!grown_keys) {
+			perror("realloc");
+			exit(1);
+		}
+		*keys = grown_keys;
+		grown_values = realloc(*values, new_cap * sizeof(char *));
+		if (!grown_values) {
+			perror("realloc");
+			exit(1);
+		}
+		*values = grown_values;
+		*cap = new_cap;
+	}
+	*eq = '\0';
+	(*keys)[*size] = arg;
+	(*values)[*size] = eq + 1;
+	(*size)++;
+}
+
+static int argen_selftest(void);
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] level --level. Required.
+ * @param[out] jobs -j, --jobs. Defaults to 4.
+ * @param[out] name --name. Defaults to "anon".
+ * @param[out] force --force. Set when given.
+ * @param[out] verbose -v, --verbose. Counts the times it is given.
+ * @param[out] defs__keys -D, --define.
+ * @param[out] defs__values Values paired with defs__keys.
+ * @param[out] defs__size Number of pairs.
+ * @param[out] input FILE. Required.
+ * @param[out] rest REST...
+ * @param[out] rest__size Number of values in rest.
+ */
+void parse_args(int argc, char **argv, int *level, int *jobs, char* *name, int *force, int *verbose, char* **defs__keys, char* **defs__values, size_t *defs__size, char* *input, char* **rest, size_t *rest__size) {
+	const char *progname = "demo";
+	bool level__isset = false;
+	bool jobs__isset = false;
+	bool name__isset = false;
+	bool force__isset = false;
+	bool verbose__isset = false;
+	bool defs__isset = false;
+	size_t defs__cap = 0;
+	static int jobs__default = 4;
+	static char* name__default = "anon";
+	static struct option longopts[] = {
+		{"level", required_argument, 0, 256},
+		{"jobs", required_argument, 0, 106},
+		{"name", required_argument, 0, 257},
+		{"force", no_argument, 0, 258},
+		{"verbose", no_argument, 0, 118},
+		{"define", required_argument, 0, 68},
+		{"help", 0, 0, 'h'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	int at;
+	int longindex;
+	*rest = NULL;
+	*rest__size = 0;
+	if (argc == 2 && strcmp(argv[1], "--argen-selftest") == 0) {
+		exit(argen_selftest());
+	}
+	opterr = 0;
+	for (;;) {
+		if (optind < argc && is_negative_number(argv[optind])) {
+			argv[++npos] = argv[optind++];
+			continue;
+		}
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:j:vD:h", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(2);
+		}
+		switch (ch) {
+		case 256:
+			*level = parse_int(progname, "--level", optarg);
+			level__isset = true;
+			break;
+		case 106:
+			*jobs = parse_int(progname, "--jobs", optarg);
+			jobs__isset = true;
+			break;
+		case 257:
+			*name = optarg;
+			name__isset = true;
+			break;
+		case 258:
+			*force = 1;
+			force__isset = true;
+			break;
+		case 118:
+			*verbose = verbose__isset ? *verbose + 1 : 1;
+			verbose__isset = true;
+			break;
+		case 68:
+			map_push(progname, "define", optarg, defs__keys, defs__values, defs__size, &defs__cap);
+			defs__isset = true;
+			break;
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(2);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(2);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(2);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+	if (!level__isset) {
+		usage(stderr, progname);
+		exit(2);
+	}
+	if (!jobs__isset) {
+		*jobs = jobs__default;
+	}
+	if (!name__isset) {
+		*name = name__default;
+	}
+	if (!force__isset) {
+		*force = 0;
+	}
+	if (!verbose__isset) {
+		*verbose = 0;
+	}
+	if (!defs__isset) {
+		*defs__keys = NULL;
+		*defs__values = NULL;
+		*defs__size = 0;
+	}
+
+	if (argc-optind < 1) {
+		usage(stderr, progname);
+		exit(2);
+	}
+	argv += optind;
+	argc -= optind;
+
+	*input = argv[0];
+	argv++;
+	argc--;
+
+	if (argc > 0) {
+		*rest = argv;
+		*rest__size = argc;
+	}
+}
+
+static char **selftest_argv(const char *const *args, int argc) {
+	char **argv = malloc((argc + 1) * sizeof(char *));
+	if (!argv) {
+		exit(1);
+	}
+	for (int i = 0; i < argc; i++) {
+		argv[i] = malloc(strlen(args[i]) + 1);
+		if (!argv[i]) {
+			exit(1);
+		}
+		strcpy(argv[i], args[i]);
+	}
+	argv[argc] = NULL;
+	return argv;
+}
+
+static const char *const argen_selftest_0_args[] = {"demo", "--level", "42", "value"};
+
+static int argen_selftest_0(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(4, selftest_argv(argen_selftest_0_args, 4), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return level != (int)42
+		|| jobs != (int)4
+		|| name == NULL || strcmp(name, "anon") != 0
+		|| force != 0
+		|| verbose != 0
+		|| defs__size != 0
+		|| input == NULL || strcmp(input, "value") != 0
+		|| rest__size != 0;
+}
+
+static const char *const argen_selftest_1_args[] = {"demo", "value"};
+
+static int argen_selftest_1(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(2, selftest_argv(argen_selftest_1_args, 2), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return 0;
+}
+
+static const char *const argen_selftest_2_args[] = {"demo", "--level", "42"};
+
+static int argen_selftest_2(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(3, selftest_argv(argen_selftest_2_args, 3), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return 0;
+}
+
+static const char *const argen_selftest_3_args[] = {"demo", "--level", "42", "--jobs", "42", "value"};
+
+static int argen_selftest_3(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(6, selftest_argv(argen_selftest_3_args, 6), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return jobs != (int)42;
+}
+
+static const char *const argen_selftest_4_args[] = {"demo", "--level", "42", "--jobs", "x", "value"};
+
+static int argen_selftest_4(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(6, selftest_argv(argen_selftest_4_args, 6), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return 0;
+}
+
+static const char *const argen_selftest_5_args[] = {"demo", "--level", "42", "--name", "value", "value"};
+
+static int argen_selftest_5(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(6, selftest_argv(argen_selftest_5_args, 6), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return name == NULL || strcmp(name, "value") != 0;
+}
+
+static const char *const argen_selftest_6_args[] = {"demo", "--level", "42", "--force", "value"};
+
+static int argen_selftest_6(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(5, selftest_argv(argen_selftest_6_args, 5), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return force != 1;
+}
+
+static const char *const argen_selftest_7_args[] = {"demo", "--level", "42", "--verbose", "value"};
+
+static int argen_selftest_7(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(5, selftest_argv(argen_selftest_7_args, 5), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return verbose != 1;
+}
+
+static const char *const argen_selftest_8_args[] = {"demo", "--level", "42", "--define", "name=value", "value"};
+
+static int argen_selftest_8(void) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(6, selftest_argv(argen_selftest_8_args, 6), &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+	return defs__size != 1 || strcmp(defs__keys[0], "name") != 0 || strcmp(defs__values[0], "value") != 0;
+}
+
+static int argen_selftest(void) {
+	static const struct {
+		const char *what;
+		int (*run)(void);
+		int status;
+	} cases[] = {
+		{"defaults are applied", argen_selftest_0, 0},
+		{"--level is required", argen_selftest_1, 2},
+		{"FILE is required", argen_selftest_2, 2},
+		{"--jobs is read", argen_selftest_3, 0},
+		{"--jobs rejects a value that is not a number", argen_selftest_4, 2},
+		{"--name is read", argen_selftest_5, 0},
+		{"--force is read", argen_selftest_6, 0},
+		{"--verbose is read", argen_selftest_7, 0},
+		{"--define is read", argen_selftest_8, 0},
+	};
+	int failed = 0;
+	printf("1..%d\n", (int)(sizeof(cases) / sizeof(cases[0])));
+	for (size_t i = 0; i < sizeof(cases) / sizeof(cases[0]); i++) {
+		int status;
+		pid_t pid;
+		fflush(stdout);
+		pid = fork();
+		if (pid == 0) {
+			if (!freopen("/dev/null", "w", stdout) || !freopen("/dev/null", "w", stderr)) {
+				exit(1);
+			}
+			exit(cases[i].run() ? 1 : 0);
+		}
+		if (pid < 0 || waitpid(pid, &status, 0) < 0 || !WIFEXITED(status) || WEXITSTATUS(status) != cases[i].status) {
+			printf("not ok %d - %s\n", (int)i + 1, cases[i].what);
+			failed++;
+		} else {
+			printf("ok %d - %s\n", (int)i + 1, cases[i].what);
+		}
+	}
+	return failed > 0;
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	int level;
+	int jobs;
+	char* name;
+	int force;
+	int verbose;
+	char* *defs__keys;
+	char* *defs__values;
+	size_t defs__size;
+	char* input;
+	char* *rest;
+	size_t rest__size;
+
+	parse_args(argc, argv, &level, &jobs, &name, &force, &verbose, &defs__keys, &defs__values, &defs__size, &input, &rest, &rest__size);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	return 0;
+	/* ARGEN:END */
+}
@@ -0,0 +1,363 @@
+#define _CRT_SECURE_NO_WARNINGS
+#include<stdlib.h>
+#include<stdio.h>
+#include<string.h>
+#include<errno.h>
+#include<limits.h>
+#include<stdbool.h>
+
+
+struct option {
+	const char *name;
+	int has_arg;
+	int *flag;
+	int val;
+};
+
+#define no_argument 0
+#define required_argument 1
+
+static char *optarg;
+static int optind = 1, opterr = 1, optopt;
+
+static int getopt_long_name(int argc, char **argv, const struct option *longopts, int *longindex) {
+	char *name = argv[optind++] + 2;
+	size_t len = strcspn(name, "=");
+	int found = -1, ambiguous = 0, i;
+	for (i = 0; longopts[i].name; i++) {
+		if (strncmp(longopts[i].name, name, len) != 0) {
+			continue;
+		}
+		if (strlen(longopts[i].name) == len) {
+			found = i;
+			ambiguous = 0;
+			break;
+		}
+		if (found < 0) {
+			found = i;
+		} else if (longopts[i].has_arg != longopts[found].has_arg || longopts[i].val != longopts[found].val) {
+			ambiguous = 1;
+		}
+	}
+	if (found < 0 || ambiguous) {
+		optopt = 0;
+		return '?';
+	}
+	if (longindex) {
+		*longindex = found;
+	}
+	if (name[len] == '=') {
+		if (longopts[found].has_arg == no_argument) {
+			optopt = longopts[found].val;
+			return '?';
+		}
+		optarg = name + len + 1;
+	} else if (longopts[found].has_arg == required_argument) {
+		if (optind >= argc) {
+			optopt = longopts[found].val;
+			return ':';
+		}
+		optarg = argv[optind++];
+	}
+	if (longopts[found].flag) {
+		*longopts[found].flag = longopts[found].val;
+		return 0;
+	}
+	return longopts[found].val;
+}
+
+static int getopt_long(int argc, char **argv, const char *optstring,
+	                       const struct option *longopts, int *longindex) {
+	static char *next = NULL;
+	const char *shorts = optstring + strspn(optstring, "+-:");
+	const char *spec;
+	int c;
+	optarg = NULL;
+	if (!next || !*next) {
+		next = NULL;
+		if (optind >= argc) {
+			return -1;
+		}
+		if (argv[optind][0] != '-' || argv[optind][1] == '\0') {
+			if (optstring[0] != '-') {
+				return -1;
+			}
+			optarg = argv[optind++];
+			return 1;
+		}
+		if (strcmp(argv[optind], "--") == 0) {
+			optind++;
+			return -1;
+		}
+		if (argv[optind][1] == '-') {
+			return getopt_long_name(argc, argv, longopts, longindex);
+		}
+		next = argv[optind] + 1;
+	}
+	c = (unsigned char)*next++;
+	spec = c == ':' ? NULL : strchr(shorts, c);
+	if (!*next) {
+		optind++;
+	}
+	if (!spec) {
+		optopt = c;
+		return '?';
+	}
+	if (spec[1] == ':') {
+		if (*next) {
+			optarg = next;
+			optind++;
+		} else if (optind < argc) {
+			optarg = argv[optind++];
+		} else {
+			optopt = c;
+			return ':';
+		}
+		next = NULL;
+	}
+	return c;
+}
+
+/**
+ * Prints the usage line and help for each argument.
+ *
+ * @param out Stream to print to, stdout for --help.
+ * @param progname Name of the program, shown in the usage line.
+ */
+static void usage(FILE *out, const char *progname) {
+	fprintf(out, "usage: %s [options] [FILE...]\n%s", progname,
+	             "  FILE\n"
+	             "  -h, --help         print this usage and exit\n"
+	             "  -l, --level <NUM>\n"
+	             "  -v, --verbose\n"
+	             "  -n, --name <STR>\n"
+	             );
+}
+
+static size_t edit_distance(const char *typed, size_t typed_len, const char *known) {
+	size_t row[8];
+	size_t known_len = strlen(known);
+	for (size_t j = 0; j <= known_len; j++) {
+		row[j] = j;
+	}
+	for (size_t i = 1; i <= typed_len; i++) {
+		size_t diag = row[0];
+		row[0] = i;
+		for (size_t j = 1; j <= known_len; j++) {
+			size_t up = row[j];
+			size_t best = diag + (typed[i-1] != known[j-1]);
+			if (up + 1 < best) {
+				best = up + 1;
+			}
+			if (row[j-1] + 1 < best) {
+				best = row[j-1] + 1;
+			}
+			row[j] = best;
+			diag = up;
+		}
+	}
+	return row[known_len];
+}
+
+static void unknown_option(const char *progname, const struct option *longopts, int opt, const char *arg) {
+	static const char *names[] = {
+		"level",
+		"verbose",
+		"name",
+		"help",
+	};
+	const char *typed = arg + strspn(arg, "-");
+	size_t typed_len = strcspn(typed, "=");
+	const char *best = NULL;
+	size_t best_dist = 0;
+	if (opt && strncmp(arg, "--", 2) == 0 && typed[typed_len] == '=') {
+		for (size_t i = 0; longopts[i].name; i++) {
+			if (longopts[i].val == opt && !longopts[i].has_arg && strncmp(longopts[i].name, typed, typed_len) == 0) {
+				fprintf(stderr, "%s: option does not take an argument: --%s\n", progname, longopts[i].name);
+				return;
+			}
+		}
+	}
+	if (opt > 0 && opt < 128) {
+		fprintf(stderr, "%s: unknown option: -%c\n", progname, opt);
+		return;
+	}
+	if (opt) {
+		fprintf(stderr, "%s: unknown option: -\\%03o\n", progname, (unsigned char)opt);
+		return;
+	}
+	fprintf(stderr, "%s: unknown option: --%.*s\n", progname, (int)typed_len, typed);
+	for (size_t i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		size_t dist = edit_distance(typed, typed_len, names[i]);
+		if (2 * dist <= strlen(names[i]) && (!best || dist < best_dist)) {
+			best = names[i];
+			best_dist = dist;
+		}
+	}
+	if (best) {
+		fprintf(stderr, "%s: did you mean --%s?\n", progname, best);
+	}
+}
+
+static int is_digit(char c) {
+	return c >= '0' && c <= '9';
+}
+
+static int is_negative_number(const char *arg) {
+	if (arg[0] != '-' || !is_digit(arg[1])) {
+		return 0;
+	}
+	arg++;
+	while (is_digit(*arg)) {
+		arg++;
+	}
+	if (arg[0] == '.' && is_digit(arg[1])) {
+		arg++;
+		while (is_digit(*arg)) {
+			arg++;
+		}
+	}
+	return *arg == '\0';
+}
+
+static int parse_int(const char *progname, const char *name, const char *arg) {
+	char *end;
+	long value;
+	errno = 0;
+	value = strtol(arg, &end, 0);
+	if (end == arg || *end != '\0') {
+		fprintf(stderr, "%s: invalid value for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	if (errno == ERANGE || value < INT_MIN || value > INT_MAX) {
+		fprintf(stderr, "%s: value out of range for %s: '%s'\n", progname, name, arg);
+		usage(stderr, progname);
+		exit(1);
+	}
+	return (int)value;
+}
+
+/**
+ * Parses the command line into the variables pointed to, printing a message and
+ * exiting if it does not match the spec.
+ *
+ * @param argc Number of arguments, as main was given it.
+ * @param argv The arguments, as main was given them.
+ * @param[out] level -l, --level. Defaults to 1.
+ * @param[out] verbose -v, --verbose. Set when given.
+ * @param[out] name -n, --name.
+ * @param[out] files FILE...
+ * @param[out] files__size Number of values in files.
+ */
+void parse_args(int argc, char **argv, int *level, int *verbose, char* *name, char* **files, size_t *files__size) {
+	const char *progname = argv[0];
+	bool level__isset = false;
+	bool verbose__isset = false;
+	static int level__default = 1;
+	static struct option longopts[] = {
+		{"level", required_argument, 0, 108},
+		{"verbose", no_argument, 0, 118},
+		{"name", required_argument, 0, 110},
+		{"help", 0, 0, 'h'},
+		{0, 0, 0, 0}
+	};
+	int npos = 0;
+	int ch;
+	int at;
+	int longindex;
+	*name = NULL;
+	*files = NULL;
+	*files__size = 0;
+	opterr = 0;
+	for (;;) {
+		if (optind < argc && is_negative_number(argv[optind])) {
+			argv[++npos] = argv[optind++];
+			continue;
+		}
+		at = optind;
+		longindex = -1;
+		ch = getopt_long(argc, argv, "-:l:vn:h", longopts, &longindex);
+		if (ch == -1) {
+			break;
+		}
+		if (longindex >= 0 && strcspn(argv[at] + 2, "=") != strlen(longopts[longindex].name)) {
+			unknown_option(progname, longopts, 0, argv[at]);
+			usage(stderr, progname);
+			exit(1);
+		}
+		switch (ch) {
+		case 108:
+			*level = parse_int(progname, "--level", optarg);
+			level__isset = true;
+			break;
+		case 118:
+			*verbose = 1;
+			verbose__isset = true;
+			break;
+		case 110:
+			*name = optarg;
+			break;
+		case 1:
+			argv[++npos] = optarg;
+			break;
+		case ':':
+			if (strncmp(argv[optind-1], "--", 2) == 0) {
+				fprintf(stderr, "%s: option requires an argument: %s\n", progname, argv[optind-1]);
+			} else {
+				fprintf(stderr, "%s: option requires an argument: -%c\n", progname, optopt);
+			}
+			usage(stderr, progname);
+			exit(1);
+		case '?':
+			unknown_option(progname, longopts, optopt, argv[optind-1]);
+			usage(stderr, progname);
+			exit(1);
+		case 0:
+			break;
+		case 'h':
+			usage(stdout, progname);
+			exit(0);
+		default:
+			usage(stderr, progname);
+			exit(1);
+		}
+	}
+	while (optind < argc) {
+		argv[++npos] = argv[optind++];
+	}
+	argc = npos + 1;
+	optind = 1;
+	if (!level__isset) {
+		*level = level__default;
+	}
+	if (!verbose__isset) {
+		*verbose = 0;
+	}
+
+	argv += optind;
+	argc -= optind;
+
+	if (argc > 0) {
+		*files = argv;
+		*files__size = argc;
+	}
+}
+
+/* ARGEN:BEGIN user-helpers */
+/* ARGEN:END */
+
+int main(int argc, char **argv) {
+	int level;
+	int verbose;
+	char* name;
+	char* *files;
+	size_t files__size;
+
+	parse_args(argc, argv, &level, &verbose, &name, &files, &files__size);
+
+	/* ARGEN:BEGIN user-code */
+	/* call your code here */
+	return 0;
+	/* ARGEN:END */
+}
@@ -0,0 +1,176 @@
+// Argen
+// Copyright (C) 2017 Matt Lee <matt@kynelee.com>, Lucas Morales <lucas@lucasem.com>
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compiles generated parsers and checks what they make of command lines.
+//!
+//! For each tests/run/NAME.cases there is a spec tests/specs/NAME.toml and a file tests/run/NAME.c
+//! holding the user code regions to build it with, which print what was parsed. A cases file is
+//! made of blocks separated by blank lines. Each block starts with `$ ` and the arguments, split
+//! on whitespace except within single quotes. The lines that follow are what the program must
+//! print, and a line `? N` says it must exit with status N rather than 0. A line `! TEXT` says
+//! one of the lines it prints to stderr must end in TEXT, which leaves out the program's path.
+//! Output is only compared for blocks that list some, and a line of just `.` stands for a blank
+//! one. Programs run in tests/run, so response files can live there too.
+
+mod common;
+
+use argen::{preserve_regions, Language};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+struct Case {
+    args: Vec<String>,
+    stdout: Option<String>,
+    stderr: Vec<String>,
+    status: i32,
+}
+
+/// Splits a command line on whitespace, keeping single-quoted words together.
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quoted = false;
+    for ch in line.chars() {
+        match ch {
+            '\'' => {
+                quoted = !quoted;
+                arg.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => args.extend(arg.take()),
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    args
+}
+
+fn parse_cases(path: &Path) -> Vec<Case> {
+    let text = fs::read_to_string(path).unwrap();
+    let mut cases = Vec::new();
+    for block in text.split("\n\n").map(str::trim).filter(|b| !b.is_empty()) {
+        let mut lines = block.lines().filter(|l| !l.starts_with('#'));
+        let command = lines.next().unwrap();
+        let args = command
+            .strip_prefix("$ ")
+            .or_else(|| command.strip_prefix('$'))
+            .unwrap_or_else(|| panic!("{}: block must start with $: {}", path.display(), command));
+        let mut case = Case {
+            args: split_args(args),
+            stdout: None,
+            stderr: Vec::new(),
+            status: 0,
+        };
+        for line in lines {
+            if let Some(stderr) = line.strip_prefix("! ") {
+                case.stderr.push(stderr.to_owned());
+                continue;
+            }
+            match line.strip_prefix("? ") {
+                Some(status) => case.status = status.parse().unwrap(),
+                None => {
+                    let stdout = case.stdout.get_or_insert_with(String::new);
+                    if line != "." {
+                        stdout.push_str(line);
+                    }
+                    stdout.push('\n');
+                }
+            }
+        }
+        cases.push(case);
+    }
+    cases
+}
+
+/// Builds the program for a cases file, returning where it is.
+fn build(cases: &Path, cc: &str, dir: &Path) -> PathBuf {
+    let name = common::stem(cases);
+    let spec = common::read_spec(&Path::new("tests/specs").join(format!("{}.toml", name)));
+    let user_code = fs::read_to_string(cases.with_extension("c")).unwrap();
+    let code = preserve_regions(&spec.gen(Language::C), &user_code)
+        .unwrap_or_else(|e| panic!("{}: {}", name, e));
+    let source = dir.join(format!("{}.c", name));
+    let program = dir.join(name);
+    fs::write(&source, code).unwrap();
+    let out = Command::new(cc)
+        .arg("-o")
+        .arg(&program)
+        .arg(&source)
+        .output()
+        .unwrap();
+    assert!(
+        out.status.success(),
+        "{} does not compile:\n{}",
+        name,
+        String::from_utf8_lossy(&out.stderr)
+    );
+    program
+}
+
+#[test]
+fn generated_parsers_parse() {
+    let cc = match common::compilers().first() {
+        Some(cc) => *cc,
+        None => {
+            eprintln!("neither gcc nor clang is installed, skipping");
+            return;
+        }
+    };
+    let dir = common::scratch_dir("run");
+    let mut cases_files: Vec<PathBuf> = fs::read_dir("tests/run")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cases"))
+        .collect();
+    cases_files.sort();
+    let mut failures = Vec::new();
+    for cases in &cases_files {
+        let program = build(cases, cc, &dir);
+        for case in parse_cases(cases) {
+            let out = Command::new(&program)
+                .args(&case.args)
+                .current_dir("tests/run")
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8_lossy(&out.stdout);
+            let status = out.status.code().unwrap_or(-1);
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            let stdout_ok = case.stdout.as_ref().is_none_or(|s| *s == stdout);
+            let stderr_ok = case
+                .stderr
+                .iter()
+                .all(|l| stderr.lines().any(|got| got.ends_with(l.as_str())));
+            if status != case.status || !stdout_ok || !stderr_ok {
+                failures.push(format!(
+                    "{} $ {}\nexpected status {} and output:\n{}{}got status {} and output:\n{}{}",
+                    common::stem(cases),
+                    case.args.join(" "),
+                    case.status,
+                    case.stdout.as_deref().unwrap_or("(any)\n"),
+                    case.stderr
+                        .iter()
+                        .map(|l| format!("! {}\n", l))
+                        .collect::<String>(),
+                    status,
+                    stdout,
+                    stderr
+                ));
+            }
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("verbose=%d verbatim=%d name=%s\n", verbose, verbatim, name ? name : "(null)");
+	return 0;
+/* ARGEN:END */
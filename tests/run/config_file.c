@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("host=%s port=%d verbose=%d\n", host, port, verbose);
+	return 0;
+/* ARGEN:END */
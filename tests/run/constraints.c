@@ -0,0 +1,5 @@
+/* ARGEN:BEGIN user-code */
+	printf("target=%s retries=%d user=%s json=%d xml=%d port=%d host=%s\n",
+	       target, retries, user, json, xml, port, host ? host : "(null)");
+	return 0;
+/* ARGEN:END */
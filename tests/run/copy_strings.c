@@ -0,0 +1,17 @@
+/* ARGEN:BEGIN user-code */
+	size_t i;
+	/* the values are copies, so they outlive whatever happens to argv */
+	for (i = 1; i < (size_t)argc; i++) {
+		memset(argv[i], '#', strlen(argv[i]));
+	}
+	printf("input=%s name=%s", input, name);
+	for (i = 0; i < rest__size; i++) {
+		printf(" rest=%s", rest[i]);
+	}
+	for (i = 0; i < defs__size; i++) {
+		printf(" %s=%s", defs__keys[i], defs__values[i]);
+	}
+	printf("\n");
+	free_args(name, defs__keys, defs__values, defs__size, input, rest, rest__size);
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,32 @@
+/* ARGEN:BEGIN user-helpers */
+int parse_ipaddr(const char *value, struct in_addr *out, char *errbuf, size_t errlen) {
+	if (inet_pton(AF_INET, value, out) != 1) {
+		snprintf(errbuf, errlen, "'%s' is not an IPv4 address", value);
+		return 1;
+	}
+	return 0;
+}
+
+int parse_ratio(const char *value, double *out, char *errbuf, size_t errlen) {
+	char *end;
+	(void)errbuf;
+	(void)errlen;
+	*out = strtod(value, &end);
+	return end == value || *end != '\0';
+}
+
+int validate_ratio(const char *value, char *errbuf, size_t errlen) {
+	if (value[0] == '-') {
+		snprintf(errbuf, errlen, "ratio cannot be negative");
+		return 1;
+	}
+	return 0;
+}
+/* ARGEN:END */
+
+/* ARGEN:BEGIN user-code */
+	char buf[INET_ADDRSTRLEN];
+	printf("addr=%s", inet_ntop(AF_INET, &addr, buf, sizeof(buf)));
+	printf(" mask=%s ratio=%.2f\n", inet_ntop(AF_INET, &mask, buf, sizeof(buf)), ratio);
+	return 0;
+/* ARGEN:END */
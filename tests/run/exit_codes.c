@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("level=%d\n", level);
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("input=%s output=%s color=%d jobs=%d\n", input, output ? output : "(null)", color, jobs);
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("color=%d colour=%d threads=%d jobs=%d\n", color, colour, threads, jobs);
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,8 @@
+/* ARGEN:BEGIN user-code */
+	size_t i;
+	printf("input=%d a=%d b=%d v=%d\n", input, a, b, v);
+	for (i = 0; i < defs__size; i++) {
+		printf("%s => %s\n", defs__keys[i], defs__values[i]);
+	}
+	return 0;
+/* ARGEN:END */
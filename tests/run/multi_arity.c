@@ -0,0 +1,9 @@
+/* ARGEN:BEGIN user-code */
+	size_t i;
+	printf("op=%s", op);
+	for (i = 0; i < operands__size; i++) {
+		printf(" %s", operands[i]);
+	}
+	printf("\n");
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("base=%d scale=%s offset=%d verbose=%d\n", base, scale, offset, verbose);
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,8 @@
+/* ARGEN:BEGIN user-code */
+	int i;
+	printf("count=%d rest=%d\n", count, (int)rest__size);
+	for (i = 0; i < (int)rest__size; i++) {
+		printf("%s\n", rest[i]);
+	}
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("name=%s n=%d\n", name ? name : "(null)", n);
+	return 0;
+/* ARGEN:END */
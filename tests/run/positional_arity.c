@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("input=%s output=%s\n", input, output);
+	return 0;
+/* ARGEN:END */
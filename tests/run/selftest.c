@@ -0,0 +1,5 @@
+/* ARGEN:BEGIN user-code */
+	printf("input=%s level=%d jobs=%d name=%s force=%d verbose=%d defs=%d rest=%d\n",
+	       input, level, jobs, name, force, verbose, (int)defs__size, (int)rest__size);
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,5 @@
+/* ARGEN:BEGIN user-code */
+	printf("input=%s verbose=%d config=%s log_level=%s dry_run=%d\n", input, verbose,
+	       config ? config : "(null)", log_level, dry_run);
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,7 @@
+/* ARGEN:BEGIN user-code */
+	size_t i;
+	for (i = 0; i < files__size; i++) {
+		printf("%s\n", files[i]);
+	}
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,4 @@
+/* ARGEN:BEGIN user-code */
+	printf("hello, %s\n", name);
+	return 0;
+/* ARGEN:END */
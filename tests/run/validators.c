@@ -0,0 +1,34 @@
+/* ARGEN:BEGIN user-helpers */
+int validate_file(const char *value, char *errbuf, size_t errlen) {
+	(void)errbuf;
+	(void)errlen;
+	return strchr(value, '/') != NULL;
+}
+
+int validate_port(const char *value, char *errbuf, size_t errlen) {
+	int port = atoi(value);
+	if (port < 1 || port > 65535) {
+		snprintf(errbuf, errlen, "port %s is not between 1 and 65535", value);
+		return 1;
+	}
+	return 0;
+}
+
+int validate_host(const char *value, char *errbuf, size_t errlen) {
+	if (*value == '\0') {
+		snprintf(errbuf, errlen, "host cannot be empty");
+		return 1;
+	}
+	return 0;
+}
+/* ARGEN:END */
+
+/* ARGEN:BEGIN user-code */
+	size_t i;
+	printf("port=%d host=%s", port, host ? host : "(null)");
+	for (i = 0; i < files__size; i++) {
+		printf(" %s", files[i]);
+	}
+	printf("\n");
+	return 0;
+/* ARGEN:END */
@@ -0,0 +1,8 @@
+/* ARGEN:BEGIN user-code */
+	printf("level=%d verbose=%d name=%s files=%d", level, verbose, name ? name : "(null)", (int)files__size);
+	for (size_t i = 0; i < files__size; i++) {
+		printf(" %s", files[i]);
+	}
+	printf("\n");
+	return 0;
+/* ARGEN:END */